@@ -1,7 +1,7 @@
 use sp_core::{Pair, Public, sr25519};
 use node_template_runtime::{
 	AccountId, AuraConfig, BalancesConfig, GenesisConfig, GrandpaConfig,
-	SudoConfig, SystemConfig, WASM_BINARY, Signature
+	SearchConfig, SudoConfig, SystemConfig, WASM_BINARY, Signature
 };
 use sp_consensus_aura::sr25519::{AuthorityId as AuraId};
 use sp_finality_grandpa::{AuthorityId as GrandpaId};
@@ -139,9 +139,33 @@ fn testnet_genesis(initial_authorities: Vec<(AuraId, GrandpaId)>,
 		sudo: Some(SudoConfig {
 			key: root_key,
 		}),
+		search: Some(SearchConfig {
+			services: search_services(),
+		}),
 	}
 }
 
+/// The search services the development and local testnets start with.
+fn search_services() -> Vec<(AccountId, Vec<u8>, Vec<u8>, Vec<Vec<u8>>, Option<Vec<u8>>)> {
+	let records: &[&[u8]] = &[b"Hello", b"World"];
+	vec![
+		(
+			get_account_id_from_seed::<sr25519::Public>("Alice"),
+			b"alice".to_vec(),
+			b"http://127.0.0.1:8000".to_vec(),
+			vec![b"news".to_vec(), b"web".to_vec()],
+			Some(pallet_search::merkle::root(records).to_vec()),
+		),
+		(
+			get_account_id_from_seed::<sr25519::Public>("Bob"),
+			b"bob".to_vec(),
+			b"http://127.0.0.1:8001".to_vec(),
+			vec![b"web".to_vec()],
+			None,
+		),
+	]
+}
+
 pub fn load_spec(id: &str) -> Result<Box<dyn sc_service::ChainSpec>, String> {
 	Ok(match Alternative::from(id) {
 		Some(spec) => Box::new(spec.load()?),
@@ -0,0 +1,48 @@
+//! A collection of node-specific RPC methods.
+//!
+//! The RPCs of `sc-rpc` make no assumptions about the pallets in the runtime. The ones here can:
+//! they expose the search pallet of the template runtime.
+
+use std::sync::Arc;
+
+use node_template_runtime::{opaque::Block, AccountId};
+use sp_api::ProvideRuntimeApi;
+use sp_blockchain::HeaderBackend;
+use sp_core::offchain::OffchainStorage;
+
+/// Full client dependencies.
+pub struct FullDeps<C, S> {
+	/// The client instance to use.
+	pub client: Arc<C>,
+	/// The offchain storage read by the offchain workers, if the backend has one.
+	pub offchain_storage: Option<S>,
+}
+
+/// Instantiate all full RPC extensions.
+pub fn create_full<C, M, S>(
+	deps: FullDeps<C, S>,
+) -> jsonrpc_core::IoHandler<M> where
+	C: ProvideRuntimeApi<Block> + HeaderBackend<Block> + Send + Sync + 'static,
+	C::Api: pallet_search_rpc::SearchRuntimeApi<Block, AccountId>,
+	M: jsonrpc_core::Metadata + Default,
+	S: OffchainStorage + 'static,
+{
+	use pallet_search_rpc::{Search, SearchApi, SearchOffchain, SearchOffchainApi};
+
+	let mut io = jsonrpc_core::IoHandler::default();
+	let FullDeps {
+		client,
+		offchain_storage,
+	} = deps;
+
+	io.extend_with(
+		SearchApi::to_delegate(Search::new(client))
+	);
+	if let Some(storage) = offchain_storage {
+		io.extend_with(
+			SearchOffchainApi::to_delegate(SearchOffchain::new(storage))
+		);
+	}
+
+	io
+}
@@ -62,6 +62,13 @@ macro_rules! new_full_start {
 				import_setup = Some((grandpa_block_import, grandpa_link));
 
 				Ok(import_queue)
+			})?
+			.with_rpc_extensions(|builder| -> Result<jsonrpc_core::IoHandler<sc_rpc::Metadata>, _> {
+				let deps = crate::rpc::FullDeps {
+					client: builder.client().clone(),
+					offchain_storage: sc_client_api::backend::Backend::offchain_storage(&**builder.backend()),
+				};
+				Ok(crate::rpc::create_full(deps))
 			})?;
 
 		(builder, import_setup, inherent_data_providers)
@@ -11,16 +11,19 @@ include!(concat!(env!("OUT_DIR"), "/wasm_binary.rs"));
 use sp_std::prelude::*;
 use sp_core::OpaqueMetadata;
 use sp_runtime::{
-	ApplyExtrinsicResult, generic, create_runtime_str, impl_opaque_keys, MultiSignature,
+	ApplyExtrinsicResult, generic, create_runtime_str, impl_opaque_keys, MultiSignature, ModuleId,
 	transaction_validity::{TransactionValidity, TransactionSource},
 };
 use sp_runtime::traits::{
-	BlakeTwo256, Block as BlockT, IdentityLookup, Verify, ConvertInto, IdentifyAccount
+	self, BlakeTwo256, Block as BlockT, IdentityLookup, Verify, ConvertInto, IdentifyAccount,
+	SaturatedConversion,
 };
 use sp_api::impl_runtime_apis;
 use sp_consensus_aura::sr25519::AuthorityId as AuraId;
 use grandpa::AuthorityList as GrandpaAuthorityList;
 use grandpa::fg_primitives;
+use search_rpc_runtime_api::{MerkleProof, SearchServiceHash, SearchServiceInfo, Tag, TagMatch};
+use system::offchain::TransactionSubmitter;
 use sp_version::RuntimeVersion;
 #[cfg(feature = "std")]
 use sp_version::NativeVersion;
@@ -32,7 +35,7 @@ pub use timestamp::Call as TimestampCall;
 pub use balances::Call as BalancesCall;
 pub use sp_runtime::{Permill, Perbill};
 pub use frame_support::{
-	StorageValue, construct_runtime, parameter_types,
+	StorageValue, construct_runtime, parameter_types, debug,
 	traits::Randomness,
 	weights::Weight,
 };
@@ -40,6 +43,9 @@ pub use frame_support::{
 /// Importing a template pallet
 pub use template;
 
+/// Importing the search pallet
+pub use search;
+
 /// An index to a block.
 pub type BlockNumber = u32;
 
@@ -226,6 +232,92 @@ impl template::Trait for Runtime {
 	type Event = Event;
 }
 
+/// A transaction submitter with the given key type.
+pub type TransactionSubmitterOf<KeyType> = TransactionSubmitter<KeyType, Runtime, UncheckedExtrinsic>;
+
+/// Submits transactions from offchain workers, signed with the keys of the node.
+impl system::offchain::CreateTransaction<Runtime, UncheckedExtrinsic> for Runtime {
+	type Public = <Signature as Verify>::Signer;
+	type Signature = Signature;
+
+	fn create_transaction<TSigner: system::offchain::Signer<Self::Public, Self::Signature>>(
+		call: Call,
+		public: Self::Public,
+		account: AccountId,
+		index: Index,
+	) -> Option<(Call, <UncheckedExtrinsic as traits::Extrinsic>::SignaturePayload)> {
+		// take the biggest period possible.
+		let period = BlockHashCount::get()
+			.checked_next_power_of_two()
+			.map(|c| c / 2)
+			.unwrap_or(2) as u64;
+		let current_block = System::block_number()
+			.saturated_into::<u64>()
+			// The `System::block_number` is initialized with `n+1`,
+			// so the actual block number is `n`.
+			.saturating_sub(1);
+		let extra: SignedExtra = (
+			system::CheckVersion::<Runtime>::new(),
+			system::CheckGenesis::<Runtime>::new(),
+			system::CheckEra::<Runtime>::from(generic::Era::mortal(period, current_block)),
+			system::CheckNonce::<Runtime>::from(index),
+			system::CheckWeight::<Runtime>::new(),
+			transaction_payment::ChargeTransactionPayment::<Runtime>::from(0),
+		);
+		let raw_payload = SignedPayload::new(call, extra)
+			.map_err(|e| {
+				debug::warn!("Unable to create signed payload: {:?}", e);
+			})
+			.ok()?;
+		let signature = TSigner::sign(public, &raw_payload)?;
+		let (call, extra, _) = raw_payload.deconstruct();
+		Some((call, (account, signature, extra)))
+	}
+}
+
+parameter_types! {
+	pub const ServiceDepositBase: Balance = 1_000_000;
+	pub const ServiceDepositPerByte: Balance = 10_000;
+	pub const SearchModuleId: ModuleId = ModuleId(*b"py/srchr");
+	pub const SearchEraDuration: BlockNumber = 1 * DAYS;
+	pub const RewardPerHeat: Balance = 10_000;
+	pub const SearchEraBudget: Balance = 1_000_000_000;
+	pub const HeatDecayPeriod: BlockNumber = 1 * DAYS;
+	pub const HeatDecay: Perbill = Perbill::from_percent(90);
+	pub const RankingSize: u32 = 100;
+	pub const RootHistoryDepth: u32 = 256;
+	pub const SearchChallengeBond: Balance = 10_000_000;
+	pub const SearchChallengePeriod: BlockNumber = 12 * HOURS;
+	pub const SearchDismissalSlash: Perbill = Perbill::from_percent(10);
+	pub const SearchUploadInterval: BlockNumber = 10;
+}
+
+impl search::Trait for Runtime {
+	type Event = Event;
+	type Call = Call;
+	type SubmitSignedTransaction = TransactionSubmitterOf<search::crypto::Public>;
+	type UploadInterval = SearchUploadInterval;
+	type Currency = Balances;
+	type ModuleId = SearchModuleId;
+	type EraDuration = SearchEraDuration;
+	type RewardPerHeat = RewardPerHeat;
+	type EraBudget = SearchEraBudget;
+	type HeatDecayPeriod = HeatDecayPeriod;
+	type HeatDecay = HeatDecay;
+	type RankingSize = RankingSize;
+	type RootHistoryDepth = RootHistoryDepth;
+	type ServiceDepositBase = ServiceDepositBase;
+	type ServiceDepositPerByte = ServiceDepositPerByte;
+	/// Slashed deposits are burnt.
+	type Slashed = ();
+	type ForceOrigin = system::EnsureRoot<AccountId>;
+	type ChallengeBond = SearchChallengeBond;
+	type ChallengePeriod = SearchChallengePeriod;
+	type DismissalSlash = SearchDismissalSlash;
+	type ResolveOrigin = system::EnsureRoot<AccountId>;
+	type UnixTime = Timestamp;
+}
+
 construct_runtime!(
 	pub enum Runtime where
 		Block = Block,
@@ -242,6 +334,7 @@ construct_runtime!(
 		Sudo: sudo::{Module, Call, Config<T>, Storage, Event<T>},
 		// Used for the module template in `./template.rs`
 		TemplateModule: template::{Module, Call, Storage, Event<T>},
+		Search: search::{Module, Call, Storage, Config<T>, Event<T>},
 	}
 );
 
@@ -266,6 +359,8 @@ pub type SignedExtra = (
 );
 /// Unchecked extrinsic type as expected by this runtime.
 pub type UncheckedExtrinsic = generic::UncheckedExtrinsic<Address, Call, Signature, SignedExtra>;
+/// The payload being signed in transactions.
+pub type SignedPayload = generic::SignedPayload<Call, SignedExtra>;
 /// Extrinsic type that has already been checked.
 pub type CheckedExtrinsic = generic::CheckedExtrinsic<AccountId, Call, SignedExtra>;
 /// Executive: handles dispatch to the various modules.
@@ -359,4 +454,36 @@ impl_runtime_apis! {
 			Grandpa::grandpa_authorities()
 		}
 	}
+
+	impl search_rpc_runtime_api::SearchApi<Block, AccountId> for Runtime {
+		fn recommend(tag: Option<Tag>, count: u32) -> Vec<SearchServiceInfo<AccountId>> {
+			Search::recommend(tag, count)
+		}
+
+		fn services_by_tags(
+			tags: Vec<Tag>,
+			matching: TagMatch,
+			start: u32,
+			count: u32,
+		) -> Vec<SearchServiceInfo<AccountId>> {
+			Search::services_by_tags(tags, matching, start, count)
+		}
+
+		fn service_by_name(name: Vec<u8>) -> Option<SearchServiceInfo<AccountId>> {
+			Search::service_by_name(name)
+		}
+
+		fn service_hash(name: Vec<u8>) -> Option<SearchServiceHash<AccountId>> {
+			Search::service_hash(name)
+		}
+
+		fn verify_searched_item(
+			name: Vec<u8>,
+			upload: Option<u32>,
+			record: Vec<u8>,
+			proof: MerkleProof,
+		) -> bool {
+			Search::verify_searched_item(name, upload, record, proof)
+		}
+	}
 }
@@ -17,12 +17,12 @@
 //! Substrate chain configurations.
 
 use sc_chain_spec::ChainSpecExtension;
-use sp_core::{Pair, Public, crypto::UncheckedInto, sr25519};
+use sp_core::{Pair, Public, U256, crypto::UncheckedInto, sr25519};
 use serde::{Serialize, Deserialize};
 use node_runtime::{
 	AuthorityDiscoveryConfig, BabeConfig, BalancesConfig, ContractsConfig, CouncilConfig, DemocracyConfig,
-	GrandpaConfig, ImOnlineConfig, SessionConfig, SessionKeys, StakerStatus, StakingConfig,
-	IndicesConfig, SocietyConfig, SudoConfig, SystemConfig, TechnicalCommitteeConfig, WASM_BINARY,
+	EVMConfig, EVM, GrandpaConfig, ImOnlineConfig, SessionConfig, SessionKeys, StakerStatus, StakingConfig,
+	IndicesConfig, SearchConfig, SocietyConfig, SudoConfig, SystemConfig, TechnicalCommitteeConfig, WASM_BINARY,
 };
 use node_runtime::Block;
 use node_runtime::constants::currency::*;
@@ -33,7 +33,8 @@ use grandpa_primitives::{AuthorityId as GrandpaId};
 use sp_consensus_babe::{AuthorityId as BabeId};
 use pallet_im_online::sr25519::{AuthorityId as ImOnlineId};
 use sp_authority_discovery::AuthorityId as AuthorityDiscoveryId;
-use sp_runtime::{Perbill, traits::{Verify, IdentifyAccount}};
+use sp_runtime::{Perbill, traits::{Verify, IdentifyAccount, BlakeTwo256}};
+use pallet_evm::{Account as EVMAccount, ConvertAccountId, HashTruncateConvertAccountId};
 
 pub use node_primitives::{AccountId, Balance, Signature};
 pub use node_runtime::GenesisConfig;
@@ -147,6 +148,7 @@ fn staging_testnet_config_genesis() -> GenesisConfig {
 		initial_authorities,
 		root_key,
 		Some(endowed_accounts),
+		vec![],
 		false,
 	)
 }
@@ -200,6 +202,30 @@ pub fn get_authority_keys_from_seed(seed: &str) -> (
 	)
 }
 
+/// A search service registered at genesis: provider, name, url, tags and first root hash.
+pub type GenesisSearchService = (AccountId, Vec<u8>, Vec<u8>, Vec<Vec<u8>>, Option<Vec<u8>>);
+
+/// Helper function to generate the search services of the development and local testnets
+pub fn testnet_search_services() -> Vec<GenesisSearchService> {
+	let records: &[&[u8]] = &[b"Hello", b"World"];
+	vec![
+		(
+			get_account_id_from_seed::<sr25519::Public>("Alice"),
+			b"alice".to_vec(),
+			b"http://127.0.0.1:8000".to_vec(),
+			vec![b"news".to_vec(), b"web".to_vec()],
+			Some(pallet_search::merkle::root(records).to_vec()),
+		),
+		(
+			get_account_id_from_seed::<sr25519::Public>("Bob"),
+			b"bob".to_vec(),
+			b"http://127.0.0.1:8001".to_vec(),
+			vec![b"web".to_vec()],
+			None,
+		),
+	]
+}
+
 /// Helper function to create GenesisConfig for testing
 pub fn testnet_genesis(
 	initial_authorities: Vec<(
@@ -212,6 +238,7 @@ pub fn testnet_genesis(
 	)>,
 	root_key: AccountId,
 	endowed_accounts: Option<Vec<AccountId>>,
+	search_services: Vec<GenesisSearchService>,
 	enable_println: bool,
 ) -> GenesisConfig {
 	let endowed_accounts: Vec<AccountId> = endowed_accounts.unwrap_or_else(|| {
@@ -234,6 +261,7 @@ pub fn testnet_genesis(
 
 	const ENDOWMENT: Balance = 10_000_000 * DOLLARS;
 	const STASH: Balance = 100 * DOLLARS;
+	const EVM_ENDOWMENT: Balance = 1_000 * DOLLARS;
 
 	GenesisConfig {
 		frame_system: Some(SystemConfig {
@@ -244,6 +272,8 @@ pub fn testnet_genesis(
 			balances: endowed_accounts.iter().cloned()
 				.map(|k| (k, ENDOWMENT))
 				.chain(initial_authorities.iter().map(|x| (x.0.clone(), STASH)))
+				// Back the balances of the EVM accounts below.
+				.chain(Some((EVM::account_id(), EVM_ENDOWMENT * num_endowed_accounts as Balance)))
 				.collect(),
 		}),
 		pallet_indices: Some(IndicesConfig {
@@ -317,6 +347,18 @@ pub fn testnet_genesis(
 			max_members: 999,
 		}),
 		pallet_vesting: Some(Default::default()),
+		pallet_search: Some(SearchConfig {
+			services: search_services,
+		}),
+		pallet_evm: Some(EVMConfig {
+			accounts: endowed_accounts.iter()
+				.map(|k| (
+					HashTruncateConvertAccountId::<BlakeTwo256>::convert_account_id(k),
+					EVMAccount { nonce: U256::zero(), balance: EVM_ENDOWMENT.into() },
+				))
+				.collect(),
+			min_gas_price: U256::one(),
+		}),
 	}
 }
 
@@ -327,6 +369,7 @@ fn development_config_genesis() -> GenesisConfig {
 		],
 		get_account_id_from_seed::<sr25519::Public>("Alice"),
 		None,
+		testnet_search_services(),
 		true,
 	)
 }
@@ -353,6 +396,7 @@ fn local_testnet_genesis() -> GenesisConfig {
 		],
 		get_account_id_from_seed::<sr25519::Public>("Alice"),
 		None,
+		testnet_search_services(),
 		false,
 	)
 }
@@ -385,6 +429,7 @@ pub(crate) mod tests {
 			],
 			get_account_id_from_seed::<sr25519::Public>("Alice"),
 			None,
+			vec![],
 			false,
 		)
 	}
@@ -422,7 +467,7 @@ pub(crate) mod tests {
 	fn test_connectivity() {
 		sc_service_test::connectivity(
 			integration_test_config_with_two_authorities(),
-			|config| new_full(config),
+			|config| new_full(config, false),
 			|config| new_light(config),
 		);
 	}
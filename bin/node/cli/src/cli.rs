@@ -26,6 +26,28 @@ pub struct Cli {
 	#[allow(missing_docs)]
 	#[structopt(flatten)]
 	pub run: RunCmd,
+	/// Seal blocks with manual seal instead of running BABE and GRANDPA, for development
+	/// chains and integration tests.
+	///
+	/// `instant` seals a block for every ready transaction, `manual` only when the
+	/// `engine_createBlock` RPC is called, and a number seals a block every that many
+	/// milliseconds.
+	#[structopt(long = "dev-seal", value_name = "MODE")]
+	pub dev_seal: Option<crate::DevSeal>,
+	/// Expose the `search_queueUpload` RPC, which queues uploads for the offchain worker to
+	/// submit on behalf of the search providers whose keys this node holds.
+	///
+	/// Anyone able to call it can queue uploads, so never combine it with a public RPC
+	/// interface.
+	#[structopt(long = "search-provider")]
+	pub search_provider: bool,
+	/// Expose the `engine_setStorage` RPC of a `--dev-seal` node, which seals blocks changing the
+	/// storage as it is told to. Ignored without `--dev-seal`.
+	///
+	/// The changes aren't the result of executing these blocks, so other nodes can't import them:
+	/// only use it for nodes running without peers.
+	#[structopt(long = "unsafe-set-storage")]
+	pub unsafe_set_storage: bool,
 }
 
 /// Possible subcommands of the main binary.
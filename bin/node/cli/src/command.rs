@@ -34,12 +34,27 @@ where
 		None => {
 			opt.run.init(&version)?;
 			opt.run.update_config(&mut config, load_spec, &version)?;
-			opt.run.run(
-				config,
-				service::new_light,
-				service::new_full,
-				&version,
-			)
+			let search_provider = opt.search_provider;
+			let unsafe_set_storage = opt.unsafe_set_storage;
+			match opt.dev_seal {
+				Some(dev_seal) => opt.run.run(
+					config,
+					service::new_light,
+					|config| service::new_dev(
+						config,
+						dev_seal,
+						search_provider,
+						unsafe_set_storage,
+					),
+					&version,
+				),
+				None => opt.run.run(
+					config,
+					service::new_light,
+					|config| service::new_full(config, search_provider),
+					&version,
+				),
+			}
 		},
 		Some(Subcommand::Inspect(cmd)) => {
 			cmd.init(&version)?;
@@ -47,6 +47,7 @@ pub use browser::*;
 pub use cli::*;
 #[cfg(feature = "cli")]
 pub use command::*;
+pub use service::DevSeal;
 
 /// The chain specification option.
 #[derive(Clone, Debug, PartialEq)]
@@ -39,12 +39,43 @@ use node_executor::NativeExecutor;
 use sc_network::NetworkService;
 use sc_offchain::OffchainWorkers;
 
+/// How a development node started with `--dev-seal` seals its blocks.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DevSeal {
+	/// A block for every transaction that becomes ready in the transaction pool.
+	Instant,
+	/// A block every given number of milliseconds, empty if there are no transactions.
+	Interval(u64),
+	/// A block only when requested with the `engine_createBlock` RPC.
+	Manual,
+}
+
+impl std::str::FromStr for DevSeal {
+	type Err = String;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		match s {
+			"instant" => Ok(DevSeal::Instant),
+			"manual" => Ok(DevSeal::Manual),
+			interval => match interval.parse() {
+				Ok(millis) if millis > 0 => Ok(DevSeal::Interval(millis)),
+				_ => Err(format!(
+					"Invalid seal mode `{}`, expected `instant`, `manual` or an interval in ms",
+					interval,
+				)),
+			},
+		}
+	}
+}
+
 /// Starts a `ServiceBuilder` for a full service.
 ///
 /// Use this macro if you don't actually need the full service, but just the builder in order to
 /// be able to perform chain operations.
+///
+/// The `search_queueUpload` RPC is only exposed if `$search_provider` is set.
 macro_rules! new_full_start {
-	($config:expr) => {{
+	($config:expr, $search_provider:expr) => {{
 		use std::sync::Arc;
 		type RpcExtension = jsonrpc_core::IoHandler<sc_rpc::Metadata>;
 		let mut import_setup = None;
@@ -96,16 +127,24 @@ macro_rules! new_full_start {
 					pool: builder.pool(),
 					select_chain: builder.select_chain().cloned()
 						.expect("SelectChain is present for full services or set up failed; qed."),
-					babe: node_rpc::BabeDeps {
+					babe: Some(node_rpc::BabeDeps {
 						keystore: builder.keystore(),
 						babe_config: sc_consensus_babe::BabeLink::config(babe_link).clone(),
 						shared_epoch_changes: sc_consensus_babe::BabeLink::epoch_changes(babe_link).clone()
-					}
+					}),
+					offchain_storage: if $search_provider {
+						sc_client_api::backend::Backend::offchain_storage(&**builder.backend())
+					} else {
+						None
+					},
 				};
 				Ok(node_rpc::create_full(deps))
 			})?;
 
 		(builder, import_setup, inherent_data_providers)
+	}};
+	($config:expr) => {{
+		new_full_start!($config, false)
 	}}
 }
 
@@ -114,7 +153,7 @@ macro_rules! new_full_start {
 /// We need to use a macro because the test suit doesn't work with an opaque service. It expects
 /// concrete types instead.
 macro_rules! new_full {
-	($config:expr, $with_startup_data: expr) => {{
+	($config:expr, $search_provider:expr, $with_startup_data: expr) => {{
 		use futures::prelude::*;
 		use sc_network::Event;
 		use sc_client_api::ExecutorProvider;
@@ -138,7 +177,8 @@ macro_rules! new_full {
 		// never actively participate in any consensus process.
 		let participates_in_consensus = is_authority && !$config.sentry_mode;
 
-		let (builder, mut import_setup, inherent_data_providers) = new_full_start!($config);
+		let (builder, mut import_setup, inherent_data_providers) =
+			new_full_start!($config, $search_provider);
 
 		let service = builder
 			.with_finality_proof_provider(|client, backend| {
@@ -252,7 +292,7 @@ macro_rules! new_full {
 		Ok((service, inherent_data_providers))
 	}};
 	($config:expr) => {{
-		new_full!($config, |_, _| {})
+		new_full!($config, false, |_, _| {})
 	}}
 }
 
@@ -270,8 +310,9 @@ type ConcreteTransactionPool = sc_transaction_pool::BasicPool<
 	ConcreteBlock
 >;
 
-/// Builds a new service for a full client.
-pub fn new_full(config: Configuration)
+/// Builds a new service for a full client, exposing the `search_queueUpload` RPC if
+/// `search_provider` is set.
+pub fn new_full(config: Configuration, search_provider: bool)
 -> Result<
 	Service<
 		ConcreteBlock,
@@ -289,7 +330,126 @@ pub fn new_full(config: Configuration)
 	ServiceError,
 >
 {
-	new_full!(config).map(|(service, _)| service)
+	new_full!(config, search_provider, |_, _| {}).map(|(service, _)| service)
+}
+
+/// Builds a new service for a full client that seals its own blocks with manual seal, instead
+/// of authoring them with BABE and finalizing them with GRANDPA.
+///
+/// Blocks can always be sealed and finalized with the `engine_*` RPCs, which can also seal
+/// several empty blocks at once and move the timestamps forward. Blocks are also sealed as
+/// `dev_seal` says. The `search_queueUpload` RPC is only exposed if `search_provider` is set,
+/// and `engine_setStorage`, which seals blocks other nodes can't import, only if
+/// `unsafe_set_storage` is set.
+pub fn new_dev(
+	config: Configuration,
+	dev_seal: DevSeal,
+	search_provider: bool,
+	unsafe_set_storage: bool,
+) -> Result<impl AbstractService, ServiceError> {
+	use codec::Decode;
+	use futures::prelude::*;
+	use frame_support::StorageValue;
+	use sc_client_api::StorageProvider;
+	use sc_consensus_manual_seal::{
+		self as manual_seal,
+		rpc::{ManualSeal, ManualSealApi, ManualSealUnsafeApi},
+	};
+	use sp_core::storage::StorageKey;
+	use sp_runtime::generic::BlockId;
+
+	type RpcExtension = jsonrpc_core::IoHandler<sc_rpc::Metadata>;
+	let (command_sink, rpc_commands) = futures::channel::mpsc::channel(1024);
+
+	let builder = ServiceBuilder::new_full::<Block, RuntimeApi, node_executor::Executor>(config)?
+		.with_select_chain(|_config, backend| {
+			Ok(LongestChain::new(backend.clone()))
+		})?
+		.with_transaction_pool(|config, client, _fetcher| {
+			let pool_api = sc_transaction_pool::FullChainApi::new(client.clone());
+			Ok(sc_transaction_pool::BasicPool::new(config, Arc::new(pool_api)))
+		})?
+		.with_import_queue(|_config, client, _select_chain, _transaction_pool| {
+			Ok(manual_seal::import_queue(Box::new(client)))
+		})?;
+
+	let backend = builder.backend().clone();
+
+	// after a restart, the timestamps carry on from the best block, which is ahead of the
+	// current time if the timestamps have been moved forward with `engine_advanceTime`.
+	let client = builder.client().clone();
+	let now_key = StorageKey(pallet_timestamp::Now::<node_runtime::Runtime>::hashed_key().to_vec());
+	let last_timestamp = client.storage(&BlockId::Hash(client.chain_info().best_hash), &now_key)?
+		.and_then(|now| u64::decode(&mut &now.0[..]).ok())
+		.unwrap_or_default();
+	let timestamp = manual_seal::TimestampInherentDataProvider::new(
+		node_runtime::MinimumPeriod::get(),
+	).with_last_timestamp(last_timestamp);
+
+	let inherent_data_providers = InherentDataProviders::new();
+	inherent_data_providers
+		.register_provider(timestamp.clone())
+		.map_err(sp_consensus::Error::InherentData)?;
+
+	let service = builder
+		.with_rpc_extensions(|builder| -> Result<RpcExtension, _> {
+			let deps = node_rpc::FullDeps {
+				client: builder.client().clone(),
+				pool: builder.pool(),
+				select_chain: builder.select_chain().cloned()
+					.expect("SelectChain is present for full services or set up failed; qed."),
+				babe: None,
+				offchain_storage: if search_provider {
+					sc_client_api::backend::Backend::offchain_storage(&**builder.backend())
+				} else {
+					None
+				},
+			};
+			let mut io = node_rpc::create_full(deps);
+			if unsafe_set_storage {
+				io.extend_with(ManualSealUnsafeApi::to_delegate(
+					ManualSeal::new(command_sink.clone()),
+				));
+			}
+			io.extend_with(ManualSealApi::to_delegate(
+				ManualSeal::new(command_sink).with_timestamp(timestamp),
+			));
+			Ok(io)
+		})?
+		.build()?;
+
+	let pool = service.transaction_pool().pool().clone();
+	let commands = match dev_seal {
+		DevSeal::Instant => stream::select(
+			rpc_commands,
+			manual_seal::instant_seal_commands(&pool),
+		).boxed(),
+		DevSeal::Interval(millis) => stream::select(
+			rpc_commands,
+			manual_seal::interval_seal_commands(std::time::Duration::from_millis(millis)),
+		).boxed(),
+		DevSeal::Manual => rpc_commands.boxed(),
+	};
+
+	let proposer = sc_basic_authorship::ProposerFactory::new(
+		service.client(),
+		service.transaction_pool()
+	);
+	let select_chain = service.select_chain()
+		.ok_or(ServiceError::SelectChainRequired)?;
+
+	let authorship = manual_seal::run_manual_seal(
+		Box::new(service.client()),
+		proposer,
+		backend,
+		pool,
+		commands,
+		select_chain,
+		inherent_data_providers,
+	);
+	service.spawn_essential_task("manual-seal", authorship);
+
+	Ok(service)
 }
 
 /// Builds a new service for a light client.
@@ -471,7 +631,7 @@ mod tests {
 		};
 		sc_service_test::sync(
 			sc_chain_spec::integration_test_config(),
-			|config| new_full(config),
+			|config| new_full(config, false),
 			|mut config| new_light(config),
 			block_factory,
 			extrinsic_factory,
@@ -503,7 +663,7 @@ mod tests {
 			chain_spec,
 			|config| {
 				let mut setup_handles = None;
-				new_full!(config, |
+				new_full!(config, false, |
 					block_import: &sc_consensus_babe::BabeBlockImport<Block, _, _>,
 					babe_link: &sc_consensus_babe::BabeLink<Block>,
 				| {
@@ -654,7 +814,7 @@ mod tests {
 	fn test_consensus() {
 		sc_service_test::consensus(
 			crate::chain_spec::tests::integration_test_config_with_two_authorities(),
-			|config| new_full(config),
+			|config| new_full(config, false),
 			|config| new_light(config),
 			vec![
 				"//Alice".into(),
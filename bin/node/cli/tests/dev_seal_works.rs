@@ -0,0 +1,56 @@
+// Copyright 2020 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+#![cfg(unix)]
+
+use assert_cmd::cargo::cargo_bin;
+use std::{convert::TryInto, fs, process::Command, thread, time::Duration};
+use nix::sys::signal::{kill, Signal::SIGINT};
+use nix::unistd::Pid;
+use tempfile::tempdir;
+
+mod common;
+
+#[test]
+fn dev_seal_works() {
+	let base_path = tempdir().expect("could not create a temp dir");
+	let exported_block = base_path.path().join("exported_block");
+
+	let mut cmd = Command::new(cargo_bin("substrate"))
+		.args(&["--dev", "--dev-seal", "500", "-d"])
+		.arg(base_path.path())
+		.spawn()
+		.unwrap();
+
+	// Let it seal a block every half second.
+	thread::sleep(Duration::from_secs(20));
+	assert!(cmd.try_wait().unwrap().is_none(), "the process should still be running");
+
+	kill(Pid::from_raw(cmd.id().try_into().unwrap()), SIGINT).unwrap();
+	assert!(common::wait_for(&mut cmd, 40).map(|x| x.success()).unwrap_or_default());
+
+	let status = Command::new(cargo_bin("substrate"))
+		.args(&["export-blocks", "--dev", "--pruning", "archive", "-d"])
+		.arg(base_path.path())
+		.args(&["--from", "10", "--to", "10"])
+		.arg(&exported_block)
+		.status()
+		.unwrap();
+	assert!(status.success());
+
+	let metadata = fs::metadata(&exported_block).unwrap();
+	assert!(metadata.len() > 0, "block 10 should have been sealed");
+}
@@ -0,0 +1,1025 @@
+// Copyright 2018-2020 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+use sp_core::{NeverNativeValue, H160, H256, U256};
+use sp_runtime::traits::{BlakeTwo256, Dispatchable};
+use sp_runtime::transaction_validity::{
+	InvalidTransaction, TransactionSource, TransactionValidityError,
+};
+use node_primitives::{AccountId, Balance};
+use frame_support::{traits::Currency, unsigned::ValidateUnsigned, weights::GetDispatchInfo};
+use node_runtime::{
+	CheckedExtrinsic, Call, EVM, Event, ExistentialDeposit, MaximumBlockWeight, Origin, Runtime,
+	System, Balances, Treasury, constants::currency::*,
+};
+use pallet_evm::{BloomInput, ConvertAccountId, HashTruncateConvertAccountId};
+use node_testing::keyring::*;
+
+pub mod common;
+use self::common::*;
+
+/// The chain id of the test externalities.
+const CHAIN_ID: u64 = 42;
+
+/// Init code returning `code` as the code of the contract.
+///
+/// `PUSH1 len DUP1 PUSH1 11 PUSH1 0 CODECOPY PUSH1 0 RETURN`, followed by `code`.
+fn init_code(code: &[u8]) -> Vec<u8> {
+	let deploy = [0x60, code.len() as u8, 0x80, 0x60, 0x0b, 0x60, 0x00, 0x39, 0x60, 0x00, 0xf3];
+	[&deploy[..], code].concat()
+}
+
+/// Code storing the first word of its input at index zero.
+///
+/// `PUSH1 0 CALLDATALOAD PUSH1 0 SSTORE STOP`
+const CODE_STORE: [u8; 7] = [0x60, 0x00, 0x35, 0x60, 0x00, 0x55, 0x00];
+
+/// Code emitting a log with the topic 7 and the data 42.
+///
+/// `PUSH1 42 PUSH1 0 MSTORE PUSH1 7 PUSH1 32 PUSH1 0 LOG1 STOP`
+const CODE_LOG: [u8; 13] = [
+	0x60, 0x2a, 0x60, 0x00, 0x52, 0x60, 0x07, 0x60, 0x20, 0x60, 0x00, 0xa1, 0x00,
+];
+
+/// Code reverting with the reason "no", ABI-encoded as `Error(string)`.
+///
+/// `PUSH4 0x08c379a0 PUSH1 224 SHL PUSH1 0 MSTORE PUSH1 32 PUSH1 4 MSTORE PUSH1 2 PUSH1 36 MSTORE
+/// PUSH2 "no" PUSH1 240 SHL PUSH1 68 MSTORE PUSH1 100 PUSH1 0 REVERT`
+const CODE_REVERT: [u8; 35] = [
+	0x63, 0x08, 0xc3, 0x79, 0xa0, 0x60, 0xe0, 0x1b, 0x60, 0x00, 0x52,
+	0x60, 0x20, 0x60, 0x04, 0x52,
+	0x60, 0x02, 0x60, 0x24, 0x52,
+	0x61, 0x6e, 0x6f, 0x60, 0xf0, 0x1b, 0x60, 0x44, 0x52,
+	0x60, 0x64, 0x60, 0x00, 0xfd,
+];
+
+/// Code storing the coinbase at index zero and the block gas limit at index one.
+///
+/// `COINBASE PUSH1 0 SSTORE GASLIMIT PUSH1 1 SSTORE STOP`
+const CODE_BLOCK_CONTEXT: [u8; 9] = [0x41, 0x60, 0x00, 0x55, 0x45, 0x60, 0x01, 0x55, 0x00];
+
+fn evm_create(nonce: u32, gas_price: U256) -> CheckedExtrinsic {
+	CheckedExtrinsic {
+		signed: Some((charlie(), signed_extra(nonce, 0))),
+		function: Call::EVM(pallet_evm::Call::create::<Runtime>(
+			init_code(&CODE_STORE),
+			U256::zero(),
+			100_000,
+			gas_price,
+			None,
+		)),
+	}
+}
+
+/// The address of the Ethereum key `secret`.
+fn ethereum_address(secret: &secp256k1::SecretKey) -> H160 {
+	let public = secp256k1::PublicKey::from_secret_key(secret);
+	H160::from_slice(&sp_io::hashing::keccak_256(&public.serialize()[1..])[12..])
+}
+
+/// A raw transaction of `value` from the Ethereum key `secret` to `to`, with a gas limit of
+/// 100_000 at `gas_price`, signed for `chain_id`.
+fn signed_transfer(
+	secret: &secp256k1::SecretKey,
+	chain_id: u64,
+	nonce: u64,
+	gas_price: U256,
+	to: H160,
+	value: U256,
+) -> Vec<u8> {
+	signed_transfer_with_gas_limit(secret, chain_id, nonce, 100_000.into(), gas_price, to, value)
+}
+
+/// Like `signed_transfer`, with a gas limit of `gas_limit`.
+fn signed_transfer_with_gas_limit(
+	secret: &secp256k1::SecretKey,
+	chain_id: u64,
+	nonce: u64,
+	gas_limit: U256,
+	gas_price: U256,
+	to: H160,
+	value: U256,
+) -> Vec<u8> {
+	let mut transaction = pallet_evm::Transaction {
+		nonce: nonce.into(),
+		gas_price,
+		gas_limit,
+		action: pallet_evm::TransactionAction::Call(to),
+		value,
+		input: vec![],
+		v: chain_id * 2 + 35,
+		r: U256::zero(),
+		s: U256::zero(),
+	};
+	let message = secp256k1::Message::parse(transaction.message().as_fixed_bytes());
+	let (signature, recovery_id) = secp256k1::sign(&message, secret);
+	let signature = signature.serialize();
+	transaction.r = U256::from_big_endian(&signature[0..32]);
+	transaction.s = U256::from_big_endian(&signature[32..64]);
+	transaction.v += recovery_id.serialize() as u64;
+	transaction.encode()
+}
+
+fn created_contracts() -> Vec<H160> {
+	System::events().into_iter()
+		.filter_map(|record| match record.event {
+			Event::pallet_evm(pallet_evm::Event::Created(address)) => Some(address),
+			_ => None,
+		})
+		.collect()
+}
+
+#[test]
+fn deploying_evm_contract_should_work() {
+	let mut builder = new_test_ext(COMPACT_CODE, false);
+
+	let b1 = construct_block(
+		&mut builder,
+		1,
+		GENESIS_HASH.into(),
+		vec![
+			CheckedExtrinsic {
+				signed: None,
+				function: Call::Timestamp(pallet_timestamp::Call::set(42 * 1000)),
+			},
+			evm_create(0, U256::one()),
+		]
+	);
+	let contract = builder.execute_with(|| created_contracts()[0]);
+
+	let mut input = [0u8; 32];
+	input[31] = 42;
+	let b2 = construct_block(
+		&mut builder,
+		2,
+		b1.1,
+		vec![
+			CheckedExtrinsic {
+				signed: None,
+				function: Call::Timestamp(pallet_timestamp::Call::set(52 * 1000)),
+			},
+			CheckedExtrinsic {
+				signed: Some((charlie(), signed_extra(1, 0))),
+				function: Call::EVM(pallet_evm::Call::call::<Runtime>(
+					contract,
+					input.to_vec(),
+					U256::zero(),
+					100_000,
+					U256::one(),
+					None,
+				)),
+			},
+		]
+	);
+
+	let mut t = new_test_ext(COMPACT_CODE, false);
+
+	executor_call::<NeverNativeValue, fn() -> _>(
+		&mut t,
+		"Core_execute_block",
+		&b1.0,
+		false,
+		None,
+	).0.unwrap();
+
+	t.execute_with(|| {
+		assert_eq!(created_contracts(), vec![contract]);
+		assert_eq!(EVM::account_codes(contract), CODE_STORE.to_vec());
+	});
+
+	executor_call::<NeverNativeValue, fn() -> _>(
+		&mut t,
+		"Core_execute_block",
+		&b2.0,
+		false,
+		None,
+	).0.unwrap();
+
+	t.execute_with(|| {
+		assert_eq!(EVM::account_storages(contract, H256::zero()), H256::from_low_u64_be(42));
+	});
+}
+
+#[test]
+fn evm_create_below_min_gas_price_fails() {
+	let mut t = new_test_ext(COMPACT_CODE, false);
+
+	let b = construct_block(
+		&mut new_test_ext(COMPACT_CODE, false),
+		1,
+		GENESIS_HASH.into(),
+		vec![
+			CheckedExtrinsic {
+				signed: None,
+				function: Call::Timestamp(pallet_timestamp::Call::set(42 * 1000)),
+			},
+			// The genesis minimal gas price is one.
+			evm_create(0, U256::zero()),
+		]
+	);
+
+	executor_call::<NeverNativeValue, fn() -> _>(
+		&mut t,
+		"Core_execute_block",
+		&b.0,
+		false,
+		None,
+	).0.unwrap();
+
+	t.execute_with(|| {
+		assert!(created_contracts().is_empty());
+	});
+}
+
+#[test]
+fn evm_sees_the_block_context() {
+	let mut builder = new_test_ext(COMPACT_CODE, false);
+
+	let b1 = construct_block(
+		&mut builder,
+		1,
+		GENESIS_HASH.into(),
+		vec![
+			CheckedExtrinsic {
+				signed: None,
+				function: Call::Timestamp(pallet_timestamp::Call::set(42 * 1000)),
+			},
+			CheckedExtrinsic {
+				signed: Some((charlie(), signed_extra(0, 0))),
+				function: Call::EVM(pallet_evm::Call::create::<Runtime>(
+					init_code(&CODE_BLOCK_CONTEXT),
+					U256::zero(),
+					100_000,
+					U256::one(),
+					None,
+				)),
+			},
+		]
+	);
+	let contract = builder.execute_with(|| created_contracts()[0]);
+
+	let b2 = construct_block(
+		&mut builder,
+		2,
+		b1.1,
+		vec![
+			CheckedExtrinsic {
+				signed: None,
+				function: Call::Timestamp(pallet_timestamp::Call::set(52 * 1000)),
+			},
+			CheckedExtrinsic {
+				signed: Some((charlie(), signed_extra(1, 0))),
+				function: Call::EVM(pallet_evm::Call::call::<Runtime>(
+					contract,
+					vec![],
+					U256::zero(),
+					100_000,
+					U256::one(),
+					None,
+				)),
+			},
+		]
+	);
+
+	let mut t = new_test_ext(COMPACT_CODE, false);
+
+	executor_call::<NeverNativeValue, fn() -> _>(
+		&mut t,
+		"Core_execute_block",
+		&b1.0,
+		false,
+		None,
+	).0.unwrap();
+
+	executor_call::<NeverNativeValue, fn() -> _>(
+		&mut t,
+		"Core_execute_block",
+		&b2.0,
+		false,
+		None,
+	).0.unwrap();
+
+	t.execute_with(|| {
+		// The test blocks have no pre-runtime digest, so their author is the default account.
+		let coinbase = HashTruncateConvertAccountId::<BlakeTwo256>::convert_account_id(
+			&AccountId::default(),
+		);
+		assert_eq!(EVM::account_storages(contract, H256::zero()), H256::from(coinbase));
+		assert_eq!(
+			EVM::account_storages(contract, H256::from_low_u64_be(1)),
+			H256::from_low_u64_be(MaximumBlockWeight::get() as u64),
+		);
+	});
+}
+
+#[test]
+fn transact_from_ethereum_key_should_work() {
+	let secret = secp256k1::SecretKey::parse(&[0x46; 32]).unwrap();
+	let sender = ethereum_address(&secret);
+	let recipient = H160::repeat_byte(0x35);
+	let transaction = signed_transfer(
+		&secret,
+		CHAIN_ID,
+		0,
+		U256::one(),
+		recipient,
+		U256::from(1_000),
+	);
+
+	let mut builder = new_test_ext(COMPACT_CODE, false);
+
+	// Fund the Ethereum key from the EVM account of charlie.
+	let b1 = construct_block(
+		&mut builder,
+		1,
+		GENESIS_HASH.into(),
+		vec![
+			CheckedExtrinsic {
+				signed: None,
+				function: Call::Timestamp(pallet_timestamp::Call::set(42 * 1000)),
+			},
+			CheckedExtrinsic {
+				signed: Some((charlie(), signed_extra(0, 0))),
+				function: Call::EVM(pallet_evm::Call::call::<Runtime>(
+					sender,
+					vec![],
+					U256::from(1_000_000),
+					100_000,
+					U256::one(),
+					None,
+				)),
+			},
+		]
+	);
+	let b2 = construct_block(
+		&mut builder,
+		2,
+		b1.1,
+		vec![
+			CheckedExtrinsic {
+				signed: None,
+				function: Call::Timestamp(pallet_timestamp::Call::set(52 * 1000)),
+			},
+			CheckedExtrinsic {
+				signed: None,
+				function: Call::EVM(pallet_evm::Call::transact::<Runtime>(transaction.clone())),
+			},
+		]
+	);
+
+	let mut t = new_test_ext(COMPACT_CODE, false);
+
+	executor_call::<NeverNativeValue, fn() -> _>(
+		&mut t,
+		"Core_execute_block",
+		&b1.0,
+		false,
+		None,
+	).0.unwrap();
+
+	t.execute_with(|| {
+		assert_eq!(EVM::accounts(sender).balance, U256::from(1_000_000));
+	});
+
+	executor_call::<NeverNativeValue, fn() -> _>(
+		&mut t,
+		"Core_execute_block",
+		&b2.0,
+		false,
+		None,
+	).0.unwrap();
+
+	t.execute_with(|| {
+		assert_eq!(EVM::accounts(recipient).balance, U256::from(1_000));
+		assert_eq!(EVM::accounts(sender).nonce, U256::one());
+
+		let hash = pallet_evm::Transaction::decode(&transaction).unwrap().hash();
+		let receipt = &EVM::receipts()[0];
+		assert_eq!(receipt.transaction_hash, hash);
+		assert_eq!((receipt.from, receipt.to), (sender, Some(recipient)));
+		assert_eq!(EVM::transaction_location(hash), Some((2, 0)));
+	});
+}
+
+#[test]
+fn decoding_transaction_checks_chain_id() {
+	let secret = secp256k1::SecretKey::parse(&[0x46; 32]).unwrap();
+	let recipient = H160::repeat_byte(0x35);
+
+	new_test_ext(COMPACT_CODE, false).execute_with(|| {
+		let transaction = signed_transfer(&secret, CHAIN_ID, 3, U256::one(), recipient, U256::from(1_000));
+		let (decoded, sender) = EVM::decode_transaction(&transaction).unwrap();
+		assert_eq!(sender, ethereum_address(&secret));
+		assert_eq!(decoded.nonce, U256::from(3));
+		assert_eq!(decoded.action, pallet_evm::TransactionAction::Call(recipient));
+
+		let transaction = signed_transfer(&secret, 1, 3, U256::one(), recipient, U256::from(1_000));
+		assert!(EVM::decode_transaction(&transaction).is_err());
+	});
+}
+
+#[test]
+fn evm_executions_share_the_block_gas_limit() {
+	let secret = secp256k1::SecretKey::parse(&[0x46; 32]).unwrap();
+	let sender = ethereum_address(&secret);
+	let block_gas_limit = MaximumBlockWeight::get();
+	// Fund the Ethereum key from the EVM account of charlie.
+	let call = |gas_limit| Call::EVM(pallet_evm::Call::call::<Runtime>(
+		sender,
+		vec![],
+		U256::from(DOLLARS),
+		gas_limit,
+		U256::one(),
+		None,
+	));
+	let transact = |gas_limit: u32| pallet_evm::Call::transact::<Runtime>(
+		signed_transfer_with_gas_limit(
+			&secret,
+			CHAIN_ID,
+			0,
+			gas_limit.into(),
+			U256::one(),
+			H160::repeat_byte(0x35),
+			U256::zero(),
+		),
+	);
+	let validate = |transact: pallet_evm::Call<Runtime>| {
+		<EVM as ValidateUnsigned>::validate_unsigned(TransactionSource::External, &transact)
+	};
+
+	new_test_ext(COMPACT_CODE, false).execute_with(|| {
+		// The weight of an execution is its gas limit, whatever its gas price.
+		assert_eq!(call(100_000).get_dispatch_info().weight, 100_000);
+		assert_eq!(transact(100_000).get_dispatch_info().weight, 100_000);
+
+		assert!(call(block_gas_limit).dispatch(Origin::signed(charlie())).is_ok());
+		// A plain transfer uses exactly the base gas of a transaction.
+		assert_eq!(EVM::block_gas_used(), U256::from(21_000));
+
+		assert!(call(block_gas_limit - 20_000).dispatch(Origin::signed(charlie())).is_err());
+		assert_eq!(
+			validate(transact(block_gas_limit - 20_000)),
+			Err(TransactionValidityError::Invalid(InvalidTransaction::ExhaustsResources)),
+		);
+		assert!(validate(transact(block_gas_limit - 21_000)).is_ok());
+		assert!(call(block_gas_limit - 21_000).dispatch(Origin::signed(charlie())).is_ok());
+		assert_eq!(EVM::block_gas_used(), U256::from(42_000));
+	});
+}
+
+#[test]
+fn evm_fees_go_to_treasury_and_author() {
+	let secret = secp256k1::SecretKey::parse(&[0x46; 32]).unwrap();
+	let sender = ethereum_address(&secret);
+	let recipient = H160::repeat_byte(0x35);
+	let funds = 60 * DOLLARS;
+	// The weight of the transaction only depends on its gas limit, so the price can be high.
+	let gas_price = 5_000u128;
+	// A plain transfer uses exactly the base gas of a transaction.
+	let fee = 21_000 * gas_price;
+
+	let mut builder = new_test_ext(COMPACT_CODE, false);
+
+	let b1 = construct_block(
+		&mut builder,
+		1,
+		GENESIS_HASH.into(),
+		vec![
+			CheckedExtrinsic {
+				signed: None,
+				function: Call::Timestamp(pallet_timestamp::Call::set(42 * 1000)),
+			},
+			CheckedExtrinsic {
+				signed: Some((charlie(), signed_extra(0, 0))),
+				function: Call::EVM(pallet_evm::Call::call::<Runtime>(
+					sender,
+					vec![],
+					U256::from(funds),
+					100_000,
+					U256::one(),
+					None,
+				)),
+			},
+			// The test blocks have no pre-runtime digest, so their author is the default account,
+			// which must exist for its share of the fees not to be dropped as dust.
+			CheckedExtrinsic {
+				signed: Some((charlie(), signed_extra(1, 0))),
+				function: Call::Balances(pallet_balances::Call::transfer(
+					AccountId::default().into(),
+					10 * DOLLARS,
+				)),
+			},
+		]
+	);
+	// Unsigned, so that the only fee paid in the block is the one of the EVM.
+	let b2 = construct_block(
+		&mut builder,
+		2,
+		b1.1,
+		vec![
+			CheckedExtrinsic {
+				signed: None,
+				function: Call::Timestamp(pallet_timestamp::Call::set(52 * 1000)),
+			},
+			CheckedExtrinsic {
+				signed: None,
+				function: Call::EVM(pallet_evm::Call::transact::<Runtime>(signed_transfer(
+					&secret,
+					CHAIN_ID,
+					0,
+					U256::from(gas_price),
+					recipient,
+					U256::from(1_000),
+				))),
+			},
+		]
+	);
+
+	let mut t = new_test_ext(COMPACT_CODE, false);
+
+	executor_call::<NeverNativeValue, fn() -> _>(
+		&mut t,
+		"Core_execute_block",
+		&b1.0,
+		false,
+		None,
+	).0.unwrap();
+
+	let balances = || (
+		Balances::free_balance(EVM::account_id()),
+		Balances::free_balance(Treasury::account_id()),
+		Balances::free_balance(AccountId::default()),
+		Balances::total_issuance(),
+	);
+	let (pot, treasury, author, issuance) = t.execute_with(balances);
+
+	executor_call::<NeverNativeValue, fn() -> _>(
+		&mut t,
+		"Core_execute_block",
+		&b2.0,
+		false,
+		None,
+	).0.unwrap();
+
+	t.execute_with(|| {
+		assert_eq!(
+			EVM::accounts(sender).balance,
+			U256::from(funds - 1_000 - fee),
+		);
+		assert_eq!(EVM::accounts(recipient).balance, U256::from(1_000));
+
+		let to_treasury: Balance = fee * 80 / 100;
+		assert_eq!(
+			balances(),
+			(pot - fee, treasury + to_treasury, author + (fee - to_treasury), issuance),
+		);
+	});
+}
+
+#[test]
+fn evm_execution_is_refused_if_its_fee_cannot_be_withdrawn() {
+	let recipient = H160::repeat_byte(0x35);
+	let call = || Call::EVM(pallet_evm::Call::call::<Runtime>(
+		recipient,
+		vec![],
+		U256::from(1_000),
+		100_000,
+		U256::one(),
+		None,
+	));
+
+	new_test_ext(COMPACT_CODE, false).execute_with(|| {
+		let source = HashTruncateConvertAccountId::<BlakeTwo256>::convert_account_id(&charlie());
+		let account = EVM::accounts(source);
+
+		// Withdrawing the most the call may cost would reap the account of the module.
+		let pot = ExistentialDeposit::get() + 99_999;
+		Balances::make_free_balance_be(&EVM::account_id(), pot);
+		assert!(call().dispatch(Origin::signed(charlie())).is_err());
+		assert_eq!(EVM::accounts(source), account);
+		assert_eq!(EVM::accounts(recipient).balance, U256::zero());
+		assert_eq!(Balances::free_balance(EVM::account_id()), pot);
+
+		// The unused gas is refunded to the account of the module.
+		Balances::make_free_balance_be(&EVM::account_id(), pot + 1);
+		assert!(call().dispatch(Origin::signed(charlie())).is_ok());
+		assert_eq!(EVM::accounts(recipient).balance, U256::from(1_000));
+		assert_eq!(Balances::free_balance(EVM::account_id()), pot + 1 - 21_000);
+	});
+}
+
+#[test]
+fn evm_receipts_record_logs_and_gas() {
+	let mut builder = new_test_ext(COMPACT_CODE, false);
+
+	let b1 = construct_block(
+		&mut builder,
+		1,
+		GENESIS_HASH.into(),
+		vec![
+			CheckedExtrinsic {
+				signed: None,
+				function: Call::Timestamp(pallet_timestamp::Call::set(42 * 1000)),
+			},
+			CheckedExtrinsic {
+				signed: Some((charlie(), signed_extra(0, 0))),
+				function: Call::EVM(pallet_evm::Call::create::<Runtime>(
+					init_code(&CODE_LOG),
+					U256::zero(),
+					100_000,
+					U256::one(),
+					None,
+				)),
+			},
+		]
+	);
+	let contract = builder.execute_with(|| created_contracts()[0]);
+
+	let call = |nonce| CheckedExtrinsic {
+		signed: Some((charlie(), signed_extra(nonce, 0))),
+		function: Call::EVM(pallet_evm::Call::call::<Runtime>(
+			contract,
+			vec![],
+			U256::zero(),
+			100_000,
+			U256::one(),
+			None,
+		)),
+	};
+	let b2 = construct_block(
+		&mut builder,
+		2,
+		b1.1,
+		vec![
+			CheckedExtrinsic {
+				signed: None,
+				function: Call::Timestamp(pallet_timestamp::Call::set(52 * 1000)),
+			},
+			call(1),
+			call(2),
+		]
+	);
+
+	let mut t = new_test_ext(COMPACT_CODE, false);
+
+	executor_call::<NeverNativeValue, fn() -> _>(
+		&mut t,
+		"Core_execute_block",
+		&b1.0,
+		false,
+		None,
+	).0.unwrap();
+
+	t.execute_with(|| {
+		let receipts = EVM::receipts();
+		assert_eq!(receipts.len(), 1);
+		assert_eq!(receipts[0].to, None);
+		assert_eq!(receipts[0].contract_address, Some(contract));
+		assert!(receipts[0].logs.is_empty());
+	});
+
+	executor_call::<NeverNativeValue, fn() -> _>(
+		&mut t,
+		"Core_execute_block",
+		&b2.0,
+		false,
+		None,
+	).0.unwrap();
+
+	t.execute_with(|| {
+		let topic = H256::from_low_u64_be(7);
+		let log = pallet_evm::Log {
+			address: contract,
+			topics: vec![topic],
+			data: H256::from_low_u64_be(42).as_bytes().to_vec(),
+		};
+
+		// The receipts of the first block are gone.
+		let receipts = EVM::receipts();
+		assert_eq!(receipts.len(), 2);
+		for (index, receipt) in receipts.iter().enumerate() {
+			assert!(receipt.status);
+			assert_eq!(receipt.transaction_index, index as u32);
+			assert_eq!(receipt.to, Some(contract));
+			assert_eq!(receipt.logs, vec![log.clone()]);
+			assert_eq!(receipt.logs_bloom, pallet_evm::logs_bloom(&[log.clone()]));
+			assert_eq!(
+				EVM::transaction_location(receipt.transaction_hash),
+				Some((2, index as u32)),
+			);
+		}
+		assert_ne!(receipts[0].transaction_hash, receipts[1].transaction_hash);
+		assert_eq!(receipts[0].cumulative_gas_used, receipts[0].used_gas);
+		assert_eq!(receipts[1].cumulative_gas_used, receipts[0].used_gas + receipts[1].used_gas);
+
+		let in_bloom = |bytes: &[u8]| EVM::logs_bloom().contains_input(BloomInput::Raw(bytes));
+		assert!(in_bloom(contract.as_bytes()));
+		assert!(in_bloom(topic.as_bytes()));
+		assert!(!in_bloom(H256::from_low_u64_be(8).as_bytes()));
+	});
+}
+
+#[test]
+fn evm_dry_runs_report_outcome_and_steps() {
+	let mut builder = new_test_ext(COMPACT_CODE, false);
+
+	let b = construct_block(
+		&mut builder,
+		1,
+		GENESIS_HASH.into(),
+		vec![
+			CheckedExtrinsic {
+				signed: None,
+				function: Call::Timestamp(pallet_timestamp::Call::set(42 * 1000)),
+			},
+			evm_create(0, U256::one()),
+			CheckedExtrinsic {
+				signed: Some((charlie(), signed_extra(1, 0))),
+				function: Call::EVM(pallet_evm::Call::create::<Runtime>(
+					init_code(&CODE_REVERT),
+					U256::zero(),
+					100_000,
+					U256::one(),
+					None,
+				)),
+			},
+		]
+	);
+
+	let mut t = new_test_ext(COMPACT_CODE, false);
+
+	executor_call::<NeverNativeValue, fn() -> _>(
+		&mut t,
+		"Core_execute_block",
+		&b.0,
+		false,
+		None,
+	).0.unwrap();
+
+	t.execute_with(|| {
+		let (store, revert) = (created_contracts()[0], created_contracts()[1]);
+		let source = HashTruncateConvertAccountId::<BlakeTwo256>::convert_account_id(&charlie());
+		let mut input = [0u8; 32];
+		input[31] = 42;
+
+		let execution = EVM::trace_call(
+			source,
+			store,
+			input.to_vec(),
+			U256::zero(),
+			100_000,
+			U256::zero(),
+			true,
+		).unwrap();
+		assert_eq!(execution.exit_status, pallet_evm::ExitStatus::Succeed);
+		assert!(execution.changes.iter().any(|change| match change {
+			pallet_evm::AccountChange::Modify { address, storage, .. } =>
+				*address == store && *storage == vec![(H256::zero(), H256::from_low_u64_be(42))],
+			_ => false,
+		}));
+		// `PUSH1 0 CALLDATALOAD PUSH1 0 SSTORE STOP`
+		let steps = execution.steps.unwrap();
+		assert_eq!(
+			steps.iter().map(|step| (step.pc, step.opcode)).collect::<Vec<_>>(),
+			vec![(0, 0x60), (2, 0x35), (3, 0x60), (5, 0x55), (6, 0x00)],
+		);
+		assert_eq!(steps[1].stack, vec![H256::zero()]);
+		assert_eq!(steps[3].stack, vec![H256::from_low_u64_be(42), H256::zero()]);
+		// The gas left after the intrinsic cost of the call, with its input of one non-zero byte.
+		assert_eq!(steps[0].gas, U256::from(100_000 - 21_000 - 31 * 4 - 16));
+		assert_eq!(steps[0].gas_cost, U256::from(3));
+		assert!(!execution.truncated);
+		// The trace is of the execution reported.
+		let untraced = EVM::trace_call(
+			source,
+			store,
+			input.to_vec(),
+			U256::zero(),
+			100_000,
+			U256::zero(),
+			false,
+		).unwrap();
+		assert_eq!(
+			(execution.used_gas, &execution.changes),
+			(untraced.used_gas, &untraced.changes),
+		);
+		// Nothing is applied.
+		assert_eq!(EVM::account_storages(store, H256::zero()), H256::zero());
+
+		let execution = EVM::trace_call(
+			source,
+			revert,
+			vec![],
+			U256::zero(),
+			100_000,
+			U256::zero(),
+			false,
+		).unwrap();
+		assert_eq!(execution.exit_status, pallet_evm::ExitStatus::Revert);
+		assert_eq!(execution.output.len(), 100);
+		assert_eq!(execution.revert_reason(), Some(&b"no"[..]));
+		assert!(execution.used_gas > U256::from(21_000));
+		assert!(execution.steps.is_none());
+
+		let traced = EVM::trace_call(
+			source,
+			revert,
+			vec![],
+			U256::zero(),
+			100_000,
+			U256::zero(),
+			true,
+		).unwrap();
+		assert_eq!(traced.exit_status, pallet_evm::ExitStatus::Revert);
+		assert_eq!(traced.revert_reason(), Some(&b"no"[..]));
+		assert_eq!(traced.used_gas, execution.used_gas);
+		assert_eq!(traced.steps.unwrap().last().map(|step| step.opcode), Some(0xfd));
+
+		let execution = EVM::trace_create(
+			source,
+			init_code(&CODE_STORE),
+			U256::zero(),
+			100_000,
+			U256::zero(),
+			false,
+		).unwrap();
+		assert_eq!(execution.exit_status, pallet_evm::ExitStatus::Succeed);
+		let contract = execution.contract_address.unwrap();
+		assert!(execution.changes.iter().any(|change| match change {
+			pallet_evm::AccountChange::Modify { address, code, .. } =>
+				*address == contract && *code == Some(CODE_STORE.to_vec()),
+			_ => false,
+		}));
+		assert!(EVM::account_codes(contract).is_empty());
+
+		let traced = EVM::trace_create(
+			source,
+			init_code(&CODE_STORE),
+			U256::zero(),
+			100_000,
+			U256::zero(),
+			true,
+		).unwrap();
+		assert_eq!(traced.contract_address, Some(contract));
+		assert_eq!(traced.used_gas, execution.used_gas);
+		assert!(traced.changes.iter().any(|change| match change {
+			pallet_evm::AccountChange::Modify { address, code, .. } =>
+				*address == contract && *code == Some(CODE_STORE.to_vec()),
+			_ => false,
+		}));
+		// `PUSH1 len DUP1 PUSH1 11 PUSH1 0 CODECOPY PUSH1 0 RETURN`
+		assert_eq!(traced.steps.unwrap().len(), 7);
+	});
+}
+
+/// The gas a transaction with `input` costs before it runs, `base` being the cost of the
+/// transaction without input.
+fn intrinsic_gas(base: u32, input: &[u8]) -> u32 {
+	input.iter().fold(base, |gas, byte| gas + if *byte == 0 { 4 } else { 16 })
+}
+
+/// Check that the steps of `traced` start with the gas `gas_limit` leaves after `intrinsic`,
+/// and cost the gas `traced` used besides it and `deposit`.
+fn assert_steps_cost(traced: &pallet_evm::Execution, gas_limit: u32, intrinsic: u32, deposit: u32) {
+	let steps = traced.steps.as_ref().unwrap();
+	assert_eq!(steps[0].gas, U256::from(gas_limit - intrinsic));
+	let cost = steps.iter().fold(U256::zero(), |cost, step| cost + step.gas_cost);
+	assert_eq!(cost + U256::from(intrinsic + deposit), traced.used_gas);
+}
+
+#[test]
+fn evm_traces_match_executions() {
+	let mut builder = new_test_ext(COMPACT_CODE, false);
+
+	let b = construct_block(
+		&mut builder,
+		1,
+		GENESIS_HASH.into(),
+		vec![
+			CheckedExtrinsic {
+				signed: None,
+				function: Call::Timestamp(pallet_timestamp::Call::set(42 * 1000)),
+			},
+			evm_create(0, U256::one()),
+			CheckedExtrinsic {
+				signed: Some((charlie(), signed_extra(1, 0))),
+				function: Call::EVM(pallet_evm::Call::create::<Runtime>(
+					init_code(&CODE_REVERT),
+					U256::zero(),
+					100_000,
+					U256::one(),
+					None,
+				)),
+			},
+		]
+	);
+
+	let mut t = new_test_ext(COMPACT_CODE, false);
+
+	executor_call::<NeverNativeValue, fn() -> _>(
+		&mut t,
+		"Core_execute_block",
+		&b.0,
+		false,
+		None,
+	).0.unwrap();
+
+	t.execute_with(|| {
+		let (store, revert) = (created_contracts()[0], created_contracts()[1]);
+		let identity = H160::from_low_u64_be(4);
+		let source = HashTruncateConvertAccountId::<BlakeTwo256>::convert_account_id(&charlie());
+		let mut input = [0u8; 32];
+		input[31] = 42;
+
+		// A call, a revert, a call running out of gas at its `SSTORE` and a precompile.
+		let calls = vec![
+			(store, input.to_vec(), 100_000, pallet_evm::ExitStatus::Succeed),
+			(revert, vec![], 100_000, pallet_evm::ExitStatus::Revert),
+			(store, input.to_vec(), 21_200, pallet_evm::ExitStatus::Error(b"OutOfGas".to_vec())),
+			(identity, b"abc".to_vec(), 100_000, pallet_evm::ExitStatus::Succeed),
+		];
+		for (target, input, gas_limit, exit_status) in calls {
+			let (reason, output, used_gas, logs) = EVM::execute_call(
+				source,
+				target,
+				input.clone(),
+				U256::zero(),
+				gas_limit,
+				U256::zero(),
+				None,
+				false,
+			).unwrap();
+			let traced = EVM::trace_call(
+				source,
+				target,
+				input.clone(),
+				U256::zero(),
+				gas_limit,
+				U256::zero(),
+				true,
+			).unwrap();
+			assert_eq!(traced.exit_status, exit_status);
+			assert_eq!(pallet_evm::ExitStatus::from(&reason), exit_status);
+			assert_eq!((&traced.output, traced.used_gas, &traced.logs), (&output, used_gas, &logs));
+			if target == identity {
+				// Precompiles have no steps.
+				assert_eq!(traced.steps, Some(vec![]));
+				assert_eq!(traced.output, b"abc".to_vec());
+			} else {
+				assert_steps_cost(&traced, gas_limit, intrinsic_gas(21_000, &input), 0);
+			}
+		}
+		// Running out of gas uses all of it.
+		let traced = EVM::trace_call(
+			source,
+			store,
+			input.to_vec(),
+			U256::zero(),
+			21_200,
+			U256::zero(),
+			true,
+		).unwrap();
+		assert_eq!(traced.used_gas, U256::from(21_200));
+		assert_eq!(traced.steps.unwrap().last().map(|step| step.opcode), Some(0x55));
+
+		let init = init_code(&CODE_STORE);
+		let (reason, address, used_gas, logs) = EVM::execute_create(
+			source,
+			init.clone(),
+			U256::zero(),
+			100_000,
+			U256::zero(),
+			None,
+			false,
+		).unwrap();
+		let traced = EVM::trace_create(
+			source,
+			init.clone(),
+			U256::zero(),
+			100_000,
+			U256::zero(),
+			true,
+		).unwrap();
+		assert_eq!(pallet_evm::ExitStatus::from(&reason), pallet_evm::ExitStatus::Succeed);
+		assert_eq!(
+			(traced.exit_status.clone(), traced.contract_address, traced.used_gas, &traced.logs),
+			(pallet_evm::ExitStatus::Succeed, Some(address), used_gas, &logs),
+		);
+		// The code of the contract is deposited for 200 per byte.
+		let deposit = 200 * CODE_STORE.len() as u32;
+		assert_steps_cost(&traced, 100_000, intrinsic_gas(53_000, &init), deposit);
+	});
+}
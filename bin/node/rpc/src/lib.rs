@@ -34,6 +34,7 @@ use std::{sync::Arc, fmt};
 use node_primitives::{Block, BlockNumber, AccountId, Index, Balance};
 use node_runtime::UncheckedExtrinsic;
 use sp_api::ProvideRuntimeApi;
+use sp_core::offchain::OffchainStorage;
 use sp_transaction_pool::TransactionPool;
 use sp_blockchain::{Error as BlockChainError, HeaderMetadata, HeaderBackend};
 use sp_consensus::SelectChain;
@@ -66,20 +67,23 @@ pub struct BabeDeps {
 }
 
 /// Full client dependencies.
-pub struct FullDeps<C, P, SC> {
+pub struct FullDeps<C, P, SC, S> {
 	/// The client instance to use.
 	pub client: Arc<C>,
 	/// Transaction pool instance.
 	pub pool: Arc<P>,
 	/// The SelectChain Strategy
 	pub select_chain: SC,
-	/// BABE specific dependencies.
-	pub babe: BabeDeps,
+	/// BABE specific dependencies, if blocks are authored with BABE.
+	pub babe: Option<BabeDeps>,
+	/// The offchain storage read by the offchain workers, through which the `search_queueUpload`
+	/// RPC feeds them. The RPC is only exposed if this is given.
+	pub offchain_storage: Option<S>,
 }
 
 /// Instantiate all Full RPC extensions.
-pub fn create_full<C, P, M, SC>(
-	deps: FullDeps<C, P, SC>,
+pub fn create_full<C, P, M, SC, S>(
+	deps: FullDeps<C, P, SC, S>,
 ) -> jsonrpc_core::IoHandler<M> where
 	C: ProvideRuntimeApi<Block>,
 	C: HeaderBackend<Block> + HeaderMetadata<Block, Error=BlockChainError> + 'static,
@@ -87,29 +91,29 @@ pub fn create_full<C, P, M, SC>(
 	C::Api: substrate_frame_rpc_system::AccountNonceApi<Block, AccountId, Index>,
 	C::Api: pallet_contracts_rpc::ContractsRuntimeApi<Block, AccountId, Balance, BlockNumber>,
 	C::Api: pallet_transaction_payment_rpc::TransactionPaymentRuntimeApi<Block, Balance, UncheckedExtrinsic>,
+	C::Api: pallet_search_rpc::SearchRuntimeApi<Block, AccountId>,
+	C::Api: pallet_evm_rpc::EVMRuntimeApi<Block>,
 	C::Api: BabeApi<Block>,
 	<C::Api as sp_api::ApiErrorExt>::Error: fmt::Debug,
 	P: TransactionPool + 'static,
 	M: jsonrpc_core::Metadata + Default,
 	SC: SelectChain<Block> +'static,
+	S: OffchainStorage + 'static,
 {
 	use substrate_frame_rpc_system::{FullSystem, SystemApi};
 	use pallet_contracts_rpc::{Contracts, ContractsApi};
 	use pallet_transaction_payment_rpc::{TransactionPayment, TransactionPaymentApi};
+	use pallet_search_rpc::{Search, SearchApi, SearchOffchain, SearchOffchainApi};
+	use pallet_evm_rpc::{Eth, EthApi, EthDebugApi};
 
 	let mut io = jsonrpc_core::IoHandler::default();
 	let FullDeps {
 		client,
 		pool,
 		select_chain,
-		babe
+		babe,
+		offchain_storage,
 	} = deps;
-	let BabeDeps {
-		keystore,
-		babe_config,
-		shared_epoch_changes,
-	} = babe;
-
 	io.extend_with(
 		SystemApi::to_delegate(FullSystem::new(client.clone(), pool))
 	);
@@ -123,10 +127,37 @@ pub fn create_full<C, P, M, SC>(
 		TransactionPaymentApi::to_delegate(TransactionPayment::new(client.clone()))
 	);
 	io.extend_with(
-		sc_consensus_babe_rpc::BabeApi::to_delegate(
-			BabeRPCHandler::new(client, shared_epoch_changes, keystore, babe_config, select_chain)
-		)
+		SearchApi::to_delegate(Search::new(client.clone()))
+	);
+	if let Some(storage) = offchain_storage {
+		io.extend_with(
+			SearchOffchainApi::to_delegate(SearchOffchain::new(storage))
+		);
+	}
+	io.extend_with(
+		EthApi::to_delegate(Eth::new(client.clone()))
+	);
+	io.extend_with(
+		EthDebugApi::to_delegate(Eth::new(client.clone()))
 	);
+	if let Some(babe) = babe {
+		let BabeDeps {
+			keystore,
+			babe_config,
+			shared_epoch_changes,
+		} = babe;
+		io.extend_with(
+			sc_consensus_babe_rpc::BabeApi::to_delegate(
+				BabeRPCHandler::new(
+					client,
+					shared_epoch_changes,
+					keystore,
+					babe_config,
+					select_chain,
+				)
+			)
+		);
+	}
 
 	io
 }
@@ -21,7 +21,8 @@ use frame_support::{
     traits::{Currency, Get, OnUnbalanced},
     weights::Weight,
 };
-use node_primitives::Balance;
+use node_primitives::{AccountId, Balance};
+use sp_core::U256;
 use sp_runtime::traits::{Convert, Saturating};
 use sp_runtime::{Fixed64, Perbill};
 
@@ -32,6 +33,23 @@ impl OnUnbalanced<NegativeImbalance> for Author {
     }
 }
 
+/// The author of the current block, whose EVM address is the coinbase.
+pub struct BlockAuthor;
+impl Get<AccountId> for BlockAuthor {
+    fn get() -> AccountId {
+        Authorship::author()
+    }
+}
+
+/// The EVM gas limit of a block. An execution weighs as much as its gas limit, whatever its gas
+/// price, so a block fits as much gas as it has weight.
+pub struct BlockGasLimit;
+impl Get<U256> for BlockGasLimit {
+    fn get() -> U256 {
+        U256::from(MaximumBlockWeight::get())
+    }
+}
+
 /// Struct that handles the conversion of Balance -> `u64`. This is used for staking's election
 /// calculation.
 pub struct CurrencyToVoteHandler;
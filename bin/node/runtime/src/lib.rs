@@ -23,21 +23,25 @@
 
 use frame_support::{
     construct_runtime, debug, parameter_types,
-    traits::{Currency, Imbalance, OnUnbalanced, Randomness},
+    traits::{Currency, Get, Imbalance, OnUnbalanced, Randomness},
     weights::Weight,
 };
 use frame_system::offchain::TransactionSubmitter;
 pub use node_primitives::{AccountId, Signature};
 use node_primitives::{AccountIndex, Balance, BlockNumber, Hash, Index, Moment};
 use pallet_contracts_rpc_runtime_api::ContractExecResult;
+use pallet_evm::HashTruncateConvertAccountId;
 use pallet_grandpa::fg_primitives;
 use pallet_grandpa::AuthorityList as GrandpaAuthorityList;
 use pallet_im_online::sr25519::AuthorityId as ImOnlineId;
+use pallet_search_rpc_runtime_api::{
+    MerkleProof, SearchServiceHash, SearchServiceInfo, Tag, TagMatch,
+};
 use pallet_transaction_payment_rpc_runtime_api::RuntimeDispatchInfo;
 use sp_api::impl_runtime_apis;
 use sp_authority_discovery::AuthorityId as AuthorityDiscoveryId;
 use sp_core::u32_trait::{_1, _2, _3, _4};
-use sp_core::OpaqueMetadata;
+use sp_core::{OpaqueMetadata, H160, H256, U256};
 use sp_inherents::{CheckInherentsResult, InherentData};
 use sp_runtime::curve::PiecewiseLinear;
 use sp_runtime::traits::{
@@ -45,7 +49,8 @@ use sp_runtime::traits::{
 };
 use sp_runtime::transaction_validity::{TransactionSource, TransactionValidity};
 use sp_runtime::{
-    create_runtime_str, generic, impl_opaque_keys, ApplyExtrinsicResult, Perbill, Percent, Permill,
+    create_runtime_str, generic, impl_opaque_keys, ApplyExtrinsicResult, ModuleId, Perbill, Percent,
+    Permill,
 };
 use sp_std::prelude::*;
 #[cfg(any(feature = "std", test))]
@@ -60,11 +65,12 @@ pub use pallet_timestamp::Call as TimestampCall;
 #[cfg(any(feature = "std", test))]
 pub use sp_runtime::BuildStorage;
 
-mod search;
-
 /// Implementations of some helper traits passed into runtime modules as associated types.
 pub mod impls;
-use impls::{Author, CurrencyToVoteHandler, LinearWeightToFee, TargetedFeeAdjustment};
+use impls::{
+    Author, BlockAuthor, BlockGasLimit, CurrencyToVoteHandler, LinearWeightToFee,
+    TargetedFeeAdjustment,
+};
 
 /// Constant values used within the runtime.
 pub mod constants;
@@ -137,8 +143,8 @@ pub const VERSION: RuntimeVersion = RuntimeVersion {
     // and set impl_version to 0. If only runtime
     // implementation changes and behavior does not, then leave spec_version as
     // is and increment impl_version.
-    spec_version: 240,
-    impl_version: 1,
+    spec_version: 255,
+    impl_version: 0,
     apis: RUNTIME_API_VERSIONS,
 };
 
@@ -657,9 +663,64 @@ impl pallet_vesting::Trait for Runtime {
     type MinVestedTransfer = MinVestedTransfer;
 }
 
-impl search::Trait for Runtime {
+parameter_types! {
+    pub const ServiceDepositBase: Balance = 1 * DOLLARS;
+    pub const ServiceDepositPerByte: Balance = 10 * CENTS;
+    pub const SearchModuleId: ModuleId = ModuleId(*b"py/srchr");
+    pub const SearchEraDuration: BlockNumber = 1 * DAYS;
+    pub const RewardPerHeat: Balance = 1 * CENTS;
+    pub const SearchEraBudget: Balance = 1_000 * DOLLARS;
+    pub const HeatDecayPeriod: BlockNumber = 1 * DAYS;
+    pub const HeatDecay: Perbill = Perbill::from_percent(90);
+    pub const RankingSize: u32 = 100;
+    pub const RootHistoryDepth: u32 = 256;
+    pub const SearchChallengeBond: Balance = 10 * DOLLARS;
+    pub const SearchChallengePeriod: BlockNumber = 12 * HOURS;
+    pub const SearchDismissalSlash: Perbill = Perbill::from_percent(10);
+    pub const SearchUploadInterval: BlockNumber = 10;
+}
+
+impl pallet_search::Trait for Runtime {
     type Event = Event;
+    type Call = Call;
+    type SubmitSignedTransaction = TransactionSubmitterOf<pallet_search::crypto::Public>;
+    type UploadInterval = SearchUploadInterval;
+    type Currency = Balances;
+    type ModuleId = SearchModuleId;
+    type EraDuration = SearchEraDuration;
+    type RewardPerHeat = RewardPerHeat;
+    type EraBudget = SearchEraBudget;
+    type HeatDecayPeriod = HeatDecayPeriod;
+    type HeatDecay = HeatDecay;
+    type RankingSize = RankingSize;
+    type RootHistoryDepth = RootHistoryDepth;
+    type ServiceDepositBase = ServiceDepositBase;
+    type ServiceDepositPerByte = ServiceDepositPerByte;
+    type Slashed = Treasury;
+    type ForceOrigin =
+        pallet_collective::EnsureProportionMoreThan<_1, _2, AccountId, CouncilCollective>;
+    type ChallengeBond = SearchChallengeBond;
+    type ChallengePeriod = SearchChallengePeriod;
+    type DismissalSlash = SearchDismissalSlash;
+    type ResolveOrigin =
+        pallet_collective::EnsureProportionMoreThan<_1, _2, AccountId, CouncilCollective>;
+    type UnixTime = Timestamp;
+}
+
+parameter_types! {
+    pub const EVMTransactionRetention: BlockNumber = 7 * DAYS;
+}
+
+impl pallet_evm::Trait for Runtime {
+    type FeeCalculator = EVM;
+    type ConvertAccountId = HashTruncateConvertAccountId<BlakeTwo256>;
     type Currency = Balances;
+    type Event = Event;
+    type Precompiles = pallet_evm_precompiles::Istanbul;
+    type BlockAuthor = BlockAuthor;
+    type BlockGasLimit = BlockGasLimit;
+    type OnChargeTransaction = pallet_evm::EVMCurrencyAdapter<DealWithFees>;
+    type TransactionRetention = EVMTransactionRetention;
 }
 
 construct_runtime!(
@@ -697,7 +758,8 @@ construct_runtime!(
         Recovery: pallet_recovery::{Module, Call, Storage, Event<T>},
         Vesting: pallet_vesting::{Module, Call, Storage, Event<T>, Config<T>},
 
-        Search: search::{Module, Call, Storage, Event<T>},
+        Search: pallet_search::{Module, Call, Storage, Config<T>, Event<T>},
+        EVM: pallet_evm::{Module, Call, Storage, Config, Event, ValidateUnsigned},
     }
 );
 
@@ -880,6 +942,148 @@ impl_runtime_apis! {
         }
     }
 
+    impl pallet_search_rpc_runtime_api::SearchApi<Block, AccountId> for Runtime {
+        fn recommend(tag: Option<Tag>, count: u32) -> Vec<SearchServiceInfo<AccountId>> {
+            Search::recommend(tag, count)
+        }
+
+        fn services_by_tags(
+            tags: Vec<Tag>,
+            matching: TagMatch,
+            start: u32,
+            count: u32,
+        ) -> Vec<SearchServiceInfo<AccountId>> {
+            Search::services_by_tags(tags, matching, start, count)
+        }
+
+        fn service_by_name(name: Vec<u8>) -> Option<SearchServiceInfo<AccountId>> {
+            Search::service_by_name(name)
+        }
+
+        fn service_hash(name: Vec<u8>) -> Option<SearchServiceHash<AccountId>> {
+            Search::service_hash(name)
+        }
+
+        fn verify_searched_item(
+            name: Vec<u8>,
+            upload: Option<u32>,
+            record: Vec<u8>,
+            proof: MerkleProof,
+        ) -> bool {
+            Search::verify_searched_item(name, upload, record, proof)
+        }
+    }
+
+    impl pallet_evm_rpc_runtime_api::EVMApi<Block> for Runtime {
+        fn chain_id() -> u64 {
+            sp_io::misc::chain_id()
+        }
+
+        fn account_basic(address: H160) -> pallet_evm::Account {
+            EVM::accounts(address)
+        }
+
+        fn account_code(address: H160) -> Vec<u8> {
+            EVM::account_codes(address)
+        }
+
+        fn account_storage(address: H160, index: H256) -> H256 {
+            EVM::account_storages(address, index)
+        }
+
+        fn call(
+            from: H160,
+            to: H160,
+            input_data: Vec<u8>,
+            value: U256,
+            gas_limit: U256,
+            gas_price: Option<U256>,
+        ) -> Result<(Vec<u8>, U256), sp_runtime::DispatchError> {
+            let (reason, output, used_gas, _) = EVM::execute_call(
+                from,
+                to,
+                input_data,
+                value,
+                gas_limit.min(BlockGasLimit::get()).min(u32::max_value().into()).low_u32(),
+                gas_price.unwrap_or_default(),
+                None,
+                false,
+            )?;
+            EVM::exit_result(reason)?;
+            Ok((output, used_gas))
+        }
+
+        fn create(
+            from: H160,
+            input_data: Vec<u8>,
+            value: U256,
+            gas_limit: U256,
+            gas_price: Option<U256>,
+        ) -> Result<(H160, U256), sp_runtime::DispatchError> {
+            let (reason, address, used_gas, _) = EVM::execute_create(
+                from,
+                input_data,
+                value,
+                gas_limit.min(BlockGasLimit::get()).min(u32::max_value().into()).low_u32(),
+                gas_price.unwrap_or_default(),
+                None,
+                false,
+            )?;
+            EVM::exit_result(reason)?;
+            Ok((address, used_gas))
+        }
+
+        fn trace_call(
+            from: H160,
+            to: H160,
+            input_data: Vec<u8>,
+            value: U256,
+            gas_limit: U256,
+            gas_price: Option<U256>,
+            trace: bool,
+        ) -> Result<pallet_evm::Execution, sp_runtime::DispatchError> {
+            EVM::trace_call(
+                from,
+                to,
+                input_data,
+                value,
+                gas_limit.min(BlockGasLimit::get()).min(u32::max_value().into()).low_u32(),
+                gas_price.unwrap_or_default(),
+                trace,
+            ).map_err(Into::into)
+        }
+
+        fn trace_create(
+            from: H160,
+            input_data: Vec<u8>,
+            value: U256,
+            gas_limit: U256,
+            gas_price: Option<U256>,
+            trace: bool,
+        ) -> Result<pallet_evm::Execution, sp_runtime::DispatchError> {
+            EVM::trace_create(
+                from,
+                input_data,
+                value,
+                gas_limit.min(BlockGasLimit::get()).min(u32::max_value().into()).low_u32(),
+                gas_price.unwrap_or_default(),
+                trace,
+            ).map_err(Into::into)
+        }
+
+        fn current_receipts() -> Vec<pallet_evm::Receipt> {
+            EVM::receipts()
+        }
+
+        fn current_logs_bloom() -> pallet_evm::Bloom {
+            EVM::logs_bloom()
+        }
+
+        fn transaction_location(hash: H256) -> Option<(U256, u32)> {
+            EVM::transaction_location(hash).map(|(number, index)| (U256::from(number), index))
+        }
+    }
+
     impl sp_session::SessionKeys<Block> for Runtime {
         fn generate_session_keys(seed: Option<Vec<u8>>) -> Vec<u8> {
             SessionKeys::generate(seed)
@@ -980,6 +1184,13 @@ impl_runtime_apis! {
                     steps,
                     repeat,
                 ),
+                b"pallet-search" | b"search" => Search::run_benchmark(
+                    extrinsic,
+                    lowest_range_values,
+                    highest_range_values,
+                    steps,
+                    repeat,
+                ),
                 _ => Err("Benchmark not found for this pallet."),
             };
 
@@ -1015,5 +1226,6 @@ mod tests {
 
         is_submit_signed_transaction::<TransactionSubmitterOf<ImOnlineId>>();
         is_sign_and_submit_transaction::<TransactionSubmitterOf<ImOnlineId>>();
+        is_submit_signed_transaction::<TransactionSubmitterOf<pallet_search::crypto::Public>>();
     }
 }
@@ -20,12 +20,14 @@ use crate::keyring::*;
 use sp_keyring::{Ed25519Keyring, Sr25519Keyring};
 use node_runtime::{
 	GenesisConfig, BalancesConfig, SessionConfig, StakingConfig, SystemConfig,
-	GrandpaConfig, IndicesConfig, ContractsConfig, SocietyConfig, WASM_BINARY,
+	GrandpaConfig, IndicesConfig, ContractsConfig, SocietyConfig, EVMConfig, EVM, WASM_BINARY,
 	AccountId,
 };
 use node_runtime::constants::currency::*;
-use sp_core::ChangesTrieConfiguration;
-use sp_runtime::Perbill;
+use node_primitives::Balance;
+use pallet_evm::{Account as EVMAccount, ConvertAccountId, HashTruncateConvertAccountId};
+use sp_core::{ChangesTrieConfiguration, U256};
+use sp_runtime::{Perbill, traits::BlakeTwo256};
 
 /// Create genesis runtime configuration for tests.
 pub fn config(support_changes_trie: bool, code: Option<&[u8]>) -> GenesisConfig {
@@ -53,6 +55,14 @@ pub fn config_endowed(
 		extra_endowed.into_iter().map(|endowed| (endowed, 100*DOLLARS))
 	);
 
+	let evm_accounts = endowed.iter()
+		.map(|(k, _)| (
+			HashTruncateConvertAccountId::<BlakeTwo256>::convert_account_id(k),
+			EVMAccount { nonce: U256::zero(), balance: U256::from(100 * DOLLARS) },
+		))
+		.collect::<Vec<_>>();
+	endowed.push((EVM::account_id(), 100 * DOLLARS * evm_accounts.len() as Balance));
+
 	GenesisConfig {
 		frame_system: Some(SystemConfig {
 			changes_trie_config: if support_changes_trie { Some(ChangesTrieConfiguration {
@@ -117,5 +127,10 @@ pub fn config_endowed(
 			max_members: 999,
 		}),
 		pallet_vesting: Some(Default::default()),
+		pallet_search: Some(Default::default()),
+		pallet_evm: Some(EVMConfig {
+			accounts: evm_accounts,
+			min_gas_price: U256::one(),
+		}),
 	}
 }
@@ -0,0 +1,16 @@
+use sc_cli::{RunCmd, Subcommand};
+use structopt::StructOpt;
+
+#[derive(Debug, StructOpt)]
+pub struct Cli {
+	#[structopt(subcommand)]
+	pub subcommand: Option<Subcommand>,
+
+	#[structopt(flatten)]
+	pub run: RunCmd,
+
+	/// Leave mining to external miners, through the `pow_getWork` and `pow_submitWork` RPC
+	/// methods, instead of mining on a thread of the node.
+	#[structopt(long = "external-miner")]
+	pub external_miner: bool,
+}
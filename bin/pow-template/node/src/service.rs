@@ -0,0 +1,205 @@
+//! Service and ServiceFactory implementation. Specialized wrapper over substrate service.
+
+use std::sync::Arc;
+use std::time::Duration;
+use sc_client::LongestChain;
+use sc_client_api::ExecutorProvider;
+use pow_template_runtime::{self, opaque::Block, RuntimeApi};
+use sc_service::{error::{Error as ServiceError}, AbstractService, Configuration, ServiceBuilder};
+use sp_inherents::InherentDataProviders;
+use sc_executor::native_executor_instance;
+pub use sc_executor::NativeExecutor;
+pub use sc_consensus_pow::hash::{HashPow, Sha3};
+use sc_consensus_pow::rpc::{Pow, PowApi};
+use sc_consensus_pow::worker::MiningWorker;
+use parking_lot::Mutex;
+
+// Our native executor instance.
+native_executor_instance!(
+	pub Executor,
+	pow_template_runtime::api::dispatch,
+	pow_template_runtime::native_version,
+);
+
+/// The number of nonces the miner tries before checking for a new best block.
+const MINING_ROUND: u32 = 10_000;
+
+/// The time the proposer has to build a block.
+const BUILD_TIME: Duration = Duration::from_secs(2);
+
+/// Starts a `ServiceBuilder` for a full service.
+///
+/// Use this macro if you don't actually need the full service, but just the builder in order to
+/// be able to perform chain operations.
+macro_rules! new_full_start {
+	($config:expr) => {{
+		let mut import_setup = None;
+		let inherent_data_providers = sp_inherents::InherentDataProviders::new();
+
+		let builder = sc_service::ServiceBuilder::new_full::<
+			pow_template_runtime::opaque::Block, pow_template_runtime::RuntimeApi, crate::service::Executor
+		>($config)?
+			.with_select_chain(|_config, backend| {
+				Ok(sc_client::LongestChain::new(backend.clone()))
+			})?
+			.with_transaction_pool(|config, client, _fetcher| {
+				let pool_api = sc_transaction_pool::FullChainApi::new(client.clone());
+				Ok(sc_transaction_pool::BasicPool::new(config, std::sync::Arc::new(pool_api)))
+			})?
+			.with_import_queue(|_config, client, select_chain, _transaction_pool| {
+				let algorithm = crate::service::HashPow::<
+					_, _, crate::service::Sha3,
+				>::new(client.clone());
+
+				let pow_block_import = sc_consensus_pow::PowBlockImport::new(
+					client.clone(),
+					client.clone(),
+					algorithm.clone(),
+					0,
+					select_chain,
+					inherent_data_providers.clone(),
+				);
+
+				let import_queue = sc_consensus_pow::import_queue(
+					Box::new(pow_block_import.clone()),
+					algorithm,
+					inherent_data_providers.clone(),
+				)?;
+
+				import_setup = Some(pow_block_import);
+
+				Ok(import_queue)
+			})?;
+
+		(builder, import_setup, inherent_data_providers)
+	}}
+}
+
+/// Builds a new service for a full client, mining on a thread of the node unless
+/// `external_miner` is set.
+pub fn new_full(config: Configuration, external_miner: bool)
+	-> Result<impl AbstractService, ServiceError>
+{
+	let is_authority = config.roles.is_authority();
+
+	// sentry nodes announce themselves as authorities to the network
+	// and should run the same protocols authorities do, but it should
+	// never actively participate in any consensus process.
+	let participates_in_consensus = is_authority && !config.sentry_mode;
+
+	let (builder, mut import_setup, inherent_data_providers) = new_full_start!(config);
+
+	let block_import = import_setup.take()
+		.expect("Block Import is present for Full Services or setup failed before. qed");
+
+	// the work handed out to external miners, with `--external-miner` only.
+	let worker = if external_miner {
+		Some(Arc::new(Mutex::new(MiningWorker::new(
+			HashPow::<_, _, Sha3>::new(builder.client().clone()),
+			Box::new(block_import.clone()),
+		))))
+	} else {
+		None
+	};
+
+	let service = builder
+		.with_rpc_extensions({
+			let worker = worker.clone();
+			move |_builder| -> Result<jsonrpc_core::IoHandler<sc_rpc::Metadata>, _> {
+				let mut io = jsonrpc_core::IoHandler::default();
+				if let Some(worker) = worker {
+					io.extend_with(PowApi::to_delegate(Pow::new(worker)));
+				}
+				Ok(io)
+			}
+		})?
+		.build()?;
+
+	if participates_in_consensus {
+		let proposer = sc_basic_authorship::ProposerFactory::new(
+			service.client(),
+			service.transaction_pool()
+		);
+
+		let client = service.client();
+		let select_chain = service.select_chain()
+			.ok_or(ServiceError::SelectChainRequired)?;
+
+		let can_author_with =
+			sp_consensus::CanAuthorWithNativeVersion::new(client.executor().clone());
+
+		if let Some(worker) = worker {
+			sc_consensus_pow::worker::start_mining_worker(
+				worker,
+				client,
+				proposer,
+				None,
+				service.network(),
+				BUILD_TIME,
+				Some(select_chain),
+				inherent_data_providers,
+				can_author_with,
+			);
+		} else {
+			// the miner runs on its own thread, as mining is CPU-intensive.
+			sc_consensus_pow::start_mine(
+				Box::new(block_import),
+				client.clone(),
+				HashPow::<_, _, Sha3>::new(client),
+				proposer,
+				None,
+				MINING_ROUND,
+				service.network(),
+				BUILD_TIME,
+				Some(select_chain),
+				inherent_data_providers,
+				can_author_with,
+			);
+		}
+	}
+
+	Ok(service)
+}
+
+/// Builds a new service for a light client.
+pub fn new_light(config: Configuration)
+	-> Result<impl AbstractService, ServiceError>
+{
+	let inherent_data_providers = InherentDataProviders::new();
+
+	ServiceBuilder::new_light::<Block, RuntimeApi, Executor>(config)?
+		.with_select_chain(|_config, backend| {
+			Ok(LongestChain::new(backend.clone()))
+		})?
+		.with_transaction_pool(|config, client, fetcher| {
+			let fetcher = fetcher
+				.ok_or_else(|| "Trying to start light transaction pool without active fetcher")?;
+
+			let pool_api = sc_transaction_pool::LightChainApi::new(client.clone(), fetcher.clone());
+			let pool = sc_transaction_pool::BasicPool::with_revalidation_type(
+				config, Arc::new(pool_api), sc_transaction_pool::RevalidationType::Light,
+			);
+			Ok(pool)
+		})?
+		.with_import_queue(|_config, client, select_chain, _transaction_pool| {
+			let algorithm = HashPow::<_, _, Sha3>::new(client.clone());
+
+			let pow_block_import = sc_consensus_pow::PowBlockImport::new(
+				client.clone(),
+				client.clone(),
+				algorithm.clone(),
+				0,
+				select_chain,
+				inherent_data_providers.clone(),
+			);
+
+			let import_queue = sc_consensus_pow::import_queue(
+				Box::new(pow_block_import),
+				algorithm,
+				inherent_data_providers.clone(),
+			)?;
+
+			Ok(import_queue)
+		})?
+		.build()
+}
@@ -96,6 +96,7 @@ fn genesis_constructor(
 		authorities,
 		sudo_account.clone(),
 		Some(endowed_accounts.to_vec()),
+		vec![],
 		enable_println,
 	)
 }
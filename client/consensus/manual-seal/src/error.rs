@@ -20,6 +20,7 @@ use sp_consensus::{Error as ConsensusError, ImportResult};
 use sp_blockchain::Error as BlockchainError;
 use sp_inherents::Error as InherentsError;
 use futures::channel::{oneshot, mpsc::SendError};
+use crate::rpc::MAX_EMPTY_BLOCKS;
 
 /// Error code for rpc
 mod codes {
@@ -30,6 +31,9 @@ mod codes {
 	pub const CONSENSUS_ERROR: i64 = 14_000;
 	pub const INHERENTS_ERROR: i64 = 15_000;
 	pub const BLOCKCHAIN_ERROR: i64 = 16_000;
+	pub const TIME_TRAVEL_UNSUPPORTED: i64 = 17_000;
+	pub const TIME_OVERFLOW: i64 = 18_000;
+	pub const INVALID_BLOCK_COUNT: i64 = 19_000;
 	pub const UNKNOWN_ERROR: i64 = 20_000;
 }
 
@@ -56,6 +60,18 @@ pub enum Error {
 	#[display(fmt = "Supplied parent_hash: {} doesn't exist in chain", _0)]
 	#[from(ignore)]
 	BlockNotFound(String),
+	/// The timestamps of the blocks aren't provided by a `TimestampInherentDataProvider`
+	#[display(fmt = "The timestamps of the blocks can't be moved forward")]
+	#[from(ignore)]
+	TimeTravelUnsupported,
+	/// Moving the timestamps of the blocks forward would overflow them
+	#[display(fmt = "The timestamps of the blocks can't be moved that far forward")]
+	#[from(ignore)]
+	TimeOverflow,
+	/// The number of empty blocks to seal is zero or more than `MAX_EMPTY_BLOCKS`
+	#[display(fmt = "The number of empty blocks to seal must be from 1 to {}", MAX_EMPTY_BLOCKS)]
+	#[from(ignore)]
+	InvalidBlockCount,
 	/// Some string error
 	#[display(fmt = "{}", _0)]
 	#[from(ignore)]
@@ -81,6 +97,9 @@ impl Error {
 			ConsensusError(_) => codes::CONSENSUS_ERROR,
 			InherentError(_) => codes::INHERENTS_ERROR,
 			BlockchainError(_) => codes::BLOCKCHAIN_ERROR,
+			TimeTravelUnsupported => codes::TIME_TRAVEL_UNSUPPORTED,
+			TimeOverflow => codes::TIME_OVERFLOW,
+			InvalidBlockCount => codes::INVALID_BLOCK_COUNT,
 			SendError(_) | Canceled(_) => codes::SERVER_SHUTTING_DOWN,
 			_ => codes::UNKNOWN_ERROR
 		}
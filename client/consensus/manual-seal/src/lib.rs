@@ -16,6 +16,12 @@
 
 //! A manual sealing engine: the engine listens for rpc calls to seal blocks and create forks.
 //! This is suitable for a testing environment.
+//!
+//! Besides the rpc calls, blocks can be sealed as soon as a transaction is ready in the
+//! transaction pool (instant seal), or at a fixed interval (interval seal).
+//!
+//! For tests depending on time or on the state, the rpc calls also seal several empty blocks at
+//! once, set storage values, and move the timestamps of the blocks forward.
 
 use sp_consensus::{
 	self, BlockImport, Environment, Proposer, BlockCheckParams,
@@ -29,21 +35,26 @@ use sp_consensus::{
 	},
 };
 use sp_inherents::InherentDataProviders;
-use sp_runtime::{traits::Block as BlockT, Justification};
-use sc_client_api::backend::Backend as ClientBackend;
+use sp_runtime::{traits::{Block as BlockT, HashFor}, Justification};
+use sc_client_api::backend::{Backend as ClientBackend, StateBackend};
+use sp_state_machine::backend::Consolidate;
 use futures::prelude::*;
 use sc_transaction_pool::txpool;
 use std::collections::HashMap;
 use std::sync::Arc;
+use std::time::Duration;
 
 pub mod rpc;
 mod error;
 mod finalize_block;
 mod seal_new_block;
+mod timestamp;
 use finalize_block::{finalize_block, FinalizeBlockParams};
 use seal_new_block::{seal_new_block, SealBlockParams};
 pub use error::Error;
 pub use rpc::{EngineCommand, CreatedBlock};
+pub use seal_new_block::StorageChanges;
+pub use timestamp::TimestampInherentDataProvider;
 
 /// The synchronous block-import worker of the engine.
 pub struct ManualSealBlockImport<I> {
@@ -100,7 +111,12 @@ impl<B: BlockT> Verifier<B> for ManualSealVerifier {
 }
 
 /// Instantiate the import queue for the manual seal consensus engine.
-pub fn import_queue<B: BlockT>(block_import: BoxBlockImport<B, ()>) -> BasicQueue<B, ()>
+pub fn import_queue<B, Transaction>(
+	block_import: BoxBlockImport<B, Transaction>,
+) -> BasicQueue<B, Transaction>
+	where
+		B: BlockT,
+		Transaction: Send + 'static,
 {
 	BasicQueue::new(
 		ManualSealVerifier,
@@ -123,8 +139,11 @@ pub async fn run_manual_seal<B, CB, E, A, C, S, T>(
 	where
 		B: BlockT + 'static,
 		CB: ClientBackend<B> + 'static,
+		CB::State: StateBackend<HashFor<B>, Transaction = T>,
+		T: Consolidate + Default + Send + 'static,
 		E: Environment<B> + 'static,
 		E::Error: std::fmt::Display,
+		E::Proposer: Proposer<B, Transaction = T>,
 		<E::Proposer as Proposer<B>>::Error: std::fmt::Display,
 		A: txpool::ChainApi<Block=B, Hash=<B as BlockT>::Hash> + 'static,
 		S: Stream<Item=EngineCommand<<B as BlockT>::Hash>> + Unpin + 'static,
@@ -136,14 +155,58 @@ pub async fn run_manual_seal<B, CB, E, A, C, S, T>(
 				create_empty,
 				finalize,
 				parent_hash,
-				sender,
+				mut sender,
 			} => {
-				seal_new_block(
+				let result = seal_new_block(
 					SealBlockParams {
-						sender,
 						parent_hash,
 						finalize,
 						create_empty,
+						storage_changes: Vec::new(),
+						env: &mut env,
+						select_chain: &select_chain,
+						block_import: &mut block_import,
+						inherent_data_provider: &inherent_data_providers,
+						pool: pool.clone(),
+						backend: backend.clone(),
+					}
+				).await;
+				rpc::send_result(&mut sender, result);
+			}
+			EngineCommand::SealEmptyBlocks { count, finalize, mut sender } => {
+				let mut result = Err(Error::InvalidBlockCount);
+				if count > rpc::MAX_EMPTY_BLOCKS {
+					rpc::send_result(&mut sender, result);
+					continue
+				}
+				for _ in 0..count {
+					result = seal_new_block(
+						SealBlockParams {
+							parent_hash: None,
+							finalize,
+							create_empty: true,
+							storage_changes: Vec::new(),
+							env: &mut env,
+							select_chain: &select_chain,
+							block_import: &mut block_import,
+							inherent_data_provider: &inherent_data_providers,
+							pool: pool.clone(),
+							backend: backend.clone(),
+						}
+					).await;
+					if result.is_err() {
+						break
+					}
+				}
+				rpc::send_result(&mut sender, result);
+			}
+			EngineCommand::SetStorage { changes, finalize, mut sender } => {
+				let result = seal_new_block(
+					SealBlockParams {
+						parent_hash: None,
+						finalize,
+						create_empty: true,
+						storage_changes: changes,
 						env: &mut env,
 						select_chain: &select_chain,
 						block_import: &mut block_import,
@@ -152,6 +215,7 @@ pub async fn run_manual_seal<B, CB, E, A, C, S, T>(
 						backend: backend.clone(),
 					}
 				).await;
+				rpc::send_result(&mut sender, result);
 			}
 			EngineCommand::FinalizeBlock { hash, sender, justification } => {
 				finalize_block(
@@ -167,6 +231,44 @@ pub async fn run_manual_seal<B, CB, E, A, C, S, T>(
 	}
 }
 
+/// The commands of the instant seal engine: a new block for every transaction imported into
+/// the ready queue of the transaction pool.
+pub fn instant_seal_commands<B, A>(
+	pool: &txpool::Pool<A>,
+) -> impl Stream<Item=EngineCommand<<B as BlockT>::Hash>> + Unpin
+	where
+		B: BlockT,
+		A: txpool::ChainApi<Block=B, Hash=<B as BlockT>::Hash>,
+{
+	pool.validated_pool().import_notification_stream()
+		.map(|_| {
+			EngineCommand::SealNewBlock {
+				create_empty: false,
+				finalize: false,
+				parent_hash: None,
+				sender: None,
+			}
+		})
+}
+
+/// The commands of the interval seal engine: a new block every `interval`, empty if there are
+/// no transactions in the transaction pool.
+pub fn interval_seal_commands<Hash>(
+	interval: Duration,
+) -> impl Stream<Item=EngineCommand<Hash>> + Unpin {
+	stream::unfold((), move |()| {
+		futures_timer::Delay::new(interval).map(|()| {
+			let command = EngineCommand::SealNewBlock {
+				create_empty: true,
+				finalize: false,
+				parent_hash: None,
+				sender: None,
+			};
+			Some((command, ()))
+		})
+	})
+}
+
 /// runs the background authorship task for the instant seal engine.
 /// instant-seal creates a new block for every transaction imported into
 /// the transaction pool.
@@ -182,22 +284,17 @@ pub async fn run_instant_seal<B, CB, E, A, C, T>(
 		A: txpool::ChainApi<Block=B, Hash=<B as BlockT>::Hash> + 'static,
 		B: BlockT + 'static,
 		CB: ClientBackend<B> + 'static,
+		CB::State: StateBackend<HashFor<B>, Transaction = T>,
+		T: Consolidate + Default + Send + 'static,
 		E: Environment<B> + 'static,
 		E::Error: std::fmt::Display,
+		E::Proposer: Proposer<B, Transaction = T>,
 		<E::Proposer as Proposer<B>>::Error: std::fmt::Display,
 		C: SelectChain<B> + 'static
 {
 	// instant-seal creates blocks as soon as transactions are imported
 	// into the transaction pool.
-	let seal_block_channel = pool.validated_pool().import_notification_stream()
-		.map(|_| {
-			EngineCommand::SealNewBlock {
-				create_empty: false,
-				finalize: false,
-				parent_hash: None,
-				sender: None,
-			}
-		});
+	let seal_block_channel = instant_seal_commands(&pool);
 
 	run_manual_seal(
 		block_import,
@@ -210,6 +307,41 @@ pub async fn run_instant_seal<B, CB, E, A, C, T>(
 	).await
 }
 
+/// runs the background authorship task for the interval seal engine.
+/// interval-seal creates a new block every `interval`, whether or not
+/// there are transactions in the transaction pool.
+pub async fn run_interval_seal<B, CB, E, A, C, T>(
+	block_import: BoxBlockImport<B, T>,
+	env: E,
+	backend: Arc<CB>,
+	pool: Arc<txpool::Pool<A>>,
+	select_chain: C,
+	inherent_data_providers: InherentDataProviders,
+	interval: Duration,
+)
+	where
+		A: txpool::ChainApi<Block=B, Hash=<B as BlockT>::Hash> + 'static,
+		B: BlockT + 'static,
+		CB: ClientBackend<B> + 'static,
+		CB::State: StateBackend<HashFor<B>, Transaction = T>,
+		T: Consolidate + Default + Send + 'static,
+		E: Environment<B> + 'static,
+		E::Error: std::fmt::Display,
+		E::Proposer: Proposer<B, Transaction = T>,
+		<E::Proposer as Proposer<B>>::Error: std::fmt::Display,
+		C: SelectChain<B> + 'static
+{
+	run_manual_seal(
+		block_import,
+		env,
+		backend,
+		pool,
+		interval_seal_commands(interval),
+		select_chain,
+		inherent_data_providers,
+	).await
+}
+
 #[cfg(test)]
 mod tests {
 	use super::*;
@@ -225,8 +357,9 @@ mod tests {
 	};
 	use substrate_test_runtime_transaction_pool::{TestApi, uxt};
 	use sp_transaction_pool::{TransactionPool, MaintainedTransactionPool, TransactionSource};
-	use sp_runtime::generic::BlockId;
+	use sp_runtime::{generic::BlockId, traits::Header as _};
 	use sp_blockchain::HeaderBackend;
+	use sc_client_api::BlockchainEvents;
 	use sp_consensus::ImportedAux;
 	use sc_client::LongestChain;
 	use sp_inherents::InherentDataProviders;
@@ -303,6 +436,54 @@ mod tests {
 		assert!(backend.blockchain().header(BlockId::Number(1)).unwrap().is_some())
 	}
 
+	#[tokio::test]
+	async fn interval_seal() {
+		let builder = TestClientBuilder::new();
+		let backend = builder.backend();
+		let client = Arc::new(builder.build());
+		let select_chain = LongestChain::new(backend.clone());
+		let inherent_data_providers = InherentDataProviders::new();
+		let pool = Arc::new(BasicPool::new(Options::default(), api()).0);
+		let env = ProposerFactory::new(
+			client.clone(),
+			pool.clone()
+		);
+		let mut imported = client.import_notification_stream();
+		// this test checks that blocks are created at an interval, even with an empty pool.
+		let future = run_interval_seal(
+			Box::new(client.clone()),
+			env,
+			backend.clone(),
+			pool.pool().clone(),
+			select_chain,
+			inherent_data_providers,
+			Duration::from_millis(100),
+		);
+		std::thread::spawn(|| {
+			let mut rt = tokio::runtime::Runtime::new().unwrap();
+			// spawn the background authorship task
+			rt.block_on(future);
+		});
+		// wait for the second (empty) block to be imported, but not forever.
+		let second_block = async {
+			while let Some(notification) = imported.next().await {
+				if *notification.header.number() == 2 {
+					return;
+				}
+			}
+			panic!("the engine stopped before sealing a second block");
+		};
+		futures::pin_mut!(second_block);
+		let timeout = futures_timer::Delay::new(Duration::from_secs(30));
+		let sealed = futures::future::select(second_block, timeout).await;
+		if let futures::future::Either::Right(_) = sealed {
+			panic!("no second block was sealed in time");
+		}
+		// assert that there are new (empty) blocks in the db.
+		let header = backend.blockchain().header(BlockId::Number(2)).unwrap();
+		assert!(header.is_some());
+	}
+
 	#[tokio::test]
 	async fn manual_seal_and_finalization() {
 		let builder = TestClientBuilder::new();
@@ -371,6 +552,69 @@ mod tests {
 		assert_eq!(rx.await.unwrap().unwrap(), ());
 	}
 
+	#[tokio::test]
+	async fn empty_blocks_and_storage_changes() {
+		let builder = TestClientBuilder::new();
+		let backend = builder.backend();
+		let client = Arc::new(builder.build());
+		let select_chain = LongestChain::new(backend.clone());
+		let inherent_data_providers = InherentDataProviders::new();
+		let pool = Arc::new(BasicPool::new(Options::default(), api()).0);
+		let env = ProposerFactory::new(
+			client.clone(),
+			pool.clone()
+		);
+		// this test checks that several blocks are created at once, and that storage changes
+		// are applied to the state.
+		let (mut sink, stream) = futures::channel::mpsc::channel(1024);
+		let future = run_manual_seal(
+			Box::new(client.clone()),
+			env,
+			backend.clone(),
+			pool.pool().clone(),
+			stream,
+			select_chain,
+			inherent_data_providers,
+		);
+		std::thread::spawn(|| {
+			let mut rt = tokio::runtime::Runtime::new().unwrap();
+			// spawn the background authorship task
+			rt.block_on(future);
+		});
+		// too many blocks are rejected before any is sealed.
+		let (tx, rx) = futures::channel::oneshot::channel();
+		sink.send(EngineCommand::SealEmptyBlocks {
+			count: rpc::MAX_EMPTY_BLOCKS + 1,
+			finalize: false,
+			sender: Some(tx),
+		}).await.unwrap();
+		assert_matches::assert_matches!(rx.await.unwrap(), Err(Error::InvalidBlockCount));
+		assert_eq!(backend.blockchain().info().best_number, 0);
+
+		let (tx, rx) = futures::channel::oneshot::channel();
+		sink.send(EngineCommand::SealEmptyBlocks {
+			count: 3,
+			finalize: false,
+			sender: Some(tx),
+		}).await.unwrap();
+		let created_block = rx.await.unwrap().unwrap();
+		// assert that the last block created is the third one.
+		let header = backend.blockchain().header(BlockId::Number(3)).unwrap().unwrap();
+		assert_eq!(created_block.hash, header.hash());
+
+		let (tx, rx) = futures::channel::oneshot::channel();
+		sink.send(EngineCommand::SetStorage {
+			changes: vec![(b"key".to_vec(), Some(b"value".to_vec()))],
+			finalize: false,
+			sender: Some(tx),
+		}).await.unwrap();
+		let created_block = rx.await.unwrap().unwrap();
+		// assert that the value is in the state of the new block.
+		let state = backend.state_at(BlockId::Hash(created_block.hash)).unwrap();
+		assert_eq!(state.storage(b"key").unwrap(), Some(b"value".to_vec()));
+		assert!(backend.state_at(BlockId::Number(3)).unwrap().storage(b"key").unwrap().is_none());
+	}
+
 	#[tokio::test]
 	async fn manual_seal_fork_blocks() {
 		let builder = TestClientBuilder::new();
@@ -25,8 +25,15 @@ use futures::{
 	SinkExt
 };
 use serde::{Deserialize, Serialize};
+use sp_core::Bytes;
 use sp_runtime::Justification;
+use crate::{StorageChanges, TimestampInherentDataProvider};
 pub use self::gen_client::Client as ManualSealClient;
+pub use self::manual_seal_unsafe::gen_client::Client as ManualSealUnsafeClient;
+pub use self::manual_seal_unsafe::ManualSealUnsafeApi;
+
+/// The maximum number of empty blocks sealed by a single `SealEmptyBlocks` command.
+pub const MAX_EMPTY_BLOCKS: u32 = 1_000;
 
 /// Future's type for jsonrpc
 type FutureResult<T> = Box<dyn jsonrpc_core::futures::Future<Item = T, Error = Error> + Send>;
@@ -52,6 +59,24 @@ pub enum EngineCommand<Hash> {
 		/// sender to report errors/success to the rpc.
 		sender: Sender<CreatedBlock<Hash>>,
 	},
+	/// Tells the engine to seal `count` empty blocks, each on top of the previous one.
+	SealEmptyBlocks {
+		/// the number of blocks to seal, at least one and at most `MAX_EMPTY_BLOCKS`.
+		count: u32,
+		/// instantly finalize the blocks?
+		finalize: bool,
+		/// sender to report the last block sealed, or the first error, to the rpc.
+		sender: Sender<CreatedBlock<Hash>>,
+	},
+	/// Tells the engine to seal an empty block, with the supplied changes to the storage.
+	SetStorage {
+		/// the keys to set, and their new values.
+		changes: StorageChanges,
+		/// instantly finalize this block?
+		finalize: bool,
+		/// sender to report errors/success to the rpc.
+		sender: Sender<CreatedBlock<Hash>>,
+	},
 	/// Tells the engine to finalize the block with the supplied hash
 	FinalizeBlock {
 		/// hash of the block
@@ -82,11 +107,51 @@ pub trait ManualSealApi<Hash> {
 		hash: Hash,
 		justification: Option<Justification>
 	) -> FutureResult<bool>;
+
+	/// Instructs the manual-seal authorship task to create `count` empty blocks, which moves
+	/// the block number `count` blocks forward. Returns the last block created.
+	///
+	/// `count` must be at least one and at most `MAX_EMPTY_BLOCKS`.
+	#[rpc(name = "engine_createEmptyBlocks")]
+	fn create_empty_blocks(
+		&self,
+		count: u32,
+		finalize: bool
+	) -> FutureResult<CreatedBlock<Hash>>;
+
+	/// Moves the timestamps of the blocks created from now on `millis` milliseconds forward.
+	/// Returns how far the timestamps have been moved forward in total, or an error, without
+	/// moving them, if that overflows.
+	#[rpc(name = "engine_advanceTime")]
+	fn advance_time(&self, millis: u64) -> Result<u64, Error>;
+}
+
+mod manual_seal_unsafe {
+	use super::*;
+
+	/// RPC trait that provides the methods of the manual-seal authorship task which seal blocks
+	/// that aren't the result of executing them.
+	///
+	/// Other nodes can't import these blocks, so only expose these methods on nodes running
+	/// without peers.
+	#[rpc]
+	pub trait ManualSealUnsafeApi<Hash> {
+		/// Instructs the manual-seal authorship task to create an empty block setting the given
+		/// storage keys to the given values, or removing the keys without a value. The blocks
+		/// created afterwards see the new values.
+		#[rpc(name = "engine_setStorage")]
+		fn set_storage(
+			&self,
+			changes: Vec<(Bytes, Option<Bytes>)>,
+			finalize: bool
+		) -> FutureResult<CreatedBlock<Hash>>;
+	}
 }
 
-/// A struct that implements the [`ManualSealApi`].
+/// A struct that implements the [`ManualSealApi`] and the [`ManualSealUnsafeApi`].
 pub struct ManualSeal<Hash> {
 	import_block_channel: mpsc::Sender<EngineCommand<Hash>>,
+	timestamp: Option<TimestampInherentDataProvider>,
 }
 
 /// return type of `engine_createBlock`
@@ -101,7 +166,13 @@ pub struct CreatedBlock<Hash> {
 impl<Hash> ManualSeal<Hash> {
 	/// Create new `ManualSeal` with the given reference to the client.
 	pub fn new(import_block_channel: mpsc::Sender<EngineCommand<Hash>>) -> Self {
-		Self { import_block_channel }
+		Self { import_block_channel, timestamp: None }
+	}
+
+	/// Move the timestamps given by `timestamp` forward on `engine_advanceTime`.
+	pub fn with_timestamp(mut self, timestamp: TimestampInherentDataProvider) -> Self {
+		self.timestamp = Some(timestamp);
+		self
 	}
 }
 
@@ -141,6 +212,48 @@ impl<Hash: Send + 'static> ManualSealApi<Hash> for ManualSeal<Hash> {
 
 		Box::new(future.boxed().map_err(Error::from).compat())
 	}
+
+	fn create_empty_blocks(&self, count: u32, finalize: bool) -> FutureResult<CreatedBlock<Hash>> {
+		let mut sink = self.import_block_channel.clone();
+		let future = async move {
+			let (sender, receiver) = oneshot::channel();
+			sink.send(
+				EngineCommand::SealEmptyBlocks { count, finalize, sender: Some(sender) }
+			).await?;
+
+			receiver.await?
+		};
+
+		Box::new(future.boxed().map_err(Error::from).compat())
+	}
+
+	fn advance_time(&self, millis: u64) -> Result<u64, Error> {
+		let timestamp = self.timestamp.as_ref().ok_or(crate::Error::TimeTravelUnsupported)?;
+		timestamp.advance(millis).ok_or_else(|| crate::Error::TimeOverflow.into())
+	}
+}
+
+impl<Hash: Send + 'static> ManualSealUnsafeApi<Hash> for ManualSeal<Hash> {
+	fn set_storage(
+		&self,
+		changes: Vec<(Bytes, Option<Bytes>)>,
+		finalize: bool
+	) -> FutureResult<CreatedBlock<Hash>> {
+		let mut sink = self.import_block_channel.clone();
+		let changes = changes.into_iter()
+			.map(|(key, value)| (key.0, value.map(|value| value.0)))
+			.collect();
+		let future = async move {
+			let (sender, receiver) = oneshot::channel();
+			sink.send(
+				EngineCommand::SetStorage { changes, finalize, sender: Some(sender) }
+			).await?;
+
+			receiver.await?
+		};
+
+		Box::new(future.boxed().map_err(Error::from).compat())
+	}
 }
 
 /// report any errors or successes encountered by the authorship task back
@@ -19,7 +19,7 @@
 use crate::{Error, rpc};
 use std::sync::Arc;
 use sp_runtime::{
-	traits::{Block as BlockT, Header as HeaderT},
+	traits::{Block as BlockT, Header as HeaderT, HashFor},
 	generic::BlockId,
 };
 use futures::prelude::*;
@@ -33,7 +33,8 @@ use sp_consensus::{
 	import_queue::BoxBlockImport,
 };
 use sp_blockchain::HeaderBackend;
-use sc_client_api::backend::Backend as ClientBackend;
+use sc_client_api::backend::{Backend as ClientBackend, StateBackend};
+use sp_state_machine::backend::Consolidate;
 use std::collections::HashMap;
 use std::time::Duration;
 use sp_inherents::InherentDataProviders;
@@ -41,6 +42,9 @@ use sp_inherents::InherentDataProviders;
 /// max duration for creating a proposal in secs
 const MAX_PROPOSAL_DURATION: u64 = 10;
 
+/// changes to the storage, a value of `None` meaning the key is removed.
+pub type StorageChanges = Vec<(Vec<u8>, Option<Vec<u8>>)>;
+
 /// params for sealing a new block
 pub struct SealBlockParams<'a, B: BlockT, C, CB, E, T, P: txpool::ChainApi> {
 	/// if true, empty blocks(without extrinsics) will be created.
@@ -50,8 +54,8 @@ pub struct SealBlockParams<'a, B: BlockT, C, CB, E, T, P: txpool::ChainApi> {
 	pub finalize: bool,
 	/// specify the parent hash of the about-to-created block
 	pub parent_hash: Option<<B as BlockT>::Hash>,
-	/// sender to report errors/success to the rpc.
-	pub sender: rpc::Sender<CreatedBlock<<B as BlockT>::Hash>>,
+	/// storage changes applied on top of the changes of the block.
+	pub storage_changes: StorageChanges,
 	/// transaction pool
 	pub pool: Arc<txpool::Pool<P>>,
 	/// client backend
@@ -73,66 +77,91 @@ pub async fn seal_new_block<B, SC, CB, E, T, P>(
 		finalize,
 		pool,
 		parent_hash,
+		storage_changes,
 		backend: back_end,
 		select_chain,
 		block_import,
 		env,
 		inherent_data_provider,
-		mut sender,
 		..
 	}: SealBlockParams<'_, B, SC, CB, E, T, P>
-)
+) -> Result<CreatedBlock<<B as BlockT>::Hash>, Error>
 	where
 		B: BlockT,
 		CB: ClientBackend<B>,
+		CB::State: StateBackend<HashFor<B>, Transaction = T>,
+		T: Consolidate + Default + Send + 'static,
 		E: Environment<B>,
 		<E as Environment<B>>::Error: std::fmt::Display,
+		E::Proposer: Proposer<B, Transaction = T>,
 		<E::Proposer as Proposer<B>>::Error: std::fmt::Display,
 		P: txpool::ChainApi<Block=B, Hash=<B as BlockT>::Hash>,
 		SC: SelectChain<B>,
 {
-	let future = async {
-		if pool.validated_pool().status().ready == 0 && !create_empty {
-			return Err(Error::EmptyTransactionPool)
-		}
+	if pool.validated_pool().status().ready == 0 && !create_empty {
+		return Err(Error::EmptyTransactionPool)
+	}
 
-		// get the header to build this new block on.
-		// use the parent_hash supplied via `EngineCommand`
-		// or fetch the best_block.
-		let header = match parent_hash {
-			Some(hash) => {
-				match back_end.blockchain().header(BlockId::Hash(hash))? {
-					Some(header) => header,
-					None => return Err(Error::BlockNotFound(format!("{}", hash))),
-				}
+	// get the header to build this new block on.
+	// use the parent_hash supplied via `EngineCommand`
+	// or fetch the best_block.
+	let header = match parent_hash {
+		Some(hash) => {
+			match back_end.blockchain().header(BlockId::Hash(hash))? {
+				Some(header) => header,
+				None => return Err(Error::BlockNotFound(format!("{}", hash))),
 			}
-			None => select_chain.best_chain()?
-		};
-
-		let mut proposer = env.init(&header)
-			.map_err(|err| Error::StringError(format!("{}", err))).await?;
-		let id = inherent_data_provider.create_inherent_data()?;
-		let inherents_len = id.len();
-		let proposal = proposer.propose(id, Default::default(), Duration::from_secs(MAX_PROPOSAL_DURATION), false.into())
-			.map_err(|err| Error::StringError(format!("{}", err))).await?;
-
-		if proposal.block.extrinsics().len() == inherents_len && !create_empty {
-			return Err(Error::EmptyTransactionPool)
 		}
+		None => select_chain.best_chain()?
+	};
+
+	let mut proposer = env.init(&header)
+		.map_err(|err| Error::StringError(format!("{}", err))).await?;
+	let id = inherent_data_provider.create_inherent_data()?;
+	let inherents_len = id.len();
+	let proposal = proposer.propose(
+		id,
+		Default::default(),
+		Duration::from_secs(MAX_PROPOSAL_DURATION),
+		false.into(),
+	).map_err(|err| Error::StringError(format!("{}", err))).await?;
 
-		let (header, body) = proposal.block.deconstruct();
-		let mut params = BlockImportParams::new(BlockOrigin::Own, header.clone());
-		params.body = Some(body);
-		params.finalized = finalize;
-		params.fork_choice = Some(ForkChoiceStrategy::LongestChain);
-
-		match block_import.import_block(params, HashMap::new())? {
-			ImportResult::Imported(aux) => {
-				Ok(CreatedBlock { hash: <B as BlockT>::Header::hash(&header), aux })
-			},
-			other => Err(other.into()),
+	if proposal.block.extrinsics().len() == inherents_len && !create_empty {
+		return Err(Error::EmptyTransactionPool)
+	}
+
+	let (mut header, body) = proposal.block.deconstruct();
+	let mut block_changes = proposal.storage_changes;
+	if !storage_changes.is_empty() {
+		if !block_changes.child_storage_changes.is_empty() {
+			return Err(Error::StringError(
+				"Storage changes can't be applied to a block changing child storage".into()
+			))
 		}
-	};
+		// the state root of the block is recomputed with the storage changes,
+		// so the block can't be re-executed, only imported with its changes.
+		block_changes.main_storage_changes
+			.retain(|(key, _)| storage_changes.iter().all(|(changed, _)| changed != key));
+		block_changes.main_storage_changes.extend(storage_changes);
+		let state = back_end.state_at(BlockId::Hash(*header.parent_hash()))?;
+		let (root, transaction) = state.storage_root(
+			block_changes.main_storage_changes.iter().cloned()
+		);
+		block_changes.transaction = transaction;
+		block_changes.transaction_storage_root = root;
+		header.set_state_root(root);
+	}
+
+	let mut params = BlockImportParams::new(BlockOrigin::Own, header.clone());
+	params.body = Some(body);
+	params.storage_changes = Some(block_changes);
+	params.finalized = finalize;
+	params.fork_choice = Some(ForkChoiceStrategy::LongestChain);
 
-	rpc::send_result(&mut sender, future.await)
+	match block_import.import_block(params, HashMap::new())? {
+		ImportResult::Imported(aux) => {
+			Ok(CreatedBlock { hash: <B as BlockT>::Header::hash(&header), aux })
+		},
+		other => Err(other.into()),
+	}
 }
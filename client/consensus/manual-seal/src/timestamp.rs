@@ -0,0 +1,136 @@
+// Copyright 2020 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Timestamp inherent data for manually sealed blocks.
+
+use std::sync::Arc;
+use std::time::SystemTime;
+use parking_lot::Mutex;
+use sp_inherents::{InherentData, InherentIdentifier, ProvideInherentData};
+use sp_timestamp::{InherentError, InherentType, INHERENT_IDENTIFIER};
+
+/// Provides the timestamp inherent of manually sealed blocks.
+///
+/// The timestamp is the current time, unless blocks are sealed in quick succession: each block
+/// is then given the timestamp of the previous one plus `minimum_period`, which the timestamp
+/// pallet requires between blocks. The timestamps can also be moved forward with `advance`.
+#[derive(Clone)]
+pub struct TimestampInherentDataProvider {
+	minimum_period: InherentType,
+	clock: Arc<Mutex<Clock>>,
+}
+
+#[derive(Default)]
+struct Clock {
+	/// the timestamp of the last block.
+	last: InherentType,
+	/// how far the timestamps have been moved forward.
+	offset: InherentType,
+}
+
+impl TimestampInherentDataProvider {
+	/// Create a new provider, for blocks at least `minimum_period` milliseconds apart.
+	pub fn new(minimum_period: InherentType) -> Self {
+		Self { minimum_period, clock: Default::default() }
+	}
+
+	/// Give the next blocks timestamps after `timestamp`, usually the timestamp of the best
+	/// block, which may be ahead of the current time if the timestamps have been moved forward.
+	pub fn with_last_timestamp(self, timestamp: InherentType) -> Self {
+		self.clock.lock().last = timestamp;
+		self
+	}
+
+	/// Move the timestamps of the next blocks `millis` milliseconds forward.
+	///
+	/// Returns how far the timestamps have been moved forward in total, or `None`, without moving
+	/// them, if that overflows.
+	pub fn advance(&self, millis: InherentType) -> Option<InherentType> {
+		let mut clock = self.clock.lock();
+		clock.offset = clock.offset.checked_add(millis)?;
+		Some(clock.offset)
+	}
+}
+
+impl ProvideInherentData for TimestampInherentDataProvider {
+	fn inherent_identifier(&self) -> &'static InherentIdentifier {
+		&INHERENT_IDENTIFIER
+	}
+
+	fn provide_inherent_data(
+		&self,
+		inherent_data: &mut InherentData,
+	) -> Result<(), sp_inherents::Error> {
+		let now = SystemTime::now().duration_since(SystemTime::UNIX_EPOCH)
+			.map_err(|_| sp_inherents::Error::from("Current time is before unix epoch"))?
+			.as_millis() as InherentType;
+
+		let mut clock = self.clock.lock();
+		let now = now.saturating_add(clock.offset);
+		let next = clock.last.saturating_add(self.minimum_period);
+		clock.last = if clock.last == 0 || now >= next { now } else { next };
+		inherent_data.put_data(INHERENT_IDENTIFIER, &clock.last)
+	}
+
+	fn error_to_string(&self, error: &[u8]) -> Option<String> {
+		InherentError::try_from(&INHERENT_IDENTIFIER, error).map(|e| format!("{:?}", e))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn timestamp(provider: &TimestampInherentDataProvider) -> InherentType {
+		let mut inherent_data = InherentData::new();
+		provider.provide_inherent_data(&mut inherent_data).unwrap();
+		inherent_data.get_data(&INHERENT_IDENTIFIER).unwrap().unwrap()
+	}
+
+	#[test]
+	fn timestamps_are_a_minimum_period_apart() {
+		let provider = TimestampInherentDataProvider::new(60_000);
+		let first = timestamp(&provider);
+		assert_eq!(timestamp(&provider), first + 60_000);
+		assert_eq!(timestamp(&provider), first + 120_000);
+	}
+
+	#[test]
+	fn timestamps_can_be_moved_forward() {
+		let provider = TimestampInherentDataProvider::new(1);
+		let first = timestamp(&provider);
+		assert_eq!(provider.advance(3_600_000), Some(3_600_000));
+		assert_eq!(provider.advance(3_600_000), Some(7_200_000));
+		assert_eq!(provider.advance(InherentType::max_value()), None);
+		assert!(timestamp(&provider) >= first + 7_200_000);
+		assert_eq!(provider.advance(0), Some(7_200_000));
+	}
+
+	#[test]
+	fn timestamps_do_not_overflow() {
+		let provider = TimestampInherentDataProvider::new(1_000)
+			.with_last_timestamp(InherentType::max_value() - 1);
+		assert_eq!(timestamp(&provider), InherentType::max_value());
+		assert_eq!(timestamp(&provider), InherentType::max_value());
+	}
+
+	#[test]
+	fn timestamps_follow_the_last_timestamp() {
+		let last = 10 * 365 * 24 * 3_600_000 + timestamp(&TimestampInherentDataProvider::new(1));
+		let provider = TimestampInherentDataProvider::new(1_000).with_last_timestamp(last);
+		assert_eq!(timestamp(&provider), last + 1_000);
+	}
+}
@@ -0,0 +1,215 @@
+// Copyright 2020 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Reference PoW algorithms, hashing the pre-hash of a block with a nonce.
+//!
+//! The work of a seal is the hash of the pre-hash and the nonce. It meets a difficulty when the
+//! work, as a big-endian integer, times the difficulty does not overflow, that is when the work
+//! is below `2^256 / difficulty`. The difficulty is read from the runtime through
+//! `DifficultyApi`, so that it can be adjusted on chain, for example by `pallet-difficulty`.
+
+use std::marker::PhantomData;
+use std::sync::Arc;
+use sha3::{Digest, Sha3_256};
+use sp_core::{U256, H256, hashing::blake2_256};
+use sp_api::ProvideRuntimeApi;
+use sp_runtime::generic::BlockId;
+use sp_runtime::traits::Block as BlockT;
+use sp_consensus_pow::{DifficultyApi, Seal};
+use codec::{Encode, Decode};
+use crate::{Error, PowAlgorithm};
+
+/// A hash function to do proof of work with.
+pub trait PowHash {
+	/// Hash `data`.
+	fn hash(data: &[u8]) -> H256;
+}
+
+/// SHA3-256.
+#[derive(Clone, Copy, Debug)]
+pub struct Sha3;
+
+impl PowHash for Sha3 {
+	fn hash(data: &[u8]) -> H256 {
+		H256::from_slice(Sha3_256::digest(data).as_slice())
+	}
+}
+
+/// BLAKE2b-256.
+#[derive(Clone, Copy, Debug)]
+pub struct Blake2;
+
+impl PowHash for Blake2 {
+	fn hash(data: &[u8]) -> H256 {
+		H256::from(blake2_256(data))
+	}
+}
+
+/// The seal of a block mined with a hash.
+#[derive(Clone, PartialEq, Eq, Encode, Decode, Debug)]
+pub struct HashSeal {
+	/// The difficulty the block was mined at.
+	pub difficulty: U256,
+	/// The hash of the pre-hash and the nonce.
+	pub work: H256,
+	/// The nonce.
+	pub nonce: H256,
+}
+
+impl HashSeal {
+	/// The seal of `pre_hash` with `nonce`, at `difficulty`.
+	pub fn new<H: PowHash>(pre_hash: &H256, nonce: H256, difficulty: U256) -> Self {
+		Self { difficulty, work: work::<H>(pre_hash, &nonce), nonce }
+	}
+
+	/// Whether the seal holds the work of `pre_hash`, and the work meets its difficulty.
+	pub fn is_valid<H: PowHash>(&self, pre_hash: &H256) -> bool {
+		meets_difficulty(&self.work, self.difficulty)
+			&& self.work == work::<H>(pre_hash, &self.nonce)
+	}
+}
+
+/// The work of `pre_hash` with `nonce`.
+pub fn work<H: PowHash>(pre_hash: &H256, nonce: &H256) -> H256 {
+	H::hash(&(pre_hash, nonce).encode())
+}
+
+/// Whether `work` meets `difficulty`.
+pub fn meets_difficulty(work: &H256, difficulty: U256) -> bool {
+	let (_, overflowed) = U256::from_big_endian(work.as_bytes()).overflowing_mul(difficulty);
+	!overflowed
+}
+
+/// Try `round` random nonces, returning the seal of the first one meeting `difficulty`.
+pub fn mine<H: PowHash>(pre_hash: &H256, difficulty: U256, round: u32) -> Option<HashSeal> {
+	(0..round)
+		.map(|_| HashSeal::new::<H>(pre_hash, H256::from(rand::random::<[u8; 32]>()), difficulty))
+		.find(|seal| meets_difficulty(&seal.work, difficulty))
+}
+
+/// A PoW algorithm hashing with `H`, at the difficulty given by the runtime.
+pub struct HashPow<B, C, H> {
+	client: Arc<C>,
+	_marker: PhantomData<(B, H)>,
+}
+
+impl<B, C, H> HashPow<B, C, H> {
+	/// Create a new algorithm, reading the difficulty from `client`.
+	pub fn new(client: Arc<C>) -> Self {
+		Self { client, _marker: PhantomData }
+	}
+}
+
+impl<B, C, H> Clone for HashPow<B, C, H> {
+	fn clone(&self) -> Self {
+		Self::new(self.client.clone())
+	}
+}
+
+impl<B, C, H> PowAlgorithm<B> for HashPow<B, C, H> where
+	B: BlockT<Hash = H256>,
+	C: ProvideRuntimeApi<B>,
+	C::Api: DifficultyApi<B, U256, Error = sp_blockchain::Error>,
+	H: PowHash,
+{
+	type Difficulty = U256;
+
+	fn difficulty(&self, parent: &BlockId<B>) -> Result<U256, Error<B>> {
+		self.client.runtime_api().difficulty(parent).map_err(Error::Client)
+	}
+
+	fn preliminary_verify(
+		&self,
+		pre_hash: &H256,
+		seal: &Seal,
+	) -> Result<Option<bool>, Error<B>> {
+		let valid = HashSeal::decode(&mut &seal[..])
+			.map(|seal| seal.is_valid::<H>(pre_hash))
+			.unwrap_or(false);
+		Ok(Some(valid))
+	}
+
+	fn verify(
+		&self,
+		_parent: &BlockId<B>,
+		pre_hash: &H256,
+		seal: &Seal,
+		difficulty: U256,
+	) -> Result<bool, Error<B>> {
+		let seal = match HashSeal::decode(&mut &seal[..]) {
+			Ok(seal) => seal,
+			Err(_) => return Ok(false),
+		};
+		Ok(seal.difficulty == difficulty && seal.is_valid::<H>(pre_hash))
+	}
+
+	fn mine(
+		&self,
+		_parent: &BlockId<B>,
+		pre_hash: &H256,
+		difficulty: U256,
+		round: u32,
+	) -> Result<Option<Seal>, Error<B>> {
+		Ok(mine::<H>(pre_hash, difficulty, round).map(|seal| seal.encode()))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn sha3_works() {
+		assert_eq!(
+			Sha3::hash(b"abc"),
+			H256::from_slice(&[
+				0x3a, 0x98, 0x5d, 0xa7, 0x4f, 0xe2, 0x25, 0xb2, 0x04, 0x5c, 0x17, 0x2d, 0x6b, 0xd3,
+				0x90, 0xbd, 0x85, 0x5f, 0x08, 0x6e, 0x3e, 0x9d, 0x52, 0x5b, 0x46, 0xbf, 0xe2, 0x45,
+				0x11, 0x43, 0x15, 0x32,
+			]),
+		);
+	}
+
+	#[test]
+	fn difficulty_bounds_the_work() {
+		let max = H256::repeat_byte(0xff);
+		assert!(meets_difficulty(&max, U256::one()));
+		assert!(!meets_difficulty(&max, U256::from(2)));
+
+		let mut below_half = [0xff; 32];
+		below_half[0] = 0x7f;
+		assert!(meets_difficulty(&H256::from(below_half), U256::from(2)));
+		assert!(!meets_difficulty(&H256::from(below_half), U256::from(3)));
+	}
+
+	#[test]
+	fn mined_seals_are_valid() {
+		let pre_hash = H256::repeat_byte(1);
+		let difficulty = U256::from(16);
+
+		let seal = mine::<Sha3>(&pre_hash, difficulty, 10_000).expect("a seal is found");
+		assert!(seal.is_valid::<Sha3>(&pre_hash));
+		assert!(!seal.is_valid::<Sha3>(&H256::repeat_byte(2)));
+		assert!(!seal.is_valid::<Blake2>(&pre_hash));
+
+		let seal = mine::<Blake2>(&pre_hash, difficulty, 10_000).expect("a seal is found");
+		assert!(seal.is_valid::<Blake2>(&pre_hash));
+
+		let mut forged = seal.clone();
+		forged.nonce = H256::repeat_byte(3);
+		assert!(!forged.is_valid::<Blake2>(&pre_hash));
+	}
+}
@@ -19,7 +19,11 @@
 //! To use this engine, you can need to have a struct that implements
 //! `PowAlgorithm`. After that, pass an instance of the struct, along
 //! with other necessary client references to `import_queue` to setup
-//! the queue. Use the `start_mine` function for basic CPU mining.
+//! the queue. Use the `start_mine` function for basic CPU mining, or the
+//! `worker` module and its RPC to mine with external miners.
+//!
+//! The `hash` module provides reference algorithms, hashing with SHA3 or
+//! BLAKE2 at a difficulty adjusted by the runtime.
 //!
 //! The auxiliary storage for PoW engine only stores the total difficulty.
 //! For other storage requirements for particular PoW algorithm (such as
@@ -55,6 +59,10 @@ use sc_client_api;
 use log::*;
 use sp_timestamp::{InherentError as TIError, TimestampInherentData};
 
+pub mod hash;
+pub mod rpc;
+pub mod worker;
+
 #[derive(derive_more::Display, Debug)]
 pub enum Error<B: BlockT> {
 	#[display(fmt = "Header uses the wrong engine {:?}", _0)]
@@ -83,6 +91,12 @@ pub enum Error<B: BlockT> {
 	CreateInherents(sp_inherents::Error),
 	#[display(fmt = "Checking inherents failed: {}", _0)]
 	CheckInherents(String),
+	#[display(fmt = "No block with pre-hash {:?} is being mined", _0)]
+	UnknownWork(B::Hash),
+	#[display(fmt = "Verifying the seal failed: {}", _0)]
+	FailedVerification(String),
+	#[display(fmt = "Block {:?} was not imported: {:?}", _0, _1)]
+	BlockNotImported(B::Hash, ImportResult),
 	Client(sp_blockchain::Error),
 	Codec(codec::Error),
 	Environment(String),
@@ -572,21 +586,7 @@ fn mine_loop<B: BlockT, C, Algorithm, E, SO, S, CAW>(
 			continue 'outer
 		}
 
-		let (best_hash, best_header) = match select_chain {
-			Some(select_chain) => {
-				let header = select_chain.best_chain()
-					.map_err(Error::BestHeaderSelectChain)?;
-				let hash = header.hash();
-				(hash, header)
-			},
-			None => {
-				let hash = client.info().best_hash;
-				let header = client.header(BlockId::Hash(hash))
-					.map_err(Error::BestHeader)?
-					.ok_or(Error::NoBestHeader)?;
-				(hash, header)
-			},
-		};
+		let (best_hash, best_header) = best_header(client, select_chain)?;
 
 		if let Err(err) = can_author_with.can_author_with(&BlockId::Hash(best_hash)) {
 			warn!(
@@ -667,3 +667,28 @@ fn mine_loop<B: BlockT, C, Algorithm, E, SO, S, CAW>(
 			.map_err(|e| Error::BlockBuiltError(best_hash, e))?;
 	}
 }
+
+/// The hash and the header of the best block, from `select_chain` if any.
+fn best_header<B: BlockT, C, S>(
+	client: &C,
+	select_chain: Option<&S>,
+) -> Result<(B::Hash, B::Header), Error<B>> where
+	C: HeaderBackend<B>,
+	S: SelectChain<B>,
+{
+	match select_chain {
+		Some(select_chain) => {
+			let header = select_chain.best_chain()
+				.map_err(Error::BestHeaderSelectChain)?;
+			let hash = header.hash();
+			Ok((hash, header))
+		},
+		None => {
+			let hash = client.info().best_hash;
+			let header = client.header(BlockId::Hash(hash))
+				.map_err(Error::BestHeader)?
+				.ok_or(Error::NoBestHeader)?;
+			Ok((hash, header))
+		},
+	}
+}
@@ -0,0 +1,121 @@
+// Copyright 2020 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! RPC interface for external miners.
+
+use std::sync::Arc;
+use parking_lot::Mutex;
+use jsonrpc_core::{Error as RpcError, ErrorCode, Result};
+use jsonrpc_derive::rpc;
+use sp_core::Bytes;
+use sp_runtime::traits::Block as BlockT;
+use crate::{Error, PowAlgorithm};
+use crate::worker::{MiningMetadata, MiningWorker};
+pub use self::gen_client::Client as PowClient;
+
+/// Error codes for rpc.
+mod codes {
+	pub const NO_WORK: i64 = 10_000;
+	pub const UNKNOWN_WORK: i64 = 11_000;
+	pub const INVALID_SEAL: i64 = 12_000;
+	pub const BLOCK_IMPORT_FAILED: i64 = 13_000;
+}
+
+/// RPC trait that provides methods for external miners.
+#[rpc]
+pub trait PowApi<Hash, Difficulty> {
+	/// Returns the work to do: the pre-hash of the latest block proposed, its parent and the
+	/// difficulty to seal it at.
+	#[rpc(name = "pow_getWork")]
+	fn get_work(&self) -> Result<MiningMetadata<Hash, Difficulty>>;
+
+	/// Seals the proposed block of the given pre-hash with the given encoded seal, and imports
+	/// it. Returns the hash of the sealed block.
+	#[rpc(name = "pow_submitWork")]
+	fn submit_work(&self, pre_hash: Hash, seal: Bytes) -> Result<Hash>;
+}
+
+/// A struct that implements the [`PowApi`].
+pub struct Pow<B: BlockT, Algorithm: PowAlgorithm<B>, Transaction> {
+	worker: Arc<Mutex<MiningWorker<B, Algorithm, Transaction>>>,
+}
+
+impl<B: BlockT, Algorithm: PowAlgorithm<B>, Transaction> Pow<B, Algorithm, Transaction> {
+	/// Create new `Pow` handing out the work of `worker`.
+	pub fn new(worker: Arc<Mutex<MiningWorker<B, Algorithm, Transaction>>>) -> Self {
+		Self { worker }
+	}
+}
+
+impl<B, Algorithm, Transaction> PowApi<B::Hash, Algorithm::Difficulty>
+	for Pow<B, Algorithm, Transaction> where
+	B: BlockT,
+	Algorithm: PowAlgorithm<B> + Clone + Send + Sync + 'static,
+	Algorithm::Difficulty: serde::Serialize + serde::de::DeserializeOwned + Send + Sync + 'static,
+	Transaction: Send + 'static,
+{
+	fn get_work(&self) -> Result<MiningMetadata<B::Hash, Algorithm::Difficulty>> {
+		self.worker.lock().metadata().ok_or_else(|| RpcError {
+			code: ErrorCode::ServerError(codes::NO_WORK),
+			message: "No block is being mined yet".into(),
+			data: None,
+		})
+	}
+
+	fn submit_work(&self, pre_hash: B::Hash, seal: Bytes) -> Result<B::Hash> {
+		self.worker.lock().submit(&pre_hash, seal.0).map_err(|error| {
+			let code = match error {
+				Error::UnknownWork(_) => codes::UNKNOWN_WORK,
+				Error::InvalidSeal | Error::FailedVerification(_) => codes::INVALID_SEAL,
+				_ => codes::BLOCK_IMPORT_FAILED,
+			};
+			RpcError {
+				code: ErrorCode::ServerError(code),
+				message: error.to_string(),
+				data: None,
+			}
+		})
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use sp_blockchain::HeaderBackend;
+	use crate::worker::tests::{SEAL, build, setup};
+
+	#[test]
+	fn pow_rpc_works() {
+		let (client, _, worker, mut env, _) = setup();
+		let worker = Arc::new(Mutex::new(worker));
+		let pow = Pow::new(worker.clone());
+		let genesis = client.info().best_hash;
+		assert_eq!(pow.get_work().unwrap_err().code, ErrorCode::ServerError(codes::NO_WORK));
+
+		let pre_hash = build(&mut worker.lock(), &mut env, &client, genesis, 0);
+		assert_eq!(pow.get_work().unwrap().pre_hash, pre_hash);
+		assert_eq!(
+			pow.submit_work(genesis, SEAL.to_vec().into()).unwrap_err().code,
+			ErrorCode::ServerError(codes::UNKNOWN_WORK),
+		);
+		assert_eq!(
+			pow.submit_work(pre_hash, b"bad".to_vec().into()).unwrap_err().code,
+			ErrorCode::ServerError(codes::INVALID_SEAL),
+		);
+		let hash = pow.submit_work(pre_hash, SEAL.to_vec().into()).unwrap();
+		assert_eq!(client.info().best_hash, hash);
+	}
+}
@@ -0,0 +1,468 @@
+// Copyright 2020 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Work for external miners: the blocks proposed for them to seal, and the import of the seals
+//! they submit.
+//!
+//! `start_mining_worker` proposes a block whenever the best block changes, and records it in a
+//! `MiningWorker`. Miners get the pre-hash and the difficulty of the latest block proposed, and
+//! submit a seal for it. The seal is checked by `PowVerifier`, and the block is imported through
+//! the given block import, usually a `PowBlockImport`. Seals for the blocks proposed on top of
+//! an earlier best block are still accepted: like any other block, such a block is imported as a
+//! fork, and only becomes the best block if its total difficulty is the highest.
+
+use std::collections::{HashMap, VecDeque};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+use parking_lot::Mutex;
+use serde::{Serialize, Deserialize};
+use sc_client_api::backend::AuxStore;
+use sp_blockchain::HeaderBackend;
+use sp_api::ProvideRuntimeApi;
+use sp_runtime::generic::{BlockId, Digest, DigestItem};
+use sp_runtime::traits::{Block as BlockT, Header as HeaderT};
+use sp_consensus::{
+	BlockImport, BlockOrigin, CanAuthorWith, Environment, ImportResult, Proposal, Proposer,
+	RecordProof, SelectChain, SyncOracle,
+};
+use sp_consensus::import_queue::{BoxBlockImport, Verifier};
+use sp_consensus_pow::{Seal, POW_ENGINE_ID};
+use sp_inherents::InherentDataProviders;
+use log::*;
+use crate::{Error, PowAlgorithm, PowVerifier, best_header, register_pow_inherent_data_provider};
+
+/// The number of latest proposed blocks seals are accepted for.
+const MAX_BUILDS: usize = 8;
+
+/// The work of sealing a proposed block.
+#[derive(Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MiningMetadata<Hash, Difficulty> {
+	/// The hash of the parent of the block.
+	pub parent_hash: Hash,
+	/// The hash of the block before it is sealed.
+	pub pre_hash: Hash,
+	/// The difficulty to seal the block at.
+	pub difficulty: Difficulty,
+}
+
+/// A proposed block, waiting for a seal.
+struct MiningBuild<B: BlockT, Difficulty, Transaction> {
+	metadata: MiningMetadata<B::Hash, Difficulty>,
+	proposal: Proposal<B, Transaction>,
+}
+
+/// The blocks proposed to miners, and the import of the blocks they seal.
+pub struct MiningWorker<B: BlockT, Algorithm: PowAlgorithm<B>, Transaction> {
+	builds: VecDeque<MiningBuild<B, Algorithm::Difficulty, Transaction>>,
+	algorithm: Algorithm,
+	block_import: BoxBlockImport<B, Transaction>,
+}
+
+impl<B, Algorithm, Transaction> MiningWorker<B, Algorithm, Transaction> where
+	B: BlockT,
+	Algorithm: PowAlgorithm<B> + Clone + Send + Sync,
+	Algorithm::Difficulty: 'static,
+{
+	/// Create a new worker, verifying seals with `algorithm` and importing the sealed blocks
+	/// with `block_import`.
+	pub fn new(algorithm: Algorithm, block_import: BoxBlockImport<B, Transaction>) -> Self {
+		Self { builds: VecDeque::new(), algorithm, block_import }
+	}
+
+	/// The work for the latest block proposed, if any.
+	pub fn metadata(&self) -> Option<MiningMetadata<B::Hash, Algorithm::Difficulty>> {
+		self.builds.back().map(|build| build.metadata.clone())
+	}
+
+	/// Record a newly proposed block, to be sealed at `difficulty`.
+	pub(crate) fn on_build(
+		&mut self,
+		parent_hash: B::Hash,
+		difficulty: Algorithm::Difficulty,
+		proposal: Proposal<B, Transaction>,
+	) {
+		let metadata = MiningMetadata {
+			parent_hash,
+			pre_hash: proposal.block.header().hash(),
+			difficulty,
+		};
+		self.builds.push_back(MiningBuild { metadata, proposal });
+		if self.builds.len() > MAX_BUILDS {
+			self.builds.pop_front();
+		}
+	}
+
+	/// Seal the proposed block of `pre_hash` with `seal`, and import it. A block that does not
+	/// get imported can not be sealed again, unlike one whose seal is invalid.
+	///
+	/// Returns the hash of the sealed block.
+	pub fn submit(&mut self, pre_hash: &B::Hash, seal: Seal) -> Result<B::Hash, Error<B>> {
+		let index = self.builds.iter()
+			.position(|build| build.metadata.pre_hash == *pre_hash)
+			.ok_or(Error::UnknownWork(*pre_hash))?;
+
+		let metadata = &self.builds[index].metadata;
+		let parent = BlockId::Hash(metadata.parent_hash);
+		if !self.algorithm.verify(&parent, pre_hash, &seal, metadata.difficulty)? {
+			return Err(Error::InvalidSeal);
+		}
+
+		let (mut header, body) = self.builds[index].proposal.block.clone().deconstruct();
+		header.digest_mut().push(DigestItem::Seal(POW_ENGINE_ID, seal));
+		let (import_block, _) = PowVerifier::new(self.algorithm.clone())
+			.verify(BlockOrigin::Own, header, None, Some(body))
+			.map_err(Error::FailedVerification)?;
+
+		// A build is only sealed once.
+		let build = self.builds.remove(index).expect("the build was found above; qed");
+		let mut import_block = import_block.convert_transaction();
+		import_block.storage_changes = Some(build.proposal.storage_changes);
+		let hash = import_block.post_hash();
+
+		match self.block_import.import_block(import_block, HashMap::default()) {
+			Ok(ImportResult::Imported(_)) => Ok(hash),
+			Ok(result) => Err(Error::BlockNotImported(hash, result)),
+			Err(e) => Err(Error::BlockBuiltError(build.metadata.parent_hash, e)),
+		}
+	}
+}
+
+/// Start the background thread proposing blocks to external miners, through `worker`.
+///
+/// A block is proposed whenever the best block changes, so transactions that arrive in the
+/// meantime wait for the next block. `preruntime` is the same as for `start_mine`.
+pub fn start_mining_worker<B: BlockT, C, Algorithm, E, SO, S, CAW>(
+	worker: Arc<Mutex<MiningWorker<B, Algorithm, sp_api::TransactionFor<C, B>>>>,
+	client: Arc<C>,
+	mut env: E,
+	preruntime: Option<Vec<u8>>,
+	mut sync_oracle: SO,
+	build_time: Duration,
+	select_chain: Option<S>,
+	inherent_data_providers: InherentDataProviders,
+	can_author_with: CAW,
+) where
+	C: HeaderBackend<B> + AuxStore + ProvideRuntimeApi<B> + 'static,
+	Algorithm: PowAlgorithm<B> + Clone + Send + Sync + 'static,
+	Algorithm::Difficulty: Send + 'static,
+	E: Environment<B> + Send + Sync + 'static,
+	E::Error: std::fmt::Debug,
+	E::Proposer: Proposer<B, Transaction = sp_api::TransactionFor<C, B>>,
+	SO: SyncOracle + Send + Sync + 'static,
+	S: SelectChain<B> + 'static,
+	CAW: CanAuthorWith<B> + Send + 'static,
+	sp_api::TransactionFor<C, B>: Send + 'static,
+{
+	if let Err(_) = register_pow_inherent_data_provider(&inherent_data_providers) {
+		warn!("Registering inherent data provider for timestamp failed");
+	}
+
+	thread::spawn(move || {
+		loop {
+			match build_loop(
+				&worker,
+				client.as_ref(),
+				&mut env,
+				preruntime.as_ref(),
+				&mut sync_oracle,
+				build_time,
+				select_chain.as_ref(),
+				&inherent_data_providers,
+				&can_author_with,
+			) {
+				Ok(()) => (),
+				Err(e) => error!(
+					"Proposing block failed with {:?}. Sleep for 1 second before restarting...",
+					e
+				),
+			}
+			thread::sleep(Duration::new(1, 0));
+		}
+	});
+}
+
+fn build_loop<B: BlockT, C, Algorithm, E, SO, S, CAW>(
+	worker: &Mutex<MiningWorker<B, Algorithm, sp_api::TransactionFor<C, B>>>,
+	client: &C,
+	env: &mut E,
+	preruntime: Option<&Vec<u8>>,
+	sync_oracle: &mut SO,
+	build_time: Duration,
+	select_chain: Option<&S>,
+	inherent_data_providers: &InherentDataProviders,
+	can_author_with: &CAW,
+) -> Result<(), Error<B>> where
+	C: HeaderBackend<B> + AuxStore + ProvideRuntimeApi<B>,
+	Algorithm: PowAlgorithm<B> + Clone + Send + Sync,
+	Algorithm::Difficulty: 'static,
+	E: Environment<B>,
+	E::Proposer: Proposer<B, Transaction = sp_api::TransactionFor<C, B>>,
+	E::Error: std::fmt::Debug,
+	SO: SyncOracle,
+	S: SelectChain<B>,
+	CAW: CanAuthorWith<B>,
+	sp_api::TransactionFor<C, B>: 'static,
+{
+	loop {
+		if sync_oracle.is_major_syncing() {
+			debug!(target: "pow", "Skipping proposal due to sync.");
+			thread::sleep(Duration::new(1, 0));
+			continue
+		}
+
+		let (best_hash, best_header) = best_header(client, select_chain)?;
+
+		let built_on_best = worker.lock().metadata()
+			.map_or(false, |metadata| metadata.parent_hash == best_hash);
+		if built_on_best {
+			thread::sleep(Duration::from_millis(100));
+			continue
+		}
+
+		if let Err(err) = can_author_with.can_author_with(&BlockId::Hash(best_hash)) {
+			warn!(
+				target: "pow",
+				"Skipping proposal `can_author_with` returned: {} \
+				Probably a node update is required!",
+				err,
+			);
+			thread::sleep(Duration::from_secs(1));
+			continue
+		}
+
+		let mut proposer = futures::executor::block_on(env.init(&best_header))
+			.map_err(|e| Error::Environment(format!("{:?}", e)))?;
+
+		let inherent_data = inherent_data_providers
+			.create_inherent_data().map_err(Error::CreateInherents)?;
+		let mut inherent_digest = Digest::default();
+		if let Some(preruntime) = &preruntime {
+			inherent_digest.push(DigestItem::PreRuntime(POW_ENGINE_ID, preruntime.to_vec()));
+		}
+		let proposal = futures::executor::block_on(proposer.propose(
+			inherent_data,
+			inherent_digest,
+			build_time,
+			RecordProof::No,
+		)).map_err(|e| Error::BlockProposingError(format!("{:?}", e)))?;
+
+		let mut worker = worker.lock();
+		let difficulty = worker.algorithm.difficulty(&BlockId::Hash(best_hash))?;
+		worker.on_build(best_hash, difficulty, proposal);
+	}
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+	use super::*;
+	use sc_basic_authorship::ProposerFactory;
+	use sc_transaction_pool::{BasicPool, txpool::Options};
+	use sp_core::U256;
+	use substrate_test_runtime_client::{
+		DefaultTestClientBuilderExt, LongestChain, TestClient, TestClientBuilder,
+		TestClientBuilderExt,
+		runtime::Block,
+	};
+	use substrate_test_runtime_transaction_pool::TestApi;
+	use crate::PowBlockImport;
+
+	/// The only seal `TestAlgorithm` accepts.
+	pub(crate) const SEAL: &[u8] = b"seal";
+
+	/// An algorithm accepting `SEAL` at any difficulty.
+	#[derive(Clone)]
+	pub(crate) struct TestAlgorithm;
+
+	impl PowAlgorithm<Block> for TestAlgorithm {
+		type Difficulty = U256;
+
+		fn difficulty(&self, _parent: &BlockId<Block>) -> Result<U256, Error<Block>> {
+			Ok(1.into())
+		}
+
+		fn verify(
+			&self,
+			_parent: &BlockId<Block>,
+			_pre_hash: &<Block as BlockT>::Hash,
+			seal: &Seal,
+			_difficulty: U256,
+		) -> Result<bool, Error<Block>> {
+			Ok(seal[..] == SEAL[..])
+		}
+
+		fn mine(
+			&self,
+			_parent: &BlockId<Block>,
+			_pre_hash: &<Block as BlockT>::Hash,
+			_difficulty: U256,
+			_round: u32,
+		) -> Result<Option<Seal>, Error<Block>> {
+			Ok(Some(SEAL.to_vec()))
+		}
+	}
+
+	type TestWorker = MiningWorker<Block, TestAlgorithm, sp_api::TransactionFor<TestClient, Block>>;
+	type TestEnv = ProposerFactory<
+		BasicPool<TestApi, Block>,
+		substrate_test_runtime_client::Backend,
+		TestClient,
+	>;
+
+	/// A client, a worker importing through a `PowBlockImport` and an environment proposing
+	/// blocks with that client.
+	pub(crate) fn setup() -> (
+		Arc<TestClient>,
+		LongestChain<substrate_test_runtime_client::Backend, Block>,
+		TestWorker,
+		TestEnv,
+		InherentDataProviders,
+	) {
+		let (client, select_chain) = TestClientBuilder::new().build_with_longest_chain();
+		let client = Arc::new(client);
+		let inherent_data_providers = InherentDataProviders::new();
+		register_pow_inherent_data_provider(&inherent_data_providers).unwrap();
+		let block_import = PowBlockImport::new(
+			client.clone(),
+			client.clone(),
+			TestAlgorithm,
+			// The test runtime has no inherents to check.
+			u64::max_value(),
+			Some(select_chain.clone()),
+			inherent_data_providers.clone(),
+		);
+		let worker = MiningWorker::new(TestAlgorithm, Box::new(block_import));
+		let pool = Arc::new(BasicPool::new(Options::default(), Arc::new(TestApi::empty())).0);
+		let env = ProposerFactory::new(client.clone(), pool);
+		(client, select_chain, worker, env, inherent_data_providers)
+	}
+
+	/// Propose a block on top of `parent`, told apart from the others by `salt`.
+	pub(crate) fn build(
+		worker: &mut TestWorker,
+		env: &mut TestEnv,
+		client: &TestClient,
+		parent: <Block as BlockT>::Hash,
+		salt: u8,
+	) -> <Block as BlockT>::Hash {
+		let header = client.header(&BlockId::Hash(parent)).unwrap().unwrap();
+		let mut proposer = futures::executor::block_on(env.init(&header)).unwrap();
+		let mut digest = Digest::default();
+		digest.push(DigestItem::PreRuntime(POW_ENGINE_ID, vec![salt]));
+		let proposal = futures::executor::block_on(proposer.propose(
+			Default::default(),
+			digest,
+			Duration::from_secs(1),
+			RecordProof::No,
+		)).unwrap();
+		worker.on_build(parent, 1.into(), proposal);
+		worker.metadata().unwrap().pre_hash
+	}
+
+	#[test]
+	fn submit_imports_sealed_blocks() {
+		let (client, _, mut worker, mut env, _) = setup();
+		let genesis = client.info().best_hash;
+		assert_eq!(worker.metadata(), None);
+
+		let pre_hash = build(&mut worker, &mut env, &client, genesis, 0);
+		assert_eq!(
+			worker.metadata(),
+			Some(MiningMetadata { parent_hash: genesis, pre_hash, difficulty: 1.into() }),
+		);
+		match worker.submit(&genesis, SEAL.to_vec()) {
+			Err(Error::UnknownWork(hash)) => assert_eq!(hash, genesis),
+			_ => panic!("only proposed blocks can be sealed"),
+		}
+		match worker.submit(&pre_hash, b"bad".to_vec()) {
+			Err(Error::InvalidSeal) => (),
+			_ => panic!("a bad seal is rejected"),
+		}
+
+		// The build is kept until a valid seal comes.
+		let hash = worker.submit(&pre_hash, SEAL.to_vec()).unwrap();
+		assert_eq!(client.info().best_hash, hash);
+		assert_eq!(client.info().best_number, 1);
+		assert_eq!(worker.metadata(), None);
+		match worker.submit(&pre_hash, SEAL.to_vec()) {
+			Err(Error::UnknownWork(_)) => (),
+			_ => panic!("a build is only sealed once"),
+		}
+	}
+
+	#[test]
+	fn stale_builds_are_imported_as_forks() {
+		let (client, _, mut worker, mut env, _) = setup();
+		let genesis = client.info().best_hash;
+		let stale = build(&mut worker, &mut env, &client, genesis, 0);
+		let latest = build(&mut worker, &mut env, &client, genesis, 1);
+		assert_eq!(worker.metadata().unwrap().pre_hash, latest);
+
+		let best = worker.submit(&latest, SEAL.to_vec()).unwrap();
+		let fork = worker.submit(&stale, SEAL.to_vec()).unwrap();
+		assert_ne!(fork, best);
+		assert_eq!(client.info().best_hash, best);
+		assert_eq!(client.header(&BlockId::Hash(fork)).unwrap().unwrap().parent_hash, genesis);
+	}
+
+	#[test]
+	fn old_builds_are_evicted() {
+		let (client, _, mut worker, mut env, _) = setup();
+		let genesis = client.info().best_hash;
+		let builds = (0..=MAX_BUILDS as u8)
+			.map(|salt| build(&mut worker, &mut env, &client, genesis, salt))
+			.collect::<Vec<_>>();
+
+		match worker.submit(&builds[0], SEAL.to_vec()) {
+			Err(Error::UnknownWork(_)) => (),
+			_ => panic!("the oldest build is evicted"),
+		}
+		assert!(worker.submit(&builds[1], SEAL.to_vec()).is_ok());
+	}
+
+	#[test]
+	fn start_mining_worker_proposes_on_the_best_block() {
+		let (client, select_chain, worker, env, inherent_data_providers) = setup();
+		let worker = Arc::new(Mutex::new(worker));
+		start_mining_worker(
+			worker.clone(),
+			client.clone(),
+			env,
+			None,
+			sp_consensus::NoNetwork,
+			Duration::from_secs(1),
+			Some(select_chain),
+			inherent_data_providers,
+			sp_consensus::AlwaysCanAuthor,
+		);
+		let work_on = |parent_hash| {
+			for _ in 0..100 {
+				match worker.lock().metadata() {
+					Some(metadata) if metadata.parent_hash == parent_hash => return metadata,
+					_ => (),
+				}
+				thread::sleep(Duration::from_millis(50));
+			}
+			panic!("no block is proposed on {:?}", parent_hash);
+		};
+
+		let work = work_on(client.info().best_hash);
+		let hash = worker.lock().submit(&work.pre_hash, SEAL.to_vec()).unwrap();
+		assert_eq!(client.info().best_hash, hash);
+		work_on(hash);
+	}
+}
@@ -0,0 +1,260 @@
+// Copyright 2020 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! # Difficulty Module
+//!
+//! The Difficulty module adjusts the difficulty of proof of work on chain, so that blocks are
+//! mined at `TargetBlockTime` on average.
+//!
+//! The module records the timestamp and the difficulty of the latest `Window + 1` blocks. Once it
+//! has a full window, every block retargets the difficulty: the total difficulty of the last
+//! `Window` blocks over the time they took is the hash rate, and the next difficulty is the
+//! hash rate times the target block time. The time is clamped to a factor of `MAX_ADJUSTMENT`
+//! of the time the window should take, which bounds each adjustment.
+//!
+//! Putting the difficulty adjustment on chain is safe, the node reads the difficulty of the next
+//! block through `sp_consensus_pow::DifficultyApi`, and the target block time through
+//! `sp_consensus_pow::TargetBlockTimeApi`.
+//!
+//! ## Usage
+//!
+//! Set the module as the `OnTimestampSet` of the Timestamp module, and give the difficulty of the
+//! first blocks in the genesis configuration.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use sp_std::prelude::*;
+use sp_core::U256;
+use sp_runtime::traits::{Saturating, UniqueSaturatedInto};
+use sp_timestamp::OnTimestampSet;
+use frame_support::{decl_module, decl_storage, traits::Get};
+
+/// The factor the difficulty may change by in a single adjustment, either way.
+pub const MAX_ADJUSTMENT: u64 = 4;
+
+pub trait Trait: pallet_timestamp::Trait {
+	/// The number of blocks the difficulty is adjusted over.
+	type Window: Get<u32>;
+
+	/// The time a block should take to mine.
+	type TargetBlockTime: Get<Self::Moment>;
+}
+
+decl_storage! {
+	trait Store for Module<T: Trait> as Difficulty {
+		/// The difficulty the next block is mined at.
+		pub Difficulty get(fn difficulty) config(): U256;
+
+		/// The timestamp and the difficulty of the latest `Window + 1` blocks, oldest first.
+		pub PastBlocks get(fn past_blocks): Vec<(T::Moment, U256)>;
+	}
+}
+
+decl_module! {
+	pub struct Module<T: Trait> for enum Call where origin: T::Origin {
+		/// The number of blocks the difficulty is adjusted over.
+		const Window: u32 = T::Window::get();
+
+		/// The time a block should take to mine.
+		const TargetBlockTime: T::Moment = T::TargetBlockTime::get();
+	}
+}
+
+impl<T: Trait> Module<T> {
+	/// The time a block should take to mine.
+	pub fn target_block_time() -> T::Moment {
+		T::TargetBlockTime::get()
+	}
+}
+
+impl<T: Trait> OnTimestampSet<T::Moment> for Module<T> {
+	fn on_timestamp_set(now: T::Moment) {
+		let window = T::Window::get();
+		let difficulty = Self::difficulty();
+
+		let next = <PastBlocks<T>>::mutate(|blocks| {
+			blocks.push((now, difficulty));
+			if blocks.len() > window as usize + 1 {
+				blocks.remove(0);
+			}
+			if blocks.len() <= window as usize {
+				return None;
+			}
+
+			let first = blocks.first().expect("the window holds at least one block; qed").0;
+			let timespan = UniqueSaturatedInto::<u64>::unique_saturated_into(now.saturating_sub(first));
+			// The first block only marks the start of the window.
+			let total_difficulty = blocks[1..].iter()
+				.fold(U256::zero(), |total, (_, difficulty)| total.saturating_add(*difficulty));
+			let target_block_time =
+				UniqueSaturatedInto::<u64>::unique_saturated_into(T::TargetBlockTime::get());
+			Some(retarget(total_difficulty, timespan, window, target_block_time))
+		});
+
+		if let Some(next) = next {
+			Difficulty::put(next);
+		}
+	}
+}
+
+/// The difficulty of the next block, after `window` blocks of `total_difficulty` took `timespan`
+/// to mine, each block having to take `target_block_time`.
+pub fn retarget(
+	total_difficulty: U256,
+	timespan: u64,
+	window: u32,
+	target_block_time: u64,
+) -> U256 {
+	let expected = target_block_time.saturating_mul(window as u64);
+	let timespan = timespan
+		.max(expected / MAX_ADJUSTMENT)
+		.min(expected.saturating_mul(MAX_ADJUSTMENT))
+		.max(1);
+
+	let next = total_difficulty.saturating_mul(U256::from(target_block_time))
+		/ U256::from(timespan);
+	next.max(U256::one())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use sp_core::H256;
+	use sp_runtime::{Perbill, testing::Header, traits::{BlakeTwo256, IdentityLookup}};
+	use frame_support::{impl_outer_origin, parameter_types, weights::Weight};
+
+	#[derive(Clone, PartialEq, Eq)]
+	pub struct Test;
+
+	impl_outer_origin! {
+		pub enum Origin for Test where system = frame_system {}
+	}
+
+	parameter_types! {
+		pub const BlockHashCount: u64 = 250;
+		pub const MaximumBlockWeight: Weight = 1024;
+		pub const MaximumBlockLength: u32 = 2 * 1024;
+		pub const AvailableBlockRatio: Perbill = Perbill::one();
+	}
+
+	impl frame_system::Trait for Test {
+		type Origin = Origin;
+		type Index = u64;
+		type BlockNumber = u64;
+		type Call = ();
+		type Hash = H256;
+		type Hashing = BlakeTwo256;
+		type AccountId = u64;
+		type Lookup = IdentityLookup<Self::AccountId>;
+		type Header = Header;
+		type Event = ();
+		type BlockHashCount = BlockHashCount;
+		type MaximumBlockWeight = MaximumBlockWeight;
+		type AvailableBlockRatio = AvailableBlockRatio;
+		type MaximumBlockLength = MaximumBlockLength;
+		type Version = ();
+		type ModuleToIndex = ();
+		type AccountData = ();
+		type OnNewAccount = ();
+		type OnKilledAccount = ();
+	}
+
+	parameter_types! {
+		pub const MinimumPeriod: u64 = 1;
+	}
+
+	impl pallet_timestamp::Trait for Test {
+		type Moment = u64;
+		type OnTimestampSet = DifficultyModule;
+		type MinimumPeriod = MinimumPeriod;
+	}
+
+	parameter_types! {
+		pub const Window: u32 = 3;
+		pub const TargetBlockTime: u64 = 1_000;
+	}
+
+	impl Trait for Test {
+		type Window = Window;
+		type TargetBlockTime = TargetBlockTime;
+	}
+
+	type DifficultyModule = Module<Test>;
+
+	fn new_test_ext() -> sp_io::TestExternalities {
+		let mut t = frame_system::GenesisConfig::default().build_storage::<Test>().unwrap();
+		GenesisConfig {
+			difficulty: U256::from(1_000),
+		}.assimilate_storage(&mut t).unwrap();
+		t.into()
+	}
+
+	/// Mines blocks at `interval`, returning the difficulty after each one.
+	fn mine_blocks(start: u64, interval: u64, count: u64) -> Vec<u64> {
+		(1..=count).map(|i| {
+			DifficultyModule::on_timestamp_set(start + i * interval);
+			DifficultyModule::difficulty().low_u64()
+		}).collect()
+	}
+
+	#[test]
+	fn difficulty_holds_until_the_window_is_full() {
+		new_test_ext().execute_with(|| {
+			assert_eq!(mine_blocks(0, 500, 3), vec![1_000, 1_000, 1_000]);
+			assert_eq!(DifficultyModule::past_blocks().len(), 3);
+		});
+	}
+
+	#[test]
+	fn difficulty_holds_at_the_target_block_time() {
+		new_test_ext().execute_with(|| {
+			assert_eq!(mine_blocks(0, 1_000, 6), vec![1_000; 6]);
+			assert_eq!(DifficultyModule::past_blocks().len(), 4);
+		});
+	}
+
+	#[test]
+	fn difficulty_follows_the_block_time() {
+		new_test_ext().execute_with(|| {
+			// Blocks twice as fast double the difficulty.
+			assert_eq!(mine_blocks(0, 500, 4), vec![1_000, 1_000, 1_000, 2_000]);
+			// Only one of the blocks in the window was mined at the new difficulty.
+			assert_eq!(mine_blocks(2_000, 500, 1), vec![2_666]);
+		});
+
+		new_test_ext().execute_with(|| {
+			// Blocks twice as slow halve the difficulty.
+			assert_eq!(mine_blocks(0, 2_000, 4), vec![1_000, 1_000, 1_000, 500]);
+		});
+	}
+
+	#[test]
+	fn adjustments_are_bounded() {
+		new_test_ext().execute_with(|| {
+			assert_eq!(mine_blocks(0, 1, 4), vec![1_000, 1_000, 1_000, 4_000]);
+		});
+
+		new_test_ext().execute_with(|| {
+			assert_eq!(mine_blocks(0, 100_000, 4), vec![1_000, 1_000, 1_000, 250]);
+		});
+	}
+
+	#[test]
+	fn retarget_never_reaches_zero() {
+		assert_eq!(retarget(U256::zero(), 1_000, 1, 1_000), U256::one());
+		assert_eq!(retarget(U256::from(10), 1_000, 1, 0), U256::one());
+	}
+}
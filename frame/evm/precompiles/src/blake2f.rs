@@ -0,0 +1,179 @@
+// Copyright 2020 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! The BLAKE2b compression function precompile (EIP-152).
+
+use sp_std::convert::TryInto;
+use evm::{ExitError, ExitSucceed};
+
+use crate::{Precompile, PrecompileResult, ensure_gas};
+
+/// The length of the input.
+const INPUT_LEN: usize = 213;
+
+/// The initialization vector of BLAKE2b.
+const IV: [u64; 8] = [
+	0x6a09e667f3bcc908, 0xbb67ae8584caa73b, 0x3c6ef372fe94f82b, 0xa54ff53a5f1d36f1,
+	0x510e527fade682d1, 0x9b05688c2b3e6c1f, 0x1f83d9abfb41bd6b, 0x5be0cd19137e2179,
+];
+
+/// The message word permutations of BLAKE2b, one per round, cycling.
+const SIGMA: [[usize; 16]; 10] = [
+	[0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15],
+	[14, 10, 4, 8, 9, 15, 13, 6, 1, 12, 0, 2, 11, 7, 5, 3],
+	[11, 8, 12, 0, 5, 2, 15, 13, 10, 14, 3, 6, 7, 1, 9, 4],
+	[7, 9, 3, 1, 13, 12, 11, 14, 2, 6, 5, 10, 4, 0, 15, 8],
+	[9, 0, 5, 7, 2, 4, 10, 15, 14, 1, 11, 12, 6, 8, 3, 13],
+	[2, 12, 6, 10, 0, 11, 8, 3, 4, 13, 7, 5, 15, 14, 1, 9],
+	[12, 5, 1, 15, 14, 13, 4, 10, 0, 7, 6, 3, 9, 2, 8, 11],
+	[13, 11, 7, 14, 12, 1, 3, 9, 5, 0, 15, 4, 8, 6, 2, 10],
+	[6, 15, 14, 9, 11, 3, 0, 8, 12, 2, 13, 7, 1, 4, 10, 5],
+	[10, 2, 8, 4, 7, 6, 1, 5, 15, 11, 9, 14, 3, 12, 13, 0],
+];
+
+/// The mixing function of BLAKE2b.
+fn g(v: &mut [u64; 16], a: usize, b: usize, c: usize, d: usize, x: u64, y: u64) {
+	v[a] = v[a].wrapping_add(v[b]).wrapping_add(x);
+	v[d] = (v[d] ^ v[a]).rotate_right(32);
+	v[c] = v[c].wrapping_add(v[d]);
+	v[b] = (v[b] ^ v[c]).rotate_right(24);
+	v[a] = v[a].wrapping_add(v[b]).wrapping_add(y);
+	v[d] = (v[d] ^ v[a]).rotate_right(16);
+	v[c] = v[c].wrapping_add(v[d]);
+	v[b] = (v[b] ^ v[c]).rotate_right(63);
+}
+
+/// The compression function F of BLAKE2b, with `rounds` rounds, updating the state `h` with the
+/// message block `m`, the offset counter `t` and the final block flag `f`.
+pub fn compress(h: &mut [u64; 8], m: &[u64; 16], t: [u64; 2], f: bool, rounds: u32) {
+	let mut v = [0u64; 16];
+	v[..8].copy_from_slice(h);
+	v[8..].copy_from_slice(&IV);
+	v[12] ^= t[0];
+	v[13] ^= t[1];
+	if f {
+		v[14] = !v[14];
+	}
+
+	for round in 0..rounds as usize {
+		let s = &SIGMA[round % 10];
+		g(&mut v, 0, 4, 8, 12, m[s[0]], m[s[1]]);
+		g(&mut v, 1, 5, 9, 13, m[s[2]], m[s[3]]);
+		g(&mut v, 2, 6, 10, 14, m[s[4]], m[s[5]]);
+		g(&mut v, 3, 7, 11, 15, m[s[6]], m[s[7]]);
+		g(&mut v, 0, 5, 10, 15, m[s[8]], m[s[9]]);
+		g(&mut v, 1, 6, 11, 12, m[s[10]], m[s[11]]);
+		g(&mut v, 2, 7, 8, 13, m[s[12]], m[s[13]]);
+		g(&mut v, 3, 4, 9, 14, m[s[14]], m[s[15]]);
+	}
+
+	for (i, word) in h.iter_mut().enumerate() {
+		*word ^= v[i] ^ v[i + 8];
+	}
+}
+
+/// The little-endian `u64` at `start` of `input`.
+fn read_u64(input: &[u8], start: usize) -> u64 {
+	u64::from_le_bytes(input[start..start + 8].try_into().expect("the slice is 8 bytes; qed"))
+}
+
+/// The compression function F of BLAKE2b, at `0x09`.
+///
+/// The input is exactly `rounds ++ h ++ m ++ t ++ f`: the number of rounds as a 4-byte big-endian
+/// integer, the 8 words of the state, the 16 words of the message block and the 2 words of the
+/// offset counter, each a little-endian `u64`, and the final block flag, a byte 0 or 1. The output
+/// is the new state.
+pub struct Blake2F;
+
+impl Precompile for Blake2F {
+	fn execute(input: &[u8], target_gas: Option<usize>) -> PrecompileResult {
+		if input.len() != INPUT_LEN {
+			return Err(ExitError::Other("input must be exactly 213 bytes".into()));
+		}
+		let rounds = u32::from_be_bytes(input[0..4].try_into().expect("the slice is 4 bytes; qed"));
+		let cost = rounds as usize;
+		ensure_gas(cost, target_gas)?;
+
+		let f = match input[212] {
+			0 => false,
+			1 => true,
+			_ => return Err(ExitError::Other("final block flag must be 0 or 1".into())),
+		};
+
+		let mut h = [0u64; 8];
+		for (i, word) in h.iter_mut().enumerate() {
+			*word = read_u64(input, 4 + i * 8);
+		}
+		let mut m = [0u64; 16];
+		for (i, word) in m.iter_mut().enumerate() {
+			*word = read_u64(input, 68 + i * 8);
+		}
+		let t = [read_u64(input, 196), read_u64(input, 204)];
+
+		compress(&mut h, &m, t, f, rounds);
+
+		let output = h.iter().flat_map(|word| word.to_le_bytes().to_vec()).collect();
+		Ok((ExitSucceed::Returned, output, cost))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use hex_literal::hex;
+
+	/// The compression of the block of "abc" with 12 rounds, the test vector 5 of EIP-152.
+	const INPUT: [u8; 213] = hex!("
+		0000000c
+		48c9bdf267e6096a3ba7ca8485ae67bb2bf894fe72f36e3cf1361d5f3af54fa5
+		d182e6ad7f520e511f6c3e2b8c68059b6bbd41fbabd9831f79217e1319cde05b
+		6162630000000000000000000000000000000000000000000000000000000000
+		0000000000000000000000000000000000000000000000000000000000000000
+		0000000000000000000000000000000000000000000000000000000000000000
+		0000000000000000000000000000000000000000000000000000000000000000
+		0300000000000000
+		0000000000000000
+		01
+	");
+
+	/// The BLAKE2b-512 hash of "abc".
+	const OUTPUT: [u8; 64] = hex!("
+		ba80a53f981c4d0d6a2797b69f12f6e94c212f14685ac4b74b12bb6fdbffa2d1
+		7d87c5392aab792dc252d5de4533cc9518d38aa8dbf1925ab92386edd4009923
+	");
+
+	#[test]
+	fn compression_works() {
+		assert_eq!(
+			<Blake2F as Precompile>::execute(&INPUT, None),
+			Ok((ExitSucceed::Returned, OUTPUT.to_vec(), 12)),
+		);
+	}
+
+	#[test]
+	fn rounds_are_charged() {
+		assert_eq!(<Blake2F as Precompile>::execute(&INPUT, Some(11)), Err(ExitError::OutOfGas));
+	}
+
+	#[test]
+	fn invalid_input_fails() {
+		assert!(<Blake2F as Precompile>::execute(&INPUT[..212], None).is_err());
+
+		let mut input = INPUT;
+		input[212] = 2;
+		assert!(<Blake2F as Precompile>::execute(&input, None).is_err());
+	}
+}
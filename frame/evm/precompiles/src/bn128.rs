@@ -0,0 +1,246 @@
+// Copyright 2020 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! The precompiles over the alt_bn128 curve (EIP-196 and EIP-197), at their Istanbul cost
+//! (EIP-1108).
+//!
+//! An element of the base field is a 32-byte big-endian integer, and a point of G1 is its two
+//! coordinates, the point at infinity being `(0, 0)`. An element `a * i + b` of the quadratic
+//! extension is `a ++ b`, and a point of G2 is its two coordinates.
+
+use sp_std::prelude::*;
+use evm::{ExitError, ExitSucceed};
+use bn::{AffineG1, AffineG2, Fq, Fq2, Fr, G1, G2, Group, Gt};
+
+use crate::{LinearCostPrecompile, Precompile, PrecompileResult, ensure_gas, padded};
+
+/// The cost of a pairing check.
+const PAIRING_BASE: usize = 45_000;
+/// The cost of each pair of points of a pairing check.
+const PAIRING_PER_PAIR: usize = 34_000;
+/// The length of a pair of points of a pairing check.
+const PAIR_LEN: usize = 192;
+
+fn read_fq(input: &[u8], start: usize) -> Result<Fq, ExitError> {
+	Fq::from_slice(&input[start..start + 32])
+		.map_err(|_| ExitError::Other("invalid field element".into()))
+}
+
+fn read_fr(input: &[u8], start: usize) -> Result<Fr, ExitError> {
+	Fr::from_slice(&input[start..start + 32])
+		.map_err(|_| ExitError::Other("invalid scalar".into()))
+}
+
+fn read_g1(input: &[u8], start: usize) -> Result<G1, ExitError> {
+	let x = read_fq(input, start)?;
+	let y = read_fq(input, start + 32)?;
+	if x == Fq::zero() && y == Fq::zero() {
+		return Ok(G1::zero());
+	}
+	AffineG1::new(x, y)
+		.map(Into::into)
+		.map_err(|_| ExitError::Other("invalid point of G1".into()))
+}
+
+fn read_g2(input: &[u8], start: usize) -> Result<G2, ExitError> {
+	let x_imaginary = read_fq(input, start)?;
+	let x_real = read_fq(input, start + 32)?;
+	let y_imaginary = read_fq(input, start + 64)?;
+	let y_real = read_fq(input, start + 96)?;
+	let x = Fq2::new(x_real, x_imaginary);
+	let y = Fq2::new(y_real, y_imaginary);
+	if x.is_zero() && y.is_zero() {
+		return Ok(G2::zero());
+	}
+	AffineG2::new(x, y)
+		.map(Into::into)
+		.map_err(|_| ExitError::Other("invalid point of G2".into()))
+}
+
+fn encode_g1(point: G1) -> Vec<u8> {
+	let mut output = vec![0u8; 64];
+	// The point at infinity has no affine coordinates, and is encoded as zeros.
+	if let Some(point) = AffineG1::from_jacobian(point) {
+		point.x().to_big_endian(&mut output[0..32])
+			.expect("a field element is 32 bytes; qed");
+		point.y().to_big_endian(&mut output[32..64])
+			.expect("a field element is 32 bytes; qed");
+	}
+	output
+}
+
+/// The sum of two points of G1, at `0x06`.
+pub struct Bn128Add;
+
+impl LinearCostPrecompile for Bn128Add {
+	const BASE: usize = 150;
+	const WORD: usize = 0;
+
+	fn execute(input: &[u8], _: usize) -> Result<(ExitSucceed, Vec<u8>), ExitError> {
+		let input = padded(input, 128);
+		let p1 = read_g1(&input, 0)?;
+		let p2 = read_g1(&input, 64)?;
+		Ok((ExitSucceed::Returned, encode_g1(p1 + p2)))
+	}
+}
+
+/// The product of a point of G1 by a scalar, at `0x07`.
+pub struct Bn128Mul;
+
+impl LinearCostPrecompile for Bn128Mul {
+	const BASE: usize = 6_000;
+	const WORD: usize = 0;
+
+	fn execute(input: &[u8], _: usize) -> Result<(ExitSucceed, Vec<u8>), ExitError> {
+		let input = padded(input, 96);
+		let p = read_g1(&input, 0)?;
+		let scalar = read_fr(&input, 64)?;
+		Ok((ExitSucceed::Returned, encode_g1(p * scalar)))
+	}
+}
+
+/// The check that the product of the pairings of pairs of points of G1 and G2 is one, at `0x08`.
+///
+/// The input is the pairs, each a point of G1 followed by a point of G2, and the output is one if
+/// the check passes, zero otherwise, as a 32-byte big-endian integer.
+pub struct Bn128Pairing;
+
+impl Precompile for Bn128Pairing {
+	fn execute(input: &[u8], target_gas: Option<usize>) -> PrecompileResult {
+		if input.len() % PAIR_LEN != 0 {
+			return Err(ExitError::Other("input must be a whole number of pairs".into()));
+		}
+		let pairs = input.len() / PAIR_LEN;
+		let cost = pairs.checked_mul(PAIRING_PER_PAIR)
+			.and_then(|cost| cost.checked_add(PAIRING_BASE))
+			.ok_or(ExitError::OutOfGas)?;
+		ensure_gas(cost, target_gas)?;
+
+		let pairs = input.chunks(PAIR_LEN)
+			.map(|pair| Ok((read_g1(pair, 0)?, read_g2(pair, 64)?)))
+			.collect::<Result<Vec<_>, ExitError>>()?;
+
+		let mut output = vec![0u8; 32];
+		if bn::pairing_batch(&pairs) == Gt::one() {
+			output[31] = 1;
+		}
+		Ok((ExitSucceed::Returned, output, cost))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use hex_literal::hex;
+
+	/// The generator of G1, `(1, 2)`.
+	const G1_GENERATOR: [u8; 64] = hex!("
+		0000000000000000000000000000000000000000000000000000000000000001
+		0000000000000000000000000000000000000000000000000000000000000002
+	");
+
+	/// Twice the generator of G1.
+	const G1_DOUBLE: [u8; 64] = hex!("
+		030644e72e131a029b85045b68181585d97816a916871ca8d3c208c16d87cfd3
+		15ed738c0e0a7c92e7845f96b2ae9c0a68a6a449e3538fc7ff3ebf7a5a18a2c4
+	");
+
+	/// The opposite of the generator of G1, `(1, p - 2)`.
+	const G1_OPPOSITE: [u8; 64] = hex!("
+		0000000000000000000000000000000000000000000000000000000000000001
+		30644e72e131a029b85045b68181585d97816a916871ca8d3c208c16d87cfd45
+	");
+
+	/// The generator of G2.
+	const G2_GENERATOR: [u8; 128] = hex!("
+		198e9393920d483a7260bfb731fb5d25f1aa493335a9e71297e485b7aef312c2
+		1800deef121f1e76426a00665e5c4479674322d4f75edadd46debd5cd992f6ed
+		090689d0585ff075ec9e99ad690c3395bc4b313370b38ef355acdadcd122975b
+		12c85ea5db8c6deb4aab71808dcb408fe3d1e7690c43d37b4ce6cc0166fa7daa
+	");
+
+	#[test]
+	fn add_works() {
+		let input = [&G1_GENERATOR[..], &G1_GENERATOR[..]].concat();
+		assert_eq!(
+			<Bn128Add as Precompile>::execute(&input, None),
+			Ok((ExitSucceed::Returned, G1_DOUBLE.to_vec(), 150)),
+		);
+	}
+
+	#[test]
+	fn add_of_opposites_is_infinity() {
+		let input = [&G1_GENERATOR[..], &G1_OPPOSITE[..]].concat();
+		assert_eq!(
+			<Bn128Add as Precompile>::execute(&input, None),
+			Ok((ExitSucceed::Returned, vec![0u8; 64], 150)),
+		);
+	}
+
+	#[test]
+	fn add_of_point_off_curve_fails() {
+		let mut input = [&G1_GENERATOR[..], &G1_GENERATOR[..]].concat();
+		input[63] = 3;
+		assert!(<Bn128Add as Precompile>::execute(&input, None).is_err());
+	}
+
+	#[test]
+	fn mul_works() {
+		let mut input = G1_GENERATOR.to_vec();
+		input.extend_from_slice(&[0u8; 31]);
+		input.push(2);
+		assert_eq!(
+			<Bn128Mul as Precompile>::execute(&input, None),
+			Ok((ExitSucceed::Returned, G1_DOUBLE.to_vec(), 6_000)),
+		);
+	}
+
+	#[test]
+	fn pairing_of_nothing_passes() {
+		let mut output = vec![0u8; 32];
+		output[31] = 1;
+		assert_eq!(
+			<Bn128Pairing as Precompile>::execute(&[], None),
+			Ok((ExitSucceed::Returned, output, 45_000)),
+		);
+	}
+
+	#[test]
+	fn pairing_works() {
+		// e(g1, g2) * e(-g1, g2) == 1
+		let passing = [&G1_GENERATOR[..], &G2_GENERATOR[..], &G1_OPPOSITE[..], &G2_GENERATOR[..]]
+			.concat();
+		let mut output = vec![0u8; 32];
+		output[31] = 1;
+		assert_eq!(
+			<Bn128Pairing as Precompile>::execute(&passing, None),
+			Ok((ExitSucceed::Returned, output, 113_000)),
+		);
+
+		// e(g1, g2) * e(g1, g2) != 1
+		let failing = [&G1_GENERATOR[..], &G2_GENERATOR[..], &G1_GENERATOR[..], &G2_GENERATOR[..]]
+			.concat();
+		assert_eq!(
+			<Bn128Pairing as Precompile>::execute(&failing, None),
+			Ok((ExitSucceed::Returned, vec![0u8; 32], 113_000)),
+		);
+	}
+
+	#[test]
+	fn pairing_of_partial_pair_fails() {
+		assert!(<Bn128Pairing as Precompile>::execute(&[0u8; 191], None).is_err());
+	}
+}
@@ -0,0 +1,273 @@
+// Copyright 2020 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! The standard Ethereum precompiled contracts, as of the Istanbul hard fork.
+//!
+//! Use [`Istanbul`] as the `Precompiles` of the EVM pallet to make them available at their usual
+//! addresses:
+//!
+//! | Address | Precompile    | Gas                               |
+//! |---------|---------------|-----------------------------------|
+//! | `0x01`  | ecrecover     | 3000                              |
+//! | `0x02`  | sha256        | 60 + 12 per word                  |
+//! | `0x03`  | ripemd160     | 600 + 120 per word                |
+//! | `0x04`  | identity      | 15 + 3 per word                   |
+//! | `0x05`  | modexp        | EIP-198                           |
+//! | `0x06`  | bn128 add     | 150 (EIP-1108)                    |
+//! | `0x07`  | bn128 mul     | 6000 (EIP-1108)                   |
+//! | `0x08`  | bn128 pairing | 45000 + 34000 per pair (EIP-1108) |
+//! | `0x09`  | blake2f       | 1 per round (EIP-152)             |
+//!
+//! A word is 32 bytes of input, the last one possibly partial.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+mod blake2f;
+mod bn128;
+mod modexp;
+
+pub use crate::blake2f::Blake2F;
+pub use crate::bn128::{Bn128Add, Bn128Mul, Bn128Pairing};
+pub use crate::modexp::Modexp;
+
+use sp_std::{cmp::min, prelude::*};
+use sp_core::H160;
+use evm::{ExitError, ExitSucceed};
+use sha2::Digest;
+
+/// The result of a precompile: the status, the output and the gas used.
+pub type PrecompileResult = Result<(ExitSucceed, Vec<u8>, usize), ExitError>;
+
+/// A precompiled contract.
+pub trait Precompile {
+	/// Execute the precompile on `input`, failing with `ExitError::OutOfGas` if it costs more than
+	/// `target_gas`.
+	fn execute(input: &[u8], target_gas: Option<usize>) -> PrecompileResult;
+}
+
+/// A precompiled contract whose cost is linear in the number of words of its input.
+pub trait LinearCostPrecompile {
+	/// The cost of any execution.
+	const BASE: usize;
+	/// The cost of each word of input.
+	const WORD: usize;
+
+	/// Execute the precompile on `input`, whose cost is `cost`.
+	fn execute(input: &[u8], cost: usize) -> Result<(ExitSucceed, Vec<u8>), ExitError>;
+}
+
+impl<T: LinearCostPrecompile> Precompile for T {
+	fn execute(input: &[u8], target_gas: Option<usize>) -> PrecompileResult {
+		let cost = linear_cost(input.len(), T::BASE, T::WORD)?;
+		ensure_gas(cost, target_gas)?;
+		let (succeed, output) = T::execute(input, cost)?;
+		Ok((succeed, output, cost))
+	}
+}
+
+/// The cost of `len` bytes of input at `base` plus `word` per word.
+fn linear_cost(len: usize, base: usize, word: usize) -> Result<usize, ExitError> {
+	let words = len.checked_add(31).ok_or(ExitError::OutOfGas)? / 32;
+	words.checked_mul(word)
+		.and_then(|cost| cost.checked_add(base))
+		.ok_or(ExitError::OutOfGas)
+}
+
+/// Fail with `ExitError::OutOfGas` if `cost` is more than `target_gas`.
+fn ensure_gas(cost: usize, target_gas: Option<usize>) -> Result<(), ExitError> {
+	match target_gas {
+		Some(target_gas) if cost > target_gas => Err(ExitError::OutOfGas),
+		_ => Ok(()),
+	}
+}
+
+/// `input` right-padded with zeros, or truncated, to `len` bytes.
+fn padded(input: &[u8], len: usize) -> Vec<u8> {
+	let mut padded = vec![0u8; len];
+	let copied = min(input.len(), len);
+	padded[..copied].copy_from_slice(&input[..copied]);
+	padded
+}
+
+/// The precompiles of the Istanbul hard fork, at addresses `0x01` to `0x09`.
+pub struct Istanbul;
+
+impl pallet_evm::Precompiles for Istanbul {
+	fn execute(
+		address: H160,
+		input: &[u8],
+		target_gas: Option<usize>,
+	) -> Option<PrecompileResult> {
+		if address.as_bytes()[..19].iter().any(|byte| *byte != 0) {
+			return None;
+		}
+		match address.as_bytes()[19] {
+			1 => Some(<ECRecover as Precompile>::execute(input, target_gas)),
+			2 => Some(<Sha256 as Precompile>::execute(input, target_gas)),
+			3 => Some(<Ripemd160 as Precompile>::execute(input, target_gas)),
+			4 => Some(<Identity as Precompile>::execute(input, target_gas)),
+			5 => Some(<Modexp as Precompile>::execute(input, target_gas)),
+			6 => Some(<Bn128Add as Precompile>::execute(input, target_gas)),
+			7 => Some(<Bn128Mul as Precompile>::execute(input, target_gas)),
+			8 => Some(<Bn128Pairing as Precompile>::execute(input, target_gas)),
+			9 => Some(<Blake2F as Precompile>::execute(input, target_gas)),
+			_ => None,
+		}
+	}
+}
+
+/// The recovery of the address that signed a hash, at `0x01`.
+///
+/// The input is `hash ++ v ++ r ++ s`, each 32 bytes, and the output is the address left-padded
+/// to 32 bytes, or nothing if the signature is invalid.
+pub struct ECRecover;
+
+impl LinearCostPrecompile for ECRecover {
+	const BASE: usize = 3000;
+	const WORD: usize = 0;
+
+	fn execute(input: &[u8], _: usize) -> Result<(ExitSucceed, Vec<u8>), ExitError> {
+		let input = padded(input, 128);
+
+		let mut msg = [0u8; 32];
+		msg.copy_from_slice(&input[0..32]);
+
+		// `v` is a 32-byte big-endian integer that must be 27 or 28.
+		if input[32..63].iter().any(|byte| *byte != 0) || !(input[63] == 27 || input[63] == 28) {
+			return Ok((ExitSucceed::Returned, Vec::new()));
+		}
+		let mut sig = [0u8; 65];
+		sig[0..64].copy_from_slice(&input[64..128]);
+		sig[64] = input[63] - 27;
+
+		match sp_io::crypto::secp256k1_ecdsa_recover(&sig, &msg) {
+			Ok(public) => {
+				let mut output = vec![0u8; 32];
+				output[12..32].copy_from_slice(&sp_io::hashing::keccak_256(&public)[12..]);
+				Ok((ExitSucceed::Returned, output))
+			},
+			Err(_) => Ok((ExitSucceed::Returned, Vec::new())),
+		}
+	}
+}
+
+/// The SHA-256 hash of the input, at `0x02`.
+pub struct Sha256;
+
+impl LinearCostPrecompile for Sha256 {
+	const BASE: usize = 60;
+	const WORD: usize = 12;
+
+	fn execute(input: &[u8], _: usize) -> Result<(ExitSucceed, Vec<u8>), ExitError> {
+		Ok((ExitSucceed::Returned, sha2::Sha256::digest(input).to_vec()))
+	}
+}
+
+/// The RIPEMD-160 hash of the input, left-padded to 32 bytes, at `0x03`.
+pub struct Ripemd160;
+
+impl LinearCostPrecompile for Ripemd160 {
+	const BASE: usize = 600;
+	const WORD: usize = 120;
+
+	fn execute(input: &[u8], _: usize) -> Result<(ExitSucceed, Vec<u8>), ExitError> {
+		let mut output = vec![0u8; 32];
+		output[12..32].copy_from_slice(&ripemd160::Ripemd160::digest(input));
+		Ok((ExitSucceed::Returned, output))
+	}
+}
+
+/// The input itself, at `0x04`.
+pub struct Identity;
+
+impl LinearCostPrecompile for Identity {
+	const BASE: usize = 15;
+	const WORD: usize = 3;
+
+	fn execute(input: &[u8], _: usize) -> Result<(ExitSucceed, Vec<u8>), ExitError> {
+		Ok((ExitSucceed::Returned, input.to_vec()))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use hex_literal::hex;
+	use pallet_evm::Precompiles;
+
+	fn precompile(address: u64, input: &[u8], target_gas: Option<usize>) -> PrecompileResult {
+		<Istanbul as Precompiles>::execute(H160::from_low_u64_be(address), input, target_gas)
+			.expect("a precompile is at the address")
+	}
+
+	fn is_precompile(address: H160) -> bool {
+		<Istanbul as Precompiles>::execute(address, &[], None).is_some()
+	}
+
+	#[test]
+	fn only_addresses_one_to_nine_are_precompiles() {
+		assert!(!is_precompile(H160::from_low_u64_be(0)));
+		assert!(!is_precompile(H160::from_low_u64_be(10)));
+		assert!(!is_precompile(H160::repeat_byte(1)));
+		assert!((1..=9).all(|address| is_precompile(H160::from_low_u64_be(address))));
+	}
+
+	#[test]
+	fn ecrecover_works() {
+		let input = hex!("
+			38d18acb67d25c8bb9942764b62f18e17054f66a817bd4295423adf9ed98873e
+			000000000000000000000000000000000000000000000000000000000000001b
+			38d18acb67d25c8bb9942764b62f18e17054f66a817bd4295423adf9ed98873e
+			789d1dd423d25f0772d2748d60f7e4b81bb14d086eba8e8e8efb6dcff8a4ae02
+		");
+		let output = hex!("000000000000000000000000ceaccac640adf55b2028469bd36ba501f28b699d");
+		assert_eq!(
+			precompile(1, &input, Some(3000)),
+			Ok((ExitSucceed::Returned, output.to_vec(), 3000)),
+		);
+	}
+
+	#[test]
+	fn ecrecover_with_invalid_v_returns_nothing() {
+		let mut input = [0u8; 128];
+		input[63] = 29;
+		assert_eq!(precompile(1, &input, None), Ok((ExitSucceed::Returned, vec![], 3000)));
+	}
+
+	#[test]
+	fn sha256_works() {
+		let output = hex!("e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855");
+		assert_eq!(precompile(2, &[], None), Ok((ExitSucceed::Returned, output.to_vec(), 60)));
+	}
+
+	#[test]
+	fn ripemd160_works() {
+		let output = hex!("0000000000000000000000009c1185a5c5e9fc54612808977ee8f548b2258d31");
+		assert_eq!(precompile(3, &[], None), Ok((ExitSucceed::Returned, output.to_vec(), 600)));
+	}
+
+	#[test]
+	fn identity_charges_per_word() {
+		let input = [7u8; 33];
+		assert_eq!(precompile(4, &input, None), Ok((ExitSucceed::Returned, input.to_vec(), 21)));
+	}
+
+	#[test]
+	fn running_out_of_gas_fails() {
+		assert_eq!(precompile(1, &[], Some(2999)), Err(ExitError::OutOfGas));
+		assert_eq!(precompile(4, &[0u8; 33], Some(20)), Err(ExitError::OutOfGas));
+	}
+}
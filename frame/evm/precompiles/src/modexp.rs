@@ -0,0 +1,182 @@
+// Copyright 2020 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! The modular exponentiation precompile (EIP-198).
+
+use sp_std::{cmp::{max, min}, prelude::*};
+use sp_core::U256;
+use evm::{ExitError, ExitSucceed};
+use num_bigint::BigUint;
+
+use crate::{Precompile, PrecompileResult, ensure_gas, padded};
+
+/// The divisor of the cost of an exponentiation.
+const GQUADDIVISOR: u64 = 20;
+
+/// The maximum length, in bytes, of the base, the exponent and the modulus.
+const MAX_LEN: usize = 64 * 1024;
+
+/// `base ** exponent % modulus`, at `0x05`.
+///
+/// The input is `base_len ++ exponent_len ++ modulus_len`, each a 32-byte big-endian integer,
+/// followed by the base, exponent and modulus, big-endian and of the given lengths. Missing input
+/// is read as zeros. The output is the result, left-padded to `modulus_len` bytes.
+pub struct Modexp;
+
+impl Precompile for Modexp {
+	fn execute(input: &[u8], target_gas: Option<usize>) -> PrecompileResult {
+		let header = padded(input, 96);
+		let base_len = U256::from_big_endian(&header[0..32]);
+		let exponent_len = U256::from_big_endian(&header[32..64]);
+		let modulus_len = U256::from_big_endian(&header[64..96]);
+
+		// Longer numbers are refused before anything is allocated, whatever the gas.
+		let limit = U256::from(MAX_LEN);
+		if base_len > limit || exponent_len > limit || modulus_len > limit {
+			return Err(ExitError::OutOfGas);
+		}
+		let base_len = base_len.low_u64() as usize;
+		let exponent_len = exponent_len.low_u64() as usize;
+		let modulus_len = modulus_len.low_u64() as usize;
+
+		let base_start: usize = 96;
+		let exponent_start = base_start.saturating_add(base_len);
+		let modulus_start = exponent_start.saturating_add(exponent_len);
+
+		let exponent_head = read(input, exponent_start, min(exponent_len, 32));
+		let cost = cost(base_len, exponent_len, modulus_len, &exponent_head)?;
+		ensure_gas(cost, target_gas)?;
+
+		let base = read(input, base_start, base_len);
+		let exponent = read(input, exponent_start, exponent_len);
+		let modulus = read(input, modulus_start, modulus_len);
+
+		let modulus = BigUint::from_bytes_be(&modulus);
+		let mut output = vec![0u8; modulus_len];
+		if modulus != BigUint::from(0u32) {
+			let base = BigUint::from_bytes_be(&base);
+			let exponent = BigUint::from_bytes_be(&exponent);
+			let result = base.modpow(&exponent, &modulus).to_bytes_be();
+			output[modulus_len - result.len()..].copy_from_slice(&result);
+		}
+
+		Ok((ExitSucceed::Returned, output, cost))
+	}
+}
+
+/// `len` bytes of `input` from `start`, missing ones read as zeros.
+fn read(input: &[u8], start: usize, len: usize) -> Vec<u8> {
+	padded(input.get(start..).unwrap_or(&[]), len)
+}
+
+/// The cost of an exponentiation with the given lengths, whose exponent starts with
+/// `exponent_head`, its first 32 bytes at most.
+fn cost(
+	base_len: usize,
+	exponent_len: usize,
+	modulus_len: usize,
+	exponent_head: &[u8],
+) -> Result<usize, ExitError> {
+	let complexity = mult_complexity(U256::from(max(base_len, modulus_len)))
+		.ok_or(ExitError::OutOfGas)?;
+	let iterations = max(adjusted_exponent_len(exponent_len, exponent_head), U256::one());
+	let cost = complexity.checked_mul(iterations).ok_or(ExitError::OutOfGas)?
+		/ U256::from(GQUADDIVISOR);
+	if cost > U256::from(usize::max_value()) {
+		return Err(ExitError::OutOfGas);
+	}
+	Ok(cost.low_u64() as usize)
+}
+
+/// The cost of multiplying numbers of `x` bytes.
+fn mult_complexity(x: U256) -> Option<U256> {
+	let squared = x.checked_mul(x)?;
+	if x <= U256::from(64) {
+		Some(squared)
+	} else if x <= U256::from(1024) {
+		(squared / 4).checked_add(x * 96)?.checked_sub(U256::from(3072))
+	} else {
+		(squared / 16).checked_add(x * 480)?.checked_sub(U256::from(199_680))
+	}
+}
+
+/// The number of bits of the exponent beyond the first, roughly the number of squarings.
+fn adjusted_exponent_len(exponent_len: usize, exponent_head: &[u8]) -> U256 {
+	let head = U256::from_big_endian(exponent_head);
+	let head_bits = U256::from(head.bits().saturating_sub(1));
+	if exponent_len <= 32 {
+		head_bits
+	} else {
+		U256::from(exponent_len - 32) * 8 + head_bits
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use hex_literal::hex;
+
+	#[test]
+	fn fermat_little_theorem_works() {
+		// 3 ** (p - 1) % p, with p = 2 ** 256 - 2 ** 32 - 977, from EIP-198.
+		let input = hex!("
+			0000000000000000000000000000000000000000000000000000000000000001
+			0000000000000000000000000000000000000000000000000000000000000020
+			0000000000000000000000000000000000000000000000000000000000000020
+			03
+			fffffffffffffffffffffffffffffffffffffffffffffffffffffffefffffc2e
+			fffffffffffffffffffffffffffffffffffffffffffffffffffffffefffffc2f
+		");
+		let mut output = vec![0u8; 32];
+		output[31] = 1;
+		assert_eq!(
+			<Modexp as Precompile>::execute(&input, None),
+			Ok((ExitSucceed::Returned, output, 13056)),
+		);
+	}
+
+	#[test]
+	fn zero_modulus_returns_zeros() {
+		// 0 ** (p - 2) % 0, with the modulus read from missing input, from EIP-198.
+		let input = hex!("
+			0000000000000000000000000000000000000000000000000000000000000000
+			0000000000000000000000000000000000000000000000000000000000000020
+			0000000000000000000000000000000000000000000000000000000000000020
+			fffffffffffffffffffffffffffffffffffffffffffffffffffffffefffffc2e
+		");
+		assert_eq!(
+			<Modexp as Precompile>::execute(&input, None),
+			Ok((ExitSucceed::Returned, vec![0u8; 32], 13056)),
+		);
+	}
+
+	#[test]
+	fn huge_lengths_run_out_of_gas() {
+		let mut input = [0u8; 96];
+		input[0..32].copy_from_slice(&[0xff; 32]);
+		assert_eq!(<Modexp as Precompile>::execute(&input, None), Err(ExitError::OutOfGas));
+	}
+
+	#[test]
+	fn lengths_are_bounded_without_a_gas_limit() {
+		let mut input = [0u8; 96];
+		U256::from(MAX_LEN + 1).to_big_endian(&mut input[64..96]);
+		assert_eq!(<Modexp as Precompile>::execute(&input, None), Err(ExitError::OutOfGas));
+		U256::from(MAX_LEN + 1).to_big_endian(&mut input[32..64]);
+		input[64..96].copy_from_slice(&[0; 32]);
+		assert_eq!(<Modexp as Precompile>::execute(&input, None), Err(ExitError::OutOfGas));
+	}
+}
@@ -0,0 +1,115 @@
+// Copyright 2020 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Runtime API definition required by EVM RPC extensions.
+//!
+//! This API should be imported and implemented by the runtime,
+//! of a node that wants to use the custom RPC extension
+//! adding Ethereum-compatible methods.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use sp_core::{H160, H256, U256};
+use sp_runtime::DispatchError;
+use sp_std::vec::Vec;
+
+pub use pallet_evm::{
+	Account, AccountChange, Bloom, BloomInput, Execution, ExitStatus, Log, Receipt, Step,
+	MAX_TRACE_SIZE,
+};
+
+sp_api::decl_runtime_apis! {
+	/// The API to read the state of the EVM and to execute against it without submitting
+	/// extrinsics.
+	pub trait EVMApi {
+		/// The chain id used for replay protection of Ethereum transactions.
+		fn chain_id() -> u64;
+
+		/// The nonce and balance of the account at `address`.
+		fn account_basic(address: H160) -> Account;
+
+		/// The code of the contract at `address`, empty if there is none.
+		fn account_code(address: H160) -> Vec<u8>;
+
+		/// The value stored at `index` by the contract at `address`.
+		fn account_storage(address: H160, index: H256) -> H256;
+
+		/// Execute a call from `from` to `to` without applying it.
+		///
+		/// Returns the output of the call and the gas it used. The minimal gas price is not
+		/// enforced; without `gas_price`, no fee is charged.
+		fn call(
+			from: H160,
+			to: H160,
+			input_data: Vec<u8>,
+			value: U256,
+			gas_limit: U256,
+			gas_price: Option<U256>,
+		) -> Result<(Vec<u8>, U256), DispatchError>;
+
+		/// Execute the creation of a contract by `from` without applying it.
+		///
+		/// Returns the address the contract would have and the gas the creation used. The minimal
+		/// gas price is not enforced; without `gas_price`, no fee is charged.
+		fn create(
+			from: H160,
+			input_data: Vec<u8>,
+			value: U256,
+			gas_limit: U256,
+			gas_price: Option<U256>,
+		) -> Result<(H160, U256), DispatchError>;
+
+		/// Execute a call from `from` to `to` without applying it, and report its whole outcome.
+		///
+		/// With `trace`, the steps of the outermost frame of the call are reported too, up to
+		/// `MAX_TRACE_SIZE`. The minimal gas price is not enforced; without `gas_price`, no fee is
+		/// charged.
+		fn trace_call(
+			from: H160,
+			to: H160,
+			input_data: Vec<u8>,
+			value: U256,
+			gas_limit: U256,
+			gas_price: Option<U256>,
+			trace: bool,
+		) -> Result<Execution, DispatchError>;
+
+		/// Execute the creation of a contract by `from` without applying it, and report its whole
+		/// outcome.
+		///
+		/// With `trace`, the steps of the outermost frame of the creation are reported too, up to
+		/// `MAX_TRACE_SIZE`. The minimal gas price is not enforced; without `gas_price`, no fee is
+		/// charged.
+		fn trace_create(
+			from: H160,
+			input_data: Vec<u8>,
+			value: U256,
+			gas_limit: U256,
+			gas_price: Option<U256>,
+			trace: bool,
+		) -> Result<Execution, DispatchError>;
+
+		/// The receipts of the executions applied in the block.
+		fn current_receipts() -> Vec<Receipt>;
+
+		/// The bloom filter of the logs of the block.
+		fn current_logs_bloom() -> Bloom;
+
+		/// The number of the block holding the receipt of the transaction with the given hash, and
+		/// the index of the receipt in it.
+		fn transaction_location(hash: H256) -> Option<(U256, u32)>;
+	}
+}
@@ -0,0 +1,465 @@
+// Copyright 2020 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Ethereum-compatible RPC methods (`eth_*`) over the EVM pallet, for web3 tooling.
+
+use std::sync::Arc;
+
+use jsonrpc_core::{Error, ErrorCode, Result};
+use jsonrpc_derive::rpc;
+use sp_api::ProvideRuntimeApi;
+use sp_blockchain::HeaderBackend;
+use sp_core::{hexdisplay::HexDisplay, Bytes, H160, H256, U256};
+use sp_runtime::{
+	generic::BlockId,
+	traits::{Block as BlockT, NumberFor, UniqueSaturatedInto},
+};
+
+pub use self::gen_client::Client as EthClient;
+pub use self::eth_debug::gen_client::Client as EthDebugClient;
+pub use self::eth_debug::EthDebugApi;
+pub use pallet_evm_rpc_runtime_api::{self as runtime_api, EVMApi as EVMRuntimeApi};
+
+mod types;
+pub use types::{
+	BlockNumber, BlockTag, CallRequest, Filter, Log, Receipt, StructLog, Trace, ValueOrArray,
+};
+
+const RUNTIME_ERROR: i64 = 1;
+const EXECUTION_ERROR: i64 = 2;
+
+/// The largest number of blocks `eth_getLogs` searches at once.
+const MAX_LOGS_BLOCK_RANGE: u64 = 10_000;
+
+/// Ethereum-compatible RPC methods.
+///
+/// Methods reading the state read it at `number`, or at the best block if `number` is omitted.
+#[rpc]
+pub trait EthApi {
+	/// Returns the chain id used for replay protection.
+	#[rpc(name = "eth_chainId")]
+	fn chain_id(&self) -> Result<U256>;
+
+	/// Returns the number of the best block.
+	#[rpc(name = "eth_blockNumber")]
+	fn block_number(&self) -> Result<U256>;
+
+	/// Returns the balance of the account at `address`.
+	#[rpc(name = "eth_getBalance")]
+	fn balance(&self, address: H160, number: Option<BlockNumber>) -> Result<U256>;
+
+	/// Returns the code of the contract at `address`, empty if there is none.
+	#[rpc(name = "eth_getCode")]
+	fn code_at(&self, address: H160, number: Option<BlockNumber>) -> Result<Bytes>;
+
+	/// Returns the value stored at `index` by the contract at `address`.
+	#[rpc(name = "eth_getStorageAt")]
+	fn storage_at(&self, address: H160, index: U256, number: Option<BlockNumber>) -> Result<H256>;
+
+	/// Returns the nonce of the account at `address`.
+	#[rpc(name = "eth_getTransactionCount")]
+	fn transaction_count(&self, address: H160, number: Option<BlockNumber>) -> Result<U256>;
+
+	/// Executes a call without submitting a transaction and returns its output.
+	///
+	/// If the call reverts, the error holds the revert reason in its message and the revert data
+	/// as its data.
+	#[rpc(name = "eth_call")]
+	fn call(&self, request: CallRequest, number: Option<BlockNumber>) -> Result<Bytes>;
+
+	/// Executes a call, or the creation of a contract, without submitting a transaction and
+	/// returns the least gas limit it succeeds with.
+	#[rpc(name = "eth_estimateGas")]
+	fn estimate_gas(&self, request: CallRequest, number: Option<BlockNumber>) -> Result<U256>;
+
+	/// Returns the receipt of the transaction with the given hash, `None` if it is unknown.
+	///
+	/// Executions submitted as extrinsics rather than Ethereum transactions are found under the
+	/// hash of their extrinsic.
+	#[rpc(name = "eth_getTransactionReceipt")]
+	fn transaction_receipt(&self, hash: H256) -> Result<Option<Receipt>>;
+
+	/// Returns the logs matching `filter`.
+	#[rpc(name = "eth_getLogs")]
+	fn logs(&self, filter: Filter) -> Result<Vec<Log>>;
+}
+
+mod eth_debug {
+	use super::*;
+
+	/// Methods tracing executions, for debugging tools.
+	#[rpc]
+	pub trait EthDebugApi {
+		/// Executes a call, or the creation of a contract, without submitting a transaction and
+		/// returns its steps.
+		///
+		/// Only the opcodes of the outermost frame are traced, each nested call or creation being
+		/// a single step. The steps past `MAX_TRACE_SIZE` bytes of stacks and memories are left
+		/// out.
+		#[rpc(name = "debug_traceCall")]
+		fn trace_call(&self, request: CallRequest, number: Option<BlockNumber>) -> Result<Trace>;
+	}
+}
+
+/// An implementation of the Ethereum-compatible RPC methods.
+pub struct Eth<C, B> {
+	client: Arc<C>,
+	_marker: std::marker::PhantomData<B>,
+}
+
+impl<C, B> Eth<C, B> {
+	/// Create new `Eth` with the given reference to the client.
+	pub fn new(client: Arc<C>) -> Self {
+		Eth {
+			client,
+			_marker: Default::default(),
+		}
+	}
+}
+
+impl<C, Block> Eth<C, Block> where
+	Block: BlockT,
+	C: HeaderBackend<Block>,
+{
+	fn number_of(&self, number: Option<BlockNumber>) -> u64 {
+		match number.unwrap_or_default() {
+			BlockNumber::Tag(BlockTag::Latest) | BlockNumber::Tag(BlockTag::Pending) =>
+				UniqueSaturatedInto::<u64>::unique_saturated_into(self.client.info().best_number),
+			BlockNumber::Tag(BlockTag::Earliest) => 0,
+			BlockNumber::Num(number) => number.low_u64(),
+		}
+	}
+
+	fn block_id(&self, number: Option<BlockNumber>) -> BlockId<Block> {
+		match number.unwrap_or_default() {
+			BlockNumber::Tag(BlockTag::Latest) | BlockNumber::Tag(BlockTag::Pending) =>
+				BlockId::hash(self.client.info().best_hash),
+			BlockNumber::Tag(BlockTag::Earliest) =>
+				BlockId::number(0u32.into()),
+			BlockNumber::Num(number) =>
+				BlockId::number(number.low_u64().unique_saturated_into()),
+		}
+	}
+}
+
+impl<C, Block> Eth<C, Block> where
+	Block: BlockT<Hash = H256>,
+	C: ProvideRuntimeApi<Block> + HeaderBackend<Block>,
+	C::Api: EVMRuntimeApi<Block>,
+{
+	/// Executes `request` at `number` without submitting a transaction, tracing it if `trace` is
+	/// set.
+	fn execute(
+		&self,
+		request: CallRequest,
+		number: Option<BlockNumber>,
+		trace: bool,
+	) -> Result<runtime_api::Execution> {
+		let api = self.client.runtime_api();
+		let at = self.block_id(number);
+		let CallRequest { from, to, gas_price, gas, value, data } = request;
+		let from = from.unwrap_or_default();
+		let data = data.map(|data| data.0).unwrap_or_default();
+		let value = value.unwrap_or_default();
+		let gas = gas.unwrap_or_else(default_gas_limit);
+		match to {
+			Some(to) => api.trace_call(&at, from, to, data, value, gas, gas_price, trace),
+			None => api.trace_create(&at, from, data, value, gas, gas_price, trace),
+		}
+			.map_err(runtime_error_into_rpc_err)?
+			.map_err(execution_error_into_rpc_err)
+	}
+}
+
+impl<C, Block> EthApi for Eth<C, Block> where
+	Block: BlockT<Hash = H256>,
+	C: Send + Sync + 'static + ProvideRuntimeApi<Block> + HeaderBackend<Block>,
+	C::Api: EVMRuntimeApi<Block>,
+{
+	fn chain_id(&self) -> Result<U256> {
+		let api = self.client.runtime_api();
+		api.chain_id(&self.block_id(None))
+			.map(U256::from)
+			.map_err(runtime_error_into_rpc_err)
+	}
+
+	fn block_number(&self) -> Result<U256> {
+		let number: u128 = self.client.info().best_number.unique_saturated_into();
+		Ok(U256::from(number))
+	}
+
+	fn balance(&self, address: H160, number: Option<BlockNumber>) -> Result<U256> {
+		let api = self.client.runtime_api();
+		api.account_basic(&self.block_id(number), address)
+			.map(|account| account.balance)
+			.map_err(runtime_error_into_rpc_err)
+	}
+
+	fn code_at(&self, address: H160, number: Option<BlockNumber>) -> Result<Bytes> {
+		let api = self.client.runtime_api();
+		api.account_code(&self.block_id(number), address)
+			.map(Bytes)
+			.map_err(runtime_error_into_rpc_err)
+	}
+
+	fn storage_at(&self, address: H160, index: U256, number: Option<BlockNumber>) -> Result<H256> {
+		let api = self.client.runtime_api();
+		let mut key = H256::zero();
+		index.to_big_endian(key.as_bytes_mut());
+		api.account_storage(&self.block_id(number), address, key)
+			.map_err(runtime_error_into_rpc_err)
+	}
+
+	fn transaction_count(&self, address: H160, number: Option<BlockNumber>) -> Result<U256> {
+		let api = self.client.runtime_api();
+		api.account_basic(&self.block_id(number), address)
+			.map(|account| account.nonce)
+			.map_err(runtime_error_into_rpc_err)
+	}
+
+	fn call(&self, request: CallRequest, number: Option<BlockNumber>) -> Result<Bytes> {
+		let api = self.client.runtime_api();
+		let CallRequest { from, to, gas_price, gas, value, data } = request;
+		let to = to.ok_or_else(|| Error {
+			code: ErrorCode::InvalidParams,
+			message: "A call must have a recipient".into(),
+			data: None,
+		})?;
+		let execution = api.trace_call(
+			&self.block_id(number),
+			from.unwrap_or_default(),
+			to,
+			data.map(|data| data.0).unwrap_or_default(),
+			value.unwrap_or_default(),
+			gas.unwrap_or_else(default_gas_limit),
+			gas_price,
+			false,
+		)
+			.map_err(runtime_error_into_rpc_err)?
+			.map_err(execution_error_into_rpc_err)?;
+		ensure_succeeded(&execution)?;
+		Ok(Bytes(execution.output))
+	}
+
+	fn estimate_gas(&self, request: CallRequest, number: Option<BlockNumber>) -> Result<U256> {
+		let execution = self.execute(request.clone(), number, false)?;
+		ensure_succeeded(&execution)?;
+
+		// The gas used is after refunds, and the gas the execution needs may be more than it uses,
+		// so the least gas limit it succeeds with is searched for. Less gas than it used fails.
+		let mut failing = execution.used_gas.saturating_sub(U256::one());
+		let mut succeeding = request.gas.unwrap_or_else(default_gas_limit);
+		while succeeding - failing > U256::one() {
+			let gas = failing + (succeeding - failing) / 2;
+			let request = CallRequest { gas: Some(gas), ..request.clone() };
+			match self.execute(request, number, false) {
+				Ok(ref execution) if ensure_succeeded(execution).is_ok() => succeeding = gas,
+				_ => failing = gas,
+			}
+		}
+		Ok(succeeding)
+	}
+
+	fn transaction_receipt(&self, hash: H256) -> Result<Option<Receipt>> {
+		let api = self.client.runtime_api();
+		let location = api.transaction_location(&self.block_id(None), hash)
+			.map_err(runtime_error_into_rpc_err)?;
+		let (number, index) = match location {
+			Some((number, index)) => (number, index as usize),
+			None => return Ok(None),
+		};
+		let block_number: NumberFor<Block> = number.low_u64().unique_saturated_into();
+		let block_hash = match self.client.hash(block_number).map_err(client_error_into_rpc_err)? {
+			Some(block_hash) => block_hash,
+			None => return Ok(None),
+		};
+
+		let receipts = api.current_receipts(&BlockId::hash(block_hash))
+			.map_err(runtime_error_into_rpc_err)?;
+		let first_log_index = receipts.iter().take(index).map(|receipt| receipt.logs.len()).sum();
+		Ok(receipts.into_iter().nth(index).map(|receipt| Receipt {
+			transaction_hash: receipt.transaction_hash,
+			transaction_index: receipt.transaction_index.into(),
+			block_hash,
+			block_number: number,
+			from: receipt.from,
+			to: receipt.to,
+			cumulative_gas_used: receipt.cumulative_gas_used,
+			gas_used: receipt.used_gas,
+			contract_address: receipt.contract_address,
+			logs: logs_of(&receipt, block_hash, number, first_log_index).collect(),
+			logs_bloom: receipt.logs_bloom,
+			status: if receipt.status { U256::one() } else { U256::zero() },
+		}))
+	}
+
+	fn logs(&self, filter: Filter) -> Result<Vec<Log>> {
+		let blocks: Vec<(H256, u64)> = match filter.block_hash {
+			Some(hash) => {
+				let number = self.client.number(hash).map_err(client_error_into_rpc_err)?
+					.ok_or_else(|| Error {
+						code: ErrorCode::InvalidParams,
+						message: "Unknown block".into(),
+						data: None,
+					})?;
+				vec![(hash, UniqueSaturatedInto::<u64>::unique_saturated_into(number))]
+			},
+			None => {
+				let from = self.number_of(filter.from_block);
+				let to = self.number_of(filter.to_block);
+				if to.saturating_sub(from) >= MAX_LOGS_BLOCK_RANGE {
+					return Err(Error {
+						code: ErrorCode::InvalidParams,
+						message: format!("At most {} blocks can be searched", MAX_LOGS_BLOCK_RANGE),
+						data: None,
+					});
+				}
+				let mut blocks = Vec::new();
+				for number in from..=to {
+					match self.client.hash(number.unique_saturated_into())
+						.map_err(client_error_into_rpc_err)?
+					{
+						Some(hash) => blocks.push((hash, number)),
+						// Beyond the best block.
+						None => break,
+					}
+				}
+				blocks
+			},
+		};
+
+		let api = self.client.runtime_api();
+		let mut logs = Vec::new();
+		for (block_hash, number) in blocks {
+			let at = BlockId::hash(block_hash);
+			let bloom = api.current_logs_bloom(&at).map_err(runtime_error_into_rpc_err)?;
+			if !filter.matches_bloom(&bloom) {
+				continue;
+			}
+			let mut first_log_index = 0;
+			for receipt in api.current_receipts(&at).map_err(runtime_error_into_rpc_err)? {
+				logs.extend(
+					logs_of(&receipt, block_hash, number.into(), first_log_index)
+						.filter(|log| filter.matches(&log.address, &log.topics)),
+				);
+				first_log_index += receipt.logs.len();
+			}
+		}
+		Ok(logs)
+	}
+}
+
+impl<C, Block> EthDebugApi for Eth<C, Block> where
+	Block: BlockT<Hash = H256>,
+	C: Send + Sync + 'static + ProvideRuntimeApi<Block> + HeaderBackend<Block>,
+	C::Api: EVMRuntimeApi<Block>,
+{
+	fn trace_call(&self, request: CallRequest, number: Option<BlockNumber>) -> Result<Trace> {
+		let execution = self.execute(request, number, true)?;
+		Ok(Trace {
+			gas: execution.used_gas,
+			failed: execution.exit_status != runtime_api::ExitStatus::Succeed,
+			return_value: Bytes(execution.output),
+			struct_logs: execution.steps.unwrap_or_default().into_iter()
+				.map(|step| StructLog {
+					pc: step.pc,
+					opcode: step.opcode,
+					gas: step.gas,
+					gas_cost: step.gas_cost,
+					stack: step.stack,
+					memory: Bytes(step.memory),
+				})
+				.collect(),
+			truncated: execution.truncated,
+		})
+	}
+}
+
+/// Fails with an RPC error if `execution` did not succeed.
+///
+/// The message of a revert holds its reason, if any, and the data of the error is the revert data.
+fn ensure_succeeded(execution: &runtime_api::Execution) -> Result<()> {
+	match &execution.exit_status {
+		runtime_api::ExitStatus::Succeed => Ok(()),
+		runtime_api::ExitStatus::Revert => Err(Error {
+			code: ErrorCode::ServerError(EXECUTION_ERROR),
+			message: match execution.revert_reason() {
+				Some(reason) => format!("Execution reverted: {}", String::from_utf8_lossy(reason)),
+				None => "Execution reverted".into(),
+			},
+			data: Some(format!("0x{}", HexDisplay::from(&execution.output)).into()),
+		}),
+		runtime_api::ExitStatus::Error(description) | runtime_api::ExitStatus::Fatal(description) =>
+			Err(Error {
+				code: ErrorCode::ServerError(EXECUTION_ERROR),
+				message: "Execution failed".into(),
+				data: Some(String::from_utf8_lossy(description).into_owned().into()),
+			}),
+	}
+}
+
+/// The logs of `receipt`, in the block with the given hash and number, the first one having
+/// `first_log_index` in the block.
+fn logs_of<'a>(
+	receipt: &'a runtime_api::Receipt,
+	block_hash: H256,
+	block_number: U256,
+	first_log_index: usize,
+) -> impl Iterator<Item = Log> + 'a {
+	receipt.logs.iter().enumerate().map(move |(index, log)| Log {
+		address: log.address,
+		topics: log.topics.clone(),
+		data: Bytes(log.data.clone()),
+		block_hash,
+		block_number,
+		transaction_hash: receipt.transaction_hash,
+		transaction_index: receipt.transaction_index.into(),
+		log_index: (first_log_index + index).into(),
+		removed: false,
+	})
+}
+
+/// The gas limit of calls that do not set one.
+fn default_gas_limit() -> U256 {
+	U256::from(u32::max_value())
+}
+
+/// Converts a runtime trap into an RPC error.
+fn runtime_error_into_rpc_err(err: impl std::fmt::Debug) -> Error {
+	Error {
+		code: ErrorCode::ServerError(RUNTIME_ERROR),
+		message: "Runtime trapped".into(),
+		data: Some(format!("{:?}", err).into()),
+	}
+}
+
+/// Converts an error of the client into an RPC error.
+fn client_error_into_rpc_err(err: impl std::fmt::Debug) -> Error {
+	Error {
+		code: ErrorCode::InternalError,
+		message: "Client error".into(),
+		data: Some(format!("{:?}", err).into()),
+	}
+}
+
+/// Converts a failed execution into an RPC error.
+fn execution_error_into_rpc_err(err: impl std::fmt::Debug) -> Error {
+	Error {
+		code: ErrorCode::ServerError(EXECUTION_ERROR),
+		message: "Execution failed".into(),
+		data: Some(format!("{:?}", err).into()),
+	}
+}
@@ -0,0 +1,331 @@
+// Copyright 2020 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Types of the parameters of the Ethereum-compatible RPC methods.
+
+use serde::{Deserialize, Serialize};
+use sp_core::{Bytes, H160, H256, U256};
+use pallet_evm_rpc_runtime_api::{Bloom, BloomInput};
+
+/// The block a method reads the state at.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum BlockNumber {
+	/// One of the named blocks.
+	Tag(BlockTag),
+	/// The block with the given number.
+	Num(U256),
+}
+
+impl Default for BlockNumber {
+	fn default() -> Self {
+		BlockNumber::Tag(BlockTag::Latest)
+	}
+}
+
+/// A named block.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BlockTag {
+	/// The best block.
+	Latest,
+	/// The genesis block.
+	Earliest,
+	/// The block being built; the best block, as pending transactions are not considered.
+	Pending,
+}
+
+/// A call to execute without submitting a transaction.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct CallRequest {
+	/// The account executing the call; the zero address if omitted.
+	pub from: Option<H160>,
+	/// The account called; a contract is created from `data` if omitted.
+	pub to: Option<H160>,
+	/// The gas price; no fee is charged if omitted.
+	pub gas_price: Option<U256>,
+	/// The gas limit.
+	pub gas: Option<U256>,
+	/// The value transferred.
+	pub value: Option<U256>,
+	/// The input of the call, or the init code of the contract to create.
+	pub data: Option<Bytes>,
+}
+
+/// A single value, or any of several.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum ValueOrArray<T> {
+	/// The value.
+	Value(T),
+	/// Any of the values.
+	Array(Vec<T>),
+}
+
+impl<T> ValueOrArray<T> {
+	/// The values.
+	pub fn values(&self) -> &[T] {
+		match self {
+			ValueOrArray::Value(value) => std::slice::from_ref(value),
+			ValueOrArray::Array(values) => values,
+		}
+	}
+}
+
+/// A filter of logs.
+///
+/// A log matches if it is in the blocks, from one of the addresses, and has, at each position of
+/// `topics`, one of the topics given there. A missing address or topic matches anything, but the
+/// log must have a topic at each position of `topics`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct Filter {
+	/// The first block; the best block if omitted.
+	pub from_block: Option<BlockNumber>,
+	/// The last block; the best block if omitted.
+	pub to_block: Option<BlockNumber>,
+	/// The only block, instead of `from_block` and `to_block`.
+	pub block_hash: Option<H256>,
+	/// The addresses of the accounts emitting the logs.
+	pub address: Option<ValueOrArray<H160>>,
+	/// The topics of the logs, by position.
+	pub topics: Option<Vec<Option<ValueOrArray<H256>>>>,
+}
+
+impl Filter {
+	/// Whether the logs of a block whose bloom filter is `bloom` may match.
+	pub fn matches_bloom(&self, bloom: &Bloom) -> bool {
+		let in_bloom = |bytes: &[u8]| bloom.contains_input(BloomInput::Raw(bytes));
+		matches_any(&self.address, |address| in_bloom(address.as_bytes())) &&
+			self.topics.iter().flatten().all(|topics| {
+				matches_any(topics, |topic| in_bloom(topic.as_bytes()))
+			})
+	}
+
+	/// Whether a log emitted by `address` with `topics` matches.
+	pub fn matches(&self, address: &H160, topics: &[H256]) -> bool {
+		matches_any(&self.address, |wanted| wanted == address) &&
+			self.topics.iter().flatten().enumerate().all(|(position, wanted)| {
+				topics.get(position).map_or(false, |topic| {
+					matches_any(wanted, |wanted| wanted == topic)
+				})
+			})
+	}
+}
+
+/// Whether one of the `wanted` values passes `matches`; true if none is wanted.
+fn matches_any<T>(wanted: &Option<ValueOrArray<T>>, matches: impl Fn(&T) -> bool) -> bool {
+	match wanted {
+		Some(wanted) if !wanted.values().is_empty() => wanted.values().iter().any(matches),
+		_ => true,
+	}
+}
+
+/// A log emitted by an execution.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Log {
+	/// The account emitting the log.
+	pub address: H160,
+	/// The topics of the log.
+	pub topics: Vec<H256>,
+	/// The data of the log.
+	pub data: Bytes,
+	/// The hash of the block.
+	pub block_hash: H256,
+	/// The number of the block.
+	pub block_number: U256,
+	/// The hash of the transaction.
+	pub transaction_hash: H256,
+	/// The index of the transaction in the block.
+	pub transaction_index: U256,
+	/// The index of the log in the block.
+	pub log_index: U256,
+	/// Whether the log was removed by a reorganisation; always false, as only logs of canonical
+	/// blocks are returned.
+	pub removed: bool,
+}
+
+/// The receipt of an execution.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Receipt {
+	/// The hash of the transaction.
+	pub transaction_hash: H256,
+	/// The index of the transaction in the block.
+	pub transaction_index: U256,
+	/// The hash of the block.
+	pub block_hash: H256,
+	/// The number of the block.
+	pub block_number: U256,
+	/// The account the execution is from.
+	pub from: H160,
+	/// The account called, `None` for the creation of a contract.
+	pub to: Option<H160>,
+	/// The gas used by the executions of the block up to and including this one.
+	pub cumulative_gas_used: U256,
+	/// The gas used by the execution.
+	pub gas_used: U256,
+	/// The address of the created contract, `None` for a call.
+	pub contract_address: Option<H160>,
+	/// The logs emitted by the execution.
+	pub logs: Vec<Log>,
+	/// The bloom filter of the logs.
+	pub logs_bloom: Bloom,
+	/// One if the execution succeeded, zero otherwise.
+	pub status: U256,
+}
+
+/// The steps of an execution.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Trace {
+	/// The gas used by the execution.
+	pub gas: U256,
+	/// Whether the execution failed.
+	pub failed: bool,
+	/// The returned data, or the revert data, of a call; empty for a creation.
+	pub return_value: Bytes,
+	/// The steps of the outermost frame of the execution.
+	pub struct_logs: Vec<StructLog>,
+	/// Whether steps were left out of `struct_logs`, the trace being too large.
+	pub truncated: bool,
+}
+
+/// A step of an execution: the state of the machine before an opcode.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StructLog {
+	/// The position of the opcode in the code.
+	pub pc: u32,
+	/// The opcode.
+	pub opcode: u8,
+	/// The gas left.
+	pub gas: U256,
+	/// The gas the opcode costs.
+	pub gas_cost: U256,
+	/// The stack, its top last.
+	pub stack: Vec<H256>,
+	/// The memory.
+	pub memory: Bytes,
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn block_numbers_deserialize() {
+		let numbers: Vec<BlockNumber> = serde_json::from_str(r#"["latest", "earliest", "pending", "0x2a"]"#).unwrap();
+		assert_eq!(numbers, vec![
+			BlockNumber::Tag(BlockTag::Latest),
+			BlockNumber::Tag(BlockTag::Earliest),
+			BlockNumber::Tag(BlockTag::Pending),
+			BlockNumber::Num(42.into()),
+		]);
+	}
+
+	#[test]
+	fn call_requests_deserialize() {
+		let request: CallRequest = serde_json::from_str(r#"{
+			"from": "0x0000000000000000000000000000000000000001",
+			"to": "0x0000000000000000000000000000000000000002",
+			"gasPrice": "0x1",
+			"data": "0x1234"
+		}"#).unwrap();
+		assert_eq!(request, CallRequest {
+			from: Some(H160::from_low_u64_be(1)),
+			to: Some(H160::from_low_u64_be(2)),
+			gas_price: Some(1.into()),
+			gas: None,
+			value: None,
+			data: Some(Bytes(vec![0x12, 0x34])),
+		});
+	}
+
+	#[test]
+	fn filters_deserialize() {
+		let filter: Filter = serde_json::from_str(r#"{
+			"fromBlock": "earliest",
+			"address": "0x0000000000000000000000000000000000000001",
+			"topics": [
+				null,
+				[
+					"0x0000000000000000000000000000000000000000000000000000000000000002",
+					"0x0000000000000000000000000000000000000000000000000000000000000003"
+				]
+			]
+		}"#).unwrap();
+		assert_eq!(filter, Filter {
+			from_block: Some(BlockNumber::Tag(BlockTag::Earliest)),
+			to_block: None,
+			block_hash: None,
+			address: Some(ValueOrArray::Value(H160::from_low_u64_be(1))),
+			topics: Some(vec![
+				None,
+				Some(ValueOrArray::Array(vec![H256::from_low_u64_be(2), H256::from_low_u64_be(3)])),
+			]),
+		});
+	}
+
+	#[test]
+	fn filters_match_positions_of_topics() {
+		let address = H160::from_low_u64_be(1);
+		let topics = [H256::from_low_u64_be(2), H256::from_low_u64_be(3)];
+		let filter = |address, topics| Filter {
+			address,
+			topics: Some(topics),
+			..Default::default()
+		};
+
+		assert!(Filter::default().matches(&address, &topics));
+		assert!(filter(Some(ValueOrArray::Array(vec![])), vec![]).matches(&address, &topics));
+		assert!(filter(
+			Some(ValueOrArray::Value(address)),
+			vec![None, Some(ValueOrArray::Value(topics[1]))],
+		).matches(&address, &topics));
+		assert!(!filter(
+			Some(ValueOrArray::Value(H160::from_low_u64_be(4))),
+			vec![],
+		).matches(&address, &topics));
+		assert!(!filter(
+			None,
+			vec![Some(ValueOrArray::Value(topics[1]))],
+		).matches(&address, &topics));
+		assert!(!filter(
+			None,
+			vec![None, None, Some(ValueOrArray::Value(topics[0]))],
+		).matches(&address, &topics));
+	}
+
+	#[test]
+	fn filters_match_blooms() {
+		let address = H160::from_low_u64_be(1);
+		let topic = H256::from_low_u64_be(2);
+		let mut bloom = Bloom::default();
+		bloom.accrue(BloomInput::Raw(address.as_bytes()));
+		bloom.accrue(BloomInput::Raw(topic.as_bytes()));
+
+		let filter = Filter {
+			address: Some(ValueOrArray::Array(vec![H160::from_low_u64_be(4), address])),
+			topics: Some(vec![Some(ValueOrArray::Value(topic))]),
+			..Default::default()
+		};
+		assert!(filter.matches_bloom(&bloom));
+		assert!(!filter.matches_bloom(&Bloom::default()));
+	}
+}
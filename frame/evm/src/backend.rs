@@ -5,10 +5,10 @@ use serde::{Serialize, Deserialize};
 use codec::{Encode, Decode};
 use sp_core::{U256, H256, H160};
 use sp_runtime::traits::UniqueSaturatedInto;
-use frame_support::storage::{StorageMap, StorageDoubleMap};
+use frame_support::{storage::{StorageMap, StorageDoubleMap}, traits::Get};
 use sha3::{Keccak256, Digest};
 use evm::backend::{Backend as BackendT, ApplyBackend, Apply};
-use crate::{Trait, Accounts, AccountStorages, AccountCodes, Module, Event};
+use crate::{Trait, Accounts, AccountStorages, AccountCodes, Module, Event, ConvertAccountId};
 
 #[derive(Clone, Eq, PartialEq, Encode, Decode, Default)]
 #[cfg_attr(feature = "std", derive(Debug, Serialize, Deserialize))]
@@ -74,7 +74,7 @@ impl<'vicinity, T: Trait> BackendT for Backend<'vicinity, T> {
 	}
 
 	fn block_coinbase(&self) -> H160 {
-		H160::default()
+		T::ConvertAccountId::convert_account_id(&T::BlockAuthor::get())
 	}
 
 	fn block_timestamp(&self) -> U256 {
@@ -83,19 +83,22 @@ impl<'vicinity, T: Trait> BackendT for Backend<'vicinity, T> {
 	}
 
 	fn block_difficulty(&self) -> U256 {
+		// Blocks are not mined, so they have no difficulty.
 		U256::zero()
 	}
 
 	fn block_gas_limit(&self) -> U256 {
-		U256::zero()
+		T::BlockGasLimit::get()
 	}
 
 	fn chain_id(&self) -> U256 {
 		U256::from(sp_io::misc::chain_id())
 	}
 
-	fn exists(&self, _address: H160) -> bool {
-		true
+	fn exists(&self, address: H160) -> bool {
+		// Empty accounts are removed when changes are applied, so any account left in storage
+		// exists.
+		Accounts::contains_key(&address) || AccountCodes::contains_key(&address)
 	}
 
 	fn basic(&self, address: H160) -> evm::backend::Basic {
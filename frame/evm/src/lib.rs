@@ -20,19 +20,33 @@
 #![cfg_attr(not(feature = "std"), no_std)]
 
 mod backend;
+mod receipt;
+mod trace;
+mod transaction;
 
 pub use crate::backend::{Account, Log, Vicinity, Backend};
+pub use crate::receipt::{Receipt, logs_bloom};
+pub use crate::trace::{AccountChange, Execution, ExitStatus, Step, MAX_TRACE_SIZE};
+pub use crate::transaction::{Transaction, TransactionAction};
+pub use ethbloom::{Bloom, Input as BloomInput};
 
-use sp_std::{vec::Vec, marker::PhantomData};
+use sp_std::{prelude::*, marker::PhantomData};
+use codec::Encode;
 use frame_support::{ensure, decl_module, decl_storage, decl_event, decl_error};
-use frame_support::weights::{Weight, DispatchClass, FunctionOf};
-use frame_support::traits::{Currency, WithdrawReason, ExistenceRequirement};
-use frame_system::{self as system, ensure_signed};
+use frame_support::weights::{Weight, DispatchClass, FunctionOf, WeighData};
+use frame_support::traits::{
+	Currency, WithdrawReason, ExistenceRequirement, Get, Imbalance, OnUnbalanced,
+};
+use frame_system::{self as system, ensure_signed, ensure_root, ensure_none};
 use sp_runtime::ModuleId;
 use frame_support::weights::SimpleDispatchInfo;
 use sp_core::{U256, H256, H160, Hasher};
 use sp_runtime::{
-	DispatchResult, traits::{UniqueSaturatedInto, AccountIdConversion, SaturatedConversion},
+	DispatchResult, traits::{UniqueSaturatedInto, AccountIdConversion, SaturatedConversion, Zero},
+	transaction_validity::{
+		TransactionValidity, TransactionSource, TransactionPriority, TransactionLongevity,
+		ValidTransaction, InvalidTransaction,
+	},
 };
 use sha3::{Digest, Keccak256};
 use evm::{ExitReason, ExitSucceed, ExitError, Config};
@@ -43,6 +57,9 @@ const MODULE_ID: ModuleId = ModuleId(*b"py/ethvm");
 
 /// Type alias for currency balance.
 pub type BalanceOf<T> = <<T as Trait>::Currency as Currency<<T as frame_system::Trait>::AccountId>>::Balance;
+/// Type alias for the negative imbalance of the currency.
+pub type NegativeImbalanceOf<T> =
+	<<T as Trait>::Currency as Currency<<T as frame_system::Trait>::AccountId>>::NegativeImbalance;
 
 /// Trait that outputs the current transaction gas price.
 pub trait FeeCalculator {
@@ -116,6 +133,76 @@ impl Precompiles for () {
 	}
 }
 
+/// Handler for the fees of EVM executions.
+///
+/// The fee for the gas an execution used is taken from the EVM account of its payer, whose funds
+/// are held by the account of the module in `Currency`. The handler withdraws the most the
+/// execution may cost on the side of `Currency` before it runs, and settles the fee once it is
+/// known, so that the account of the module keeps holding exactly the funds of the EVM accounts.
+pub trait OnChargeEVMTransaction<T: Trait> {
+	/// The fee withdrawn before an execution, held until the execution is settled.
+	type LiquidityInfo;
+
+	/// Withdraw `fee`, the most the execution of the EVM account `who` may cost, before it runs.
+	///
+	/// The execution is refused if this fails.
+	fn withdraw_fee(who: &H160, fee: U256) -> Result<Self::LiquidityInfo, Error<T>>;
+
+	/// Settle `fee`, the part of the `withdrawn` fee the execution of `who` used, and refund the
+	/// rest.
+	fn correct_and_deposit_fee(who: &H160, fee: U256, withdrawn: Self::LiquidityInfo);
+}
+
+/// Burn the fees.
+impl<T: Trait> OnChargeEVMTransaction<T> for () {
+	type LiquidityInfo = Option<NegativeImbalanceOf<T>>;
+
+	fn withdraw_fee(who: &H160, fee: U256) -> Result<Self::LiquidityInfo, Error<T>> {
+		<EVMCurrencyAdapter<()> as OnChargeEVMTransaction<T>>::withdraw_fee(who, fee)
+	}
+
+	fn correct_and_deposit_fee(who: &H160, fee: U256, withdrawn: Self::LiquidityInfo) {
+		<EVMCurrencyAdapter<()> as OnChargeEVMTransaction<T>>::correct_and_deposit_fee(
+			who,
+			fee,
+			withdrawn,
+		)
+	}
+}
+
+/// Withdraw the fees from the account of the module and hand them to `OU`.
+pub struct EVMCurrencyAdapter<OU>(PhantomData<OU>);
+
+impl<T: Trait, OU: OnUnbalanced<NegativeImbalanceOf<T>>> OnChargeEVMTransaction<T>
+	for EVMCurrencyAdapter<OU>
+{
+	type LiquidityInfo = Option<NegativeImbalanceOf<T>>;
+
+	fn withdraw_fee(_who: &H160, fee: U256) -> Result<Self::LiquidityInfo, Error<T>> {
+		let fee = fee.saturated_into::<u128>().saturated_into::<BalanceOf<T>>();
+		if fee.is_zero() {
+			return Ok(None);
+		}
+		// The account of the module holds the funds of all EVM accounts, so it must not be reaped.
+		let imbalance = T::Currency::withdraw(
+			&Module::<T>::account_id(),
+			fee,
+			WithdrawReason::Fee.into(),
+			ExistenceRequirement::KeepAlive,
+		).map_err(|_| Error::<T>::WithdrawFailed)?;
+		Ok(Some(imbalance))
+	}
+
+	fn correct_and_deposit_fee(_who: &H160, fee: U256, withdrawn: Self::LiquidityInfo) {
+		if let Some(withdrawn) = withdrawn {
+			let fee = fee.saturated_into::<u128>().saturated_into::<BalanceOf<T>>();
+			let (fee, refund) = withdrawn.split(fee);
+			T::Currency::resolve_creating(&Module::<T>::account_id(), refund);
+			OU::on_unbalanced(fee);
+		}
+	}
+}
+
 static ISTANBUL_CONFIG: Config = Config::istanbul();
 
 /// EVM module trait
@@ -130,6 +217,15 @@ pub trait Trait: frame_system::Trait + pallet_timestamp::Trait {
 	type Event: From<Event> + Into<<Self as frame_system::Trait>::Event>;
 	/// Precompiles associated with this EVM engine.
 	type Precompiles: Precompiles;
+	/// The author of the current block, whose address is the coinbase.
+	type BlockAuthor: Get<Self::AccountId>;
+	/// The gas limit of a block, which the executions applied in a block may not exceed in total.
+	type BlockGasLimit: Get<U256>;
+	/// Handler for the fees of executions.
+	type OnChargeTransaction: OnChargeEVMTransaction<Self>;
+	/// The number of blocks the locations of the transactions of a block are kept for, after
+	/// which their receipts are not found by their hashes anymore. Zero keeps them forever.
+	type TransactionRetention: Get<Self::BlockNumber>;
 
 	/// EVM config used in the module.
 	fn config() -> &'static Config {
@@ -140,8 +236,20 @@ pub trait Trait: frame_system::Trait + pallet_timestamp::Trait {
 decl_storage! {
 	trait Store for Module<T: Trait> as EVM {
 		Accounts get(fn accounts) config(): map hasher(blake2_128_concat) H160 => Account;
-		AccountCodes: map hasher(blake2_128_concat) H160 => Vec<u8>;
-		AccountStorages: double_map hasher(blake2_128_concat) H160, hasher(blake2_128_concat) H256 => H256;
+		AccountCodes get(fn account_codes): map hasher(blake2_128_concat) H160 => Vec<u8>;
+		AccountStorages get(fn account_storages): double_map hasher(blake2_128_concat) H160, hasher(blake2_128_concat) H256 => H256;
+		/// The minimal gas price when the `Module` is used as the `FeeCalculator`.
+		MinGasPrice get(fn min_gas_price) config(): U256;
+		/// The receipts of the executions applied in the current block.
+		Receipts get(fn receipts): Vec<Receipt>;
+		/// The bloom filter of the logs of the current block.
+		LogsBloom get(fn logs_bloom): Bloom;
+		/// The block and the index in it of the receipt of each execution of the last
+		/// `TransactionRetention` blocks, by transaction hash.
+		TransactionLocations get(fn transaction_location):
+			map hasher(identity) H256 => Option<(T::BlockNumber, u32)>;
+		/// The hashes of the transactions of each block whose locations are kept.
+		BlockTransactions: map hasher(twox_64_concat) T::BlockNumber => Vec<H256>;
 	}
 }
 
@@ -152,6 +260,8 @@ decl_event! {
 		Log(Log),
 		/// A contract has been created at given address.
 		Created(H160),
+		/// The minimal gas price has been set to given value.
+		MinGasPriceSet(U256),
 	}
 }
 
@@ -175,6 +285,14 @@ decl_error! {
 		ExitReasonFatal,
 		/// Nonce is invalid
 		InvalidNonce,
+		/// The transaction is not a valid RLP encoding of an Ethereum transaction
+		MalformedTransaction,
+		/// The transaction is not signed for this chain
+		InvalidChainId,
+		/// The sender of the transaction could not be recovered from its signature
+		InvalidSignature,
+		/// The gas limit is higher than the gas left in the block
+		GasLimitTooHigh,
 	}
 }
 
@@ -184,6 +302,34 @@ decl_module! {
 
 		fn deposit_event() = default;
 
+		fn on_initialize(n: T::BlockNumber) -> Weight {
+			Receipts::kill();
+			LogsBloom::kill();
+			let mut weight = SimpleDispatchInfo::default().weigh_data(());
+
+			// Forget the locations of the transactions of the block leaving the retention window.
+			let retention = T::TransactionRetention::get();
+			if !retention.is_zero() && n > retention {
+				let hashes = BlockTransactions::<T>::take(n - retention);
+				for hash in &hashes {
+					TransactionLocations::<T>::remove(hash);
+				}
+				weight = weight.saturating_add((hashes.len() as Weight).saturating_mul(5_000));
+			}
+
+			weight
+		}
+
+		/// Set the minimal gas price used when the `Module` is the `FeeCalculator`.
+		///
+		/// The dispatch origin for this call must be _Root_.
+		#[weight = SimpleDispatchInfo::FixedOperational(10_000)]
+		fn set_min_gas_price(origin, price: U256) {
+			ensure_root(origin)?;
+			MinGasPrice::put(price);
+			Module::<T>::deposit_event(Event::MinGasPriceSet(price));
+		}
+
 		/// Deposit balance from currency/balances module into EVM.
 		#[weight = SimpleDispatchInfo::FixedNormal(10_000)]
 		fn deposit_balance(origin, value: BalanceOf<T>) {
@@ -228,7 +374,7 @@ decl_module! {
 		}
 
 		/// Issue an EVM call operation. This is similar to a message call transaction in Ethereum.
-		#[weight = FunctionOf(|(_, _, _, gas_limit, gas_price, _): (&H160, &Vec<u8>, &U256, &u32, &U256, &Option<U256>)| (*gas_price).saturated_into::<Weight>().saturating_mul(*gas_limit), DispatchClass::Normal, true)]
+		#[weight = FunctionOf(|(_, _, _, gas_limit, _, _): (&H160, &Vec<u8>, &U256, &u32, &U256, &Option<U256>)| *gas_limit, DispatchClass::Normal, true)]
 		fn call(
 			origin,
 			target: H160,
@@ -241,25 +387,23 @@ decl_module! {
 			let sender = ensure_signed(origin)?;
 			let source = T::ConvertAccountId::convert_account_id(&sender);
 
-			Self::execute_evm(
+			let (reason, _, used_gas, logs) = Self::execute_call(
 				source,
+				target,
+				input,
 				value,
 				gas_limit,
 				gas_price,
 				nonce,
-				|executor| ((), executor.transact_call(
-					source,
-					target,
-					value,
-					input,
-					gas_limit as usize,
-				)),
-			).map_err(Into::into)
+				true,
+			)?;
+			Self::note_receipt(None, source, Some(target), None, &reason, used_gas, logs);
+			Self::exit_result(reason).map_err(Into::into)
 		}
 
 		/// Issue an EVM create operation. This is similar to a contract creation transaction in
 		/// Ethereum.
-		#[weight = FunctionOf(|(_, _, gas_limit, gas_price, _): (&Vec<u8>, &U256, &u32, &U256, &Option<U256>)| (*gas_price).saturated_into::<Weight>().saturating_mul(*gas_limit), DispatchClass::Normal, true)]
+		#[weight = FunctionOf(|(_, _, gas_limit, _, _): (&Vec<u8>, &U256, &u32, &U256, &Option<U256>)| *gas_limit, DispatchClass::Normal, true)]
 		fn create(
 			origin,
 			init: Vec<u8>,
@@ -271,30 +415,24 @@ decl_module! {
 			let sender = ensure_signed(origin)?;
 			let source = T::ConvertAccountId::convert_account_id(&sender);
 
-			let create_address = Self::execute_evm(
+			let (reason, create_address, used_gas, logs) = Self::execute_create(
 				source,
+				init,
 				value,
 				gas_limit,
 				gas_price,
 				nonce,
-				|executor| {
-					(executor.create_address(
-						evm::CreateScheme::Legacy { caller: source },
-					), executor.transact_create(
-						source,
-						value,
-						init,
-						gas_limit as usize,
-					))
-				},
+				true,
 			)?;
+			Self::note_receipt(None, source, None, Some(create_address), &reason, used_gas, logs);
+			Self::exit_result(reason)?;
 
 			Module::<T>::deposit_event(Event::Created(create_address));
 			Ok(())
 		}
 
 		/// Issue an EVM create2 operation.
-		#[weight = FunctionOf(|(_, _, _, gas_limit, gas_price, _): (&Vec<u8>, &H256, &U256, &u32, &U256, &Option<U256>)| (*gas_price).saturated_into::<Weight>().saturating_mul(*gas_limit), DispatchClass::Normal, true)]
+		#[weight = FunctionOf(|(_, _, _, gas_limit, _, _): (&Vec<u8>, &H256, &U256, &u32, &U256, &Option<U256>)| *gas_limit, DispatchClass::Normal, true)]
 		fn create2(
 			origin,
 			init: Vec<u8>,
@@ -308,28 +446,100 @@ decl_module! {
 			let source = T::ConvertAccountId::convert_account_id(&sender);
 
 			let code_hash = H256::from_slice(Keccak256::digest(&init).as_slice());
-			let create_address = Self::execute_evm(
+			let (reason, create_address, used_gas, logs, _) = Self::execute_evm(
 				source,
 				value,
 				gas_limit,
 				gas_price,
 				nonce,
+				true,
 				|executor| {
-					(executor.create_address(
+					let address = executor.create_address(
 						evm::CreateScheme::Create2 { caller: source, code_hash, salt },
-					), executor.transact_create2(
+					);
+					(executor.transact_create2(
 						source,
 						value,
 						init,
 						salt,
 						gas_limit as usize,
-					))
+					), address)
 				},
 			)?;
+			Self::note_receipt(None, source, None, Some(create_address), &reason, used_gas, logs);
+			Self::exit_result(reason)?;
 
 			Module::<T>::deposit_event(Event::Created(create_address));
 			Ok(())
 		}
+
+		/// Issue a raw signed Ethereum transaction, RLP-encoded and signed for this chain following
+		/// EIP-155.
+		///
+		/// The dispatch origin for this call must be _None_: the sender is recovered from the
+		/// signature, and the nonce of the transaction must be the current nonce of the sender.
+		#[weight = FunctionOf(|(transaction,): (&Vec<u8>,)| Module::<T>::transaction_weight(transaction), DispatchClass::Normal, true)]
+		fn transact(origin, transaction: Vec<u8>) -> DispatchResult {
+			ensure_none(origin)?;
+			let (transaction, source) = Self::decode_transaction(&transaction)?;
+			let transaction_hash = transaction.hash();
+
+			match transaction.action {
+				TransactionAction::Call(target) => {
+					let (reason, _, used_gas, logs) = Self::execute_call(
+						source,
+						target,
+						transaction.input,
+						transaction.value,
+						transaction.gas_limit.low_u32(),
+						transaction.gas_price,
+						Some(transaction.nonce),
+						true,
+					)?;
+					Self::note_receipt(
+						Some(transaction_hash),
+						source,
+						Some(target),
+						None,
+						&reason,
+						used_gas,
+						logs,
+					);
+					Self::exit_result(reason).map_err(Into::into)
+				},
+				TransactionAction::Create => {
+					let (reason, create_address, used_gas, logs) = Self::execute_create(
+						source,
+						transaction.input,
+						transaction.value,
+						transaction.gas_limit.low_u32(),
+						transaction.gas_price,
+						Some(transaction.nonce),
+						true,
+					)?;
+					Self::note_receipt(
+						Some(transaction_hash),
+						source,
+						None,
+						Some(create_address),
+						&reason,
+						used_gas,
+						logs,
+					);
+					Self::exit_result(reason)?;
+
+					Module::<T>::deposit_event(Event::Created(create_address));
+					Ok(())
+				},
+			}
+		}
+	}
+}
+
+/// The minimal gas price is kept in storage, adjustable by root with `set_min_gas_price`.
+impl<T: Trait> FeeCalculator for Module<T> {
+	fn min_gas_price() -> U256 {
+		MinGasPrice::get()
 	}
 }
 
@@ -366,18 +576,237 @@ impl<T: Trait> Module<T> {
 		AccountStorages::remove_prefix(address);
 	}
 
+	/// Decode a raw Ethereum transaction and recover its sender.
+	///
+	/// The transaction must be signed for the chain id of this chain, and its gas limit must fit
+	/// in 32 bits and be within the block gas limit.
+	pub fn decode_transaction(raw: &[u8]) -> Result<(Transaction, H160), Error<T>> {
+		let transaction = Transaction::decode(raw).map_err(|_| Error::<T>::MalformedTransaction)?;
+		ensure!(
+			transaction.chain_id() == Some(sp_io::misc::chain_id()),
+			Error::<T>::InvalidChainId,
+		);
+		ensure!(
+			transaction.gas_limit <= U256::from(u32::max_value()).min(T::BlockGasLimit::get()),
+			Error::<T>::GasLimitTooHigh,
+		);
+		let source = transaction.sender().ok_or(Error::<T>::InvalidSignature)?;
+		Ok((transaction, source))
+	}
+
+	/// The weight of `transact` with the raw transaction `raw`: like `call` and `create`, its gas
+	/// limit.
+	fn transaction_weight(raw: &[u8]) -> Weight {
+		match Transaction::decode(raw) {
+			Ok(transaction) => transaction.gas_limit.saturated_into::<Weight>(),
+			// Never executed: the transaction does not pass validation.
+			Err(_) => 10_000,
+		}
+	}
+
+	/// The gas used by the executions applied in the current block.
+	pub fn block_gas_used() -> U256 {
+		Receipts::get().last().map(|receipt| receipt.cumulative_gas_used).unwrap_or_default()
+	}
+
+	/// The gas left in the current block, which bounds the gas limit of the next execution.
+	pub fn block_gas_left() -> U256 {
+		T::BlockGasLimit::get().saturating_sub(Self::block_gas_used())
+	}
+
+	/// Execute a call from `source` to `target`, the way the `call` dispatchable does.
+	///
+	/// Returns why the execution exited, its output, the gas it used and the logs it emitted.
+	/// Changes to the state are only kept if `apply_state` is set; the minimal gas price and the
+	/// block gas limit are only enforced then.
+	pub fn execute_call(
+		source: H160,
+		target: H160,
+		input: Vec<u8>,
+		value: U256,
+		gas_limit: u32,
+		gas_price: U256,
+		nonce: Option<U256>,
+		apply_state: bool,
+	) -> Result<(ExitReason, Vec<u8>, U256, Vec<Log>), Error<T>> {
+		let (reason, output, used_gas, logs, _) = Self::execute_evm(
+			source,
+			value,
+			gas_limit,
+			gas_price,
+			nonce,
+			apply_state,
+			|executor| executor.transact_call(
+				source,
+				target,
+				value,
+				input,
+				gas_limit as usize,
+			),
+		)?;
+		Ok((reason, output, used_gas, logs))
+	}
+
+	/// Execute the creation of a contract by `source`, the way the `create` dispatchable does.
+	///
+	/// Returns why the execution exited, the address of the contract, the gas it used and the logs
+	/// it emitted. Changes to the state are only kept if `apply_state` is set; the minimal gas
+	/// price and the block gas limit are only enforced then.
+	pub fn execute_create(
+		source: H160,
+		init: Vec<u8>,
+		value: U256,
+		gas_limit: u32,
+		gas_price: U256,
+		nonce: Option<U256>,
+		apply_state: bool,
+	) -> Result<(ExitReason, H160, U256, Vec<Log>), Error<T>> {
+		let (reason, address, used_gas, logs, _) = Self::execute_evm(
+			source,
+			value,
+			gas_limit,
+			gas_price,
+			nonce,
+			apply_state,
+			|executor| {
+				let address = executor.create_address(evm::CreateScheme::Legacy { caller: source });
+				(executor.transact_create(
+					source,
+					value,
+					init,
+					gas_limit as usize,
+				), address)
+			},
+		)?;
+		Ok((reason, address, used_gas, logs))
+	}
+
+	/// Execute a call from `source` to `target` without applying it, and report its outcome.
+	///
+	/// With `trace` set, the steps of the outermost frame of the call are recorded too, up to
+	/// `MAX_TRACE_SIZE`. The minimal gas price and the block gas limit are not enforced.
+	pub fn trace_call(
+		source: H160,
+		target: H160,
+		input: Vec<u8>,
+		value: U256,
+		gas_limit: u32,
+		gas_price: U256,
+		trace: bool,
+	) -> Result<Execution, Error<T>> {
+		let (steps, truncated) = if trace {
+			let (steps, truncated) =
+				trace::steps::<T>(source, Some(target), input.clone(), value, gas_limit, gas_price);
+			(Some(steps), truncated)
+		} else {
+			(None, false)
+		};
+		let (reason, output, used_gas, logs, changes) = Self::execute_evm(
+			source,
+			value,
+			gas_limit,
+			gas_price,
+			None,
+			false,
+			|executor| executor.transact_call(
+				source,
+				target,
+				value,
+				input,
+				gas_limit as usize,
+			),
+		)?;
+		Ok(Execution {
+			exit_status: (&reason).into(),
+			output,
+			contract_address: None,
+			used_gas,
+			logs,
+			changes,
+			steps,
+			truncated,
+		})
+	}
+
+	/// Execute the creation of a contract by `source` without applying it, and report its
+	/// outcome.
+	///
+	/// With `trace` set, the steps of the outermost frame of the creation are recorded too, up to
+	/// `MAX_TRACE_SIZE`. The minimal gas price and the block gas limit are not enforced.
+	pub fn trace_create(
+		source: H160,
+		init: Vec<u8>,
+		value: U256,
+		gas_limit: u32,
+		gas_price: U256,
+		trace: bool,
+	) -> Result<Execution, Error<T>> {
+		let (steps, truncated) = if trace {
+			let (steps, truncated) =
+				trace::steps::<T>(source, None, init.clone(), value, gas_limit, gas_price);
+			(Some(steps), truncated)
+		} else {
+			(None, false)
+		};
+		let (reason, address, used_gas, logs, changes) = Self::execute_evm(
+			source,
+			value,
+			gas_limit,
+			gas_price,
+			None,
+			false,
+			|executor| {
+				let address = executor.create_address(evm::CreateScheme::Legacy { caller: source });
+				(executor.transact_create(
+					source,
+					value,
+					init,
+					gas_limit as usize,
+				), address)
+			},
+		)?;
+		Ok(Execution {
+			exit_status: (&reason).into(),
+			// The executor does not return the output of creations.
+			output: Vec::new(),
+			contract_address: Some(address),
+			used_gas,
+			logs,
+			changes,
+			steps,
+			truncated,
+		})
+	}
+
+	/// The error of an execution that exited for `reason`, if it did not succeed.
+	pub fn exit_result(reason: ExitReason) -> Result<(), Error<T>> {
+		match reason {
+			ExitReason::Succeed(_) => Ok(()),
+			ExitReason::Error(_) => Err(Error::<T>::ExitReasonFailed),
+			ExitReason::Revert(_) => Err(Error::<T>::ExitReasonRevert),
+			ExitReason::Fatal(_) => Err(Error::<T>::ExitReasonFatal),
+		}
+	}
+
 	/// Execute an EVM operation.
+	///
+	/// Returns why the execution exited, the result of `f`, the gas used, the logs emitted and,
+	/// unless `apply_state` is set, the changes the execution would make to the state.
 	fn execute_evm<F, R>(
 		source: H160,
 		value: U256,
 		gas_limit: u32,
 		gas_price: U256,
 		nonce: Option<U256>,
+		apply_state: bool,
 		f: F,
-	) -> Result<R, Error<T>> where
-		F: FnOnce(&mut StackExecutor<Backend<T>>) -> (R, ExitReason),
+	) -> Result<(ExitReason, R, U256, Vec<Log>, Vec<AccountChange>), Error<T>> where
+		F: FnOnce(&mut StackExecutor<Backend<T>>) -> (ExitReason, R),
 	{
-		ensure!(gas_price >= T::FeeCalculator::min_gas_price(), Error::<T>::GasPriceTooLow);
+		if apply_state {
+			ensure!(gas_price >= T::FeeCalculator::min_gas_price(), Error::<T>::GasPriceTooLow);
+			ensure!(U256::from(gas_limit) <= Self::block_gas_left(), Error::<T>::GasLimitTooHigh);
+		}
 
 		let vicinity = Vicinity {
 			gas_price,
@@ -403,21 +832,141 @@ impl<T: Trait> Module<T> {
 			ensure!(source_account.nonce == nonce, Error::<T>::InvalidNonce);
 		}
 
-		let (retv, reason) = f(&mut executor);
-
-		let ret = match reason {
-			ExitReason::Succeed(_) => Ok(retv),
-			ExitReason::Error(_) => Err(Error::<T>::ExitReasonFailed),
-			ExitReason::Revert(_) => Err(Error::<T>::ExitReasonRevert),
-			ExitReason::Fatal(_) => Err(Error::<T>::ExitReasonFatal),
+		let withdrawn = if apply_state {
+			Some(T::OnChargeTransaction::withdraw_fee(&source, total_fee)?)
+		} else {
+			None
 		};
 
+		let (reason, retv) = f(&mut executor);
+		let used_gas = U256::from(executor.used_gas());
+
 		let actual_fee = executor.fee(gas_price);
 		executor.deposit(source, total_fee.saturating_sub(actual_fee));
 
 		let (values, logs) = executor.deconstruct();
-		backend.apply(values, logs, true);
+		let logs = logs.into_iter().collect::<Vec<_>>();
+		let emitted = logs.iter()
+			.map(|log| Log {
+				address: log.address,
+				topics: log.topics.clone(),
+				data: log.data.clone(),
+			})
+			.collect();
+
+		let changes = if apply_state {
+			backend.apply(values, logs, true);
+			if let Some(withdrawn) = withdrawn {
+				T::OnChargeTransaction::correct_and_deposit_fee(&source, actual_fee, withdrawn);
+			}
+			Vec::new()
+		} else {
+			trace::account_changes(values)
+		};
+
+		Ok((reason, retv, used_gas, emitted, changes))
+	}
 
-		ret
+	/// Record the receipt of an execution applied in the current block.
+	///
+	/// The receipt is found under `transaction_hash`, or under the hash of the current extrinsic
+	/// if there is none.
+	fn note_receipt(
+		transaction_hash: Option<H256>,
+		from: H160,
+		to: Option<H160>,
+		contract_address: Option<H160>,
+		reason: &ExitReason,
+		used_gas: U256,
+		logs: Vec<Log>,
+	) {
+		let transaction_hash = transaction_hash.unwrap_or_else(|| {
+			let index = frame_system::Module::<T>::extrinsic_index().unwrap_or_default();
+			let extrinsic = frame_system::Module::<T>::extrinsic_data(index);
+			H256::from_slice(T::Hashing::hash(&extrinsic).as_ref())
+		});
+		let logs_bloom = logs_bloom(&logs);
+		LogsBloom::mutate(|bloom| bloom.accrue_bloom(&logs_bloom));
+
+		let transaction_index = Receipts::mutate(|receipts| {
+			let cumulative_gas_used = receipts.last()
+				.map(|receipt| receipt.cumulative_gas_used)
+				.unwrap_or_default()
+				.saturating_add(used_gas);
+			let transaction_index = receipts.len() as u32;
+			receipts.push(Receipt {
+				transaction_hash,
+				transaction_index,
+				from,
+				to,
+				contract_address,
+				status: match reason {
+					ExitReason::Succeed(_) => true,
+					_ => false,
+				},
+				used_gas,
+				cumulative_gas_used,
+				logs,
+				logs_bloom,
+			});
+			transaction_index
+		});
+		let number = frame_system::Module::<T>::block_number();
+		TransactionLocations::<T>::insert(transaction_hash, (number, transaction_index));
+		BlockTransactions::<T>::mutate(number, |hashes| hashes.push(transaction_hash));
+	}
+}
+
+impl<T: Trait> frame_support::unsigned::ValidateUnsigned for Module<T> {
+	type Call = Call<T>;
+
+	fn validate_unsigned(
+		_source: TransactionSource,
+		call: &Self::Call,
+	) -> TransactionValidity {
+		if let Call::transact(transaction) = call {
+			let (transaction, source) = match Self::decode_transaction(transaction) {
+				Ok(decoded) => decoded,
+				Err(Error::<T>::GasLimitTooHigh) => return InvalidTransaction::ExhaustsResources.into(),
+				Err(_) => return InvalidTransaction::BadProof.into(),
+			};
+
+			if transaction.gas_limit > Self::block_gas_left() {
+				return InvalidTransaction::ExhaustsResources.into();
+			}
+
+			if transaction.gas_price < T::FeeCalculator::min_gas_price() {
+				return InvalidTransaction::Payment.into();
+			}
+
+			let account = Accounts::get(&source);
+			if transaction.nonce < account.nonce {
+				return InvalidTransaction::Stale.into();
+			}
+
+			let total_payment = transaction.gas_price.checked_mul(transaction.gas_limit)
+				.and_then(|fee| fee.checked_add(transaction.value));
+			match total_payment {
+				Some(total_payment) if total_payment <= account.balance => (),
+				_ => return InvalidTransaction::Payment.into(),
+			}
+
+			// Transactions of a sender are ordered by nonce, the first one being ready.
+			let requires = if transaction.nonce > account.nonce {
+				vec![(source, transaction.nonce - U256::one()).encode()]
+			} else {
+				vec![]
+			};
+
+			Ok(ValidTransaction {
+				priority: transaction.gas_price.saturated_into::<TransactionPriority>(),
+				requires,
+				provides: vec![(source, transaction.nonce).encode()],
+				longevity: TransactionLongevity::max_value(),
+				propagate: true,
+			})
+		} else {
+			InvalidTransaction::Call.into()
+		}
 	}
 }
@@ -0,0 +1,63 @@
+// Copyright 2020 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Receipts of the executions applied in a block, and the bloom filters of their logs.
+
+use sp_std::vec::Vec;
+#[cfg(feature = "std")]
+use serde::{Serialize, Deserialize};
+use codec::{Encode, Decode};
+use sp_core::{U256, H256, H160};
+use ethbloom::{Bloom, Input};
+use crate::Log;
+
+#[derive(Clone, Eq, PartialEq, Encode, Decode)]
+#[cfg_attr(feature = "std", derive(Debug, Serialize, Deserialize))]
+/// The receipt of an execution. Used by storage.
+pub struct Receipt {
+	/// The hash of the Ethereum transaction for `transact`, of the extrinsic otherwise.
+	pub transaction_hash: H256,
+	/// The index of the receipt in its block.
+	pub transaction_index: u32,
+	/// The account the execution is from.
+	pub from: H160,
+	/// The account called, `None` for the creation of a contract.
+	pub to: Option<H160>,
+	/// The address of the created contract, `None` for a call.
+	pub contract_address: Option<H160>,
+	/// Whether the execution succeeded.
+	pub status: bool,
+	/// The gas used by the execution.
+	pub used_gas: U256,
+	/// The gas used by the executions of the block up to and including this one.
+	pub cumulative_gas_used: U256,
+	/// The logs emitted by the execution.
+	pub logs: Vec<Log>,
+	/// The bloom filter of the logs.
+	pub logs_bloom: Bloom,
+}
+
+/// The bloom filter of `logs`, holding the address and the topics of each log.
+pub fn logs_bloom(logs: &[Log]) -> Bloom {
+	let mut bloom = Bloom::default();
+	for log in logs {
+		bloom.accrue(Input::Raw(log.address.as_bytes()));
+		for topic in &log.topics {
+			bloom.accrue(Input::Raw(topic.as_bytes()));
+		}
+	}
+	bloom
+}
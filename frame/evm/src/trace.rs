@@ -0,0 +1,374 @@
+// Copyright 2020 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! The outcome of dry runs of executions and, optionally, their steps.
+
+use sp_std::{fmt::{self, Write}, rc::Rc, vec::Vec};
+#[cfg(feature = "std")]
+use serde::{Serialize, Deserialize};
+use codec::{Encode, Decode};
+use sp_core::{U256, H256, H160};
+use evm::{Capture, Context, CreateScheme, ExitReason, Handler, Machine, Runtime, Stack};
+use evm::backend::{Apply, Backend as BackendT};
+use evm::executor::StackExecutor;
+use evm::gasometer::{self, Gasometer};
+use crate::{Trait, Log, Backend, Vicinity, Precompiles};
+
+/// The selector of `Error(string)`, the ABI encoding of a revert reason in Solidity.
+const ERROR_SELECTOR: [u8; 4] = [0x08, 0xc3, 0x79, 0xa0];
+
+/// The most bytes of stacks and memories the steps of a trace hold. The steps past it are left
+/// out.
+pub const MAX_TRACE_SIZE: usize = 4 * 1024 * 1024;
+
+#[derive(Clone, Eq, PartialEq, Encode, Decode)]
+#[cfg_attr(feature = "std", derive(Debug, Serialize, Deserialize))]
+/// How an execution exited.
+pub enum ExitStatus {
+	/// The execution succeeded.
+	Succeed,
+	/// The execution reverted, its output being the revert data.
+	Revert,
+	/// The execution failed, with the given description of the error.
+	Error(Vec<u8>),
+	/// The EVM failed, with the given description of the error.
+	Fatal(Vec<u8>),
+}
+
+impl From<&ExitReason> for ExitStatus {
+	fn from(reason: &ExitReason) -> Self {
+		match reason {
+			ExitReason::Succeed(_) => ExitStatus::Succeed,
+			ExitReason::Revert(_) => ExitStatus::Revert,
+			ExitReason::Error(error) => ExitStatus::Error(describe(error)),
+			ExitReason::Fatal(fatal) => ExitStatus::Fatal(describe(fatal)),
+		}
+	}
+}
+
+#[derive(Clone, Eq, PartialEq, Encode, Decode)]
+#[cfg_attr(feature = "std", derive(Debug, Serialize, Deserialize))]
+/// A change an execution makes to an account.
+pub enum AccountChange {
+	/// The account is created or modified.
+	Modify {
+		/// The address of the account.
+		address: H160,
+		/// The new nonce.
+		nonce: U256,
+		/// The new balance.
+		balance: U256,
+		/// The new code, if it changes.
+		code: Option<Vec<u8>>,
+		/// Whether all of the storage is cleared before `storage` is written.
+		reset_storage: bool,
+		/// The values written to the storage, by index.
+		storage: Vec<(H256, H256)>,
+	},
+	/// The account is deleted.
+	Delete {
+		/// The address of the account.
+		address: H160,
+	},
+}
+
+#[derive(Clone, Eq, PartialEq, Encode, Decode)]
+#[cfg_attr(feature = "std", derive(Debug, Serialize, Deserialize))]
+/// A step of an execution: the state of the machine before an opcode.
+pub struct Step {
+	/// The position of the opcode in the code.
+	pub pc: u32,
+	/// The opcode.
+	pub opcode: u8,
+	/// The gas left.
+	pub gas: U256,
+	/// The gas the opcode costs.
+	pub gas_cost: U256,
+	/// The stack, its top last.
+	pub stack: Vec<H256>,
+	/// The memory.
+	pub memory: Vec<u8>,
+}
+
+#[derive(Clone, Eq, PartialEq, Encode, Decode)]
+#[cfg_attr(feature = "std", derive(Debug, Serialize, Deserialize))]
+/// The outcome of an execution that was not applied.
+pub struct Execution {
+	/// How the execution exited.
+	pub exit_status: ExitStatus,
+	/// The returned data, or the revert data, of a call; empty for a creation.
+	pub output: Vec<u8>,
+	/// The address of the contract, for a creation.
+	pub contract_address: Option<H160>,
+	/// The gas used by the execution.
+	pub used_gas: U256,
+	/// The logs emitted by the execution.
+	pub logs: Vec<Log>,
+	/// The changes the execution would make to the state.
+	pub changes: Vec<AccountChange>,
+	/// The steps of the outermost frame of the execution, if it was traced.
+	pub steps: Option<Vec<Step>>,
+	/// Whether steps were left out of `steps`, the trace being larger than `MAX_TRACE_SIZE`.
+	pub truncated: bool,
+}
+
+impl Execution {
+	/// The reason of the revert, if the execution reverted with a Solidity `Error(string)`.
+	pub fn revert_reason(&self) -> Option<&[u8]> {
+		if self.exit_status != ExitStatus::Revert || !self.output.starts_with(&ERROR_SELECTOR) {
+			return None;
+		}
+		let encoded = &self.output[ERROR_SELECTOR.len()..];
+		let offset = read_usize(encoded, 0)?;
+		let len = read_usize(encoded, offset)?;
+		let start = offset.checked_add(32)?;
+		encoded.get(start..start.checked_add(len)?)
+	}
+}
+
+/// The ABI-encoded word at `start` of `encoded`, if it is in `encoded` and fits a `usize`.
+fn read_usize(encoded: &[u8], start: usize) -> Option<usize> {
+	let word = U256::from_big_endian(encoded.get(start..start.checked_add(32)?)?);
+	if word > U256::from(usize::max_value()) {
+		None
+	} else {
+		Some(word.low_u64() as usize)
+	}
+}
+
+/// A description of `error`, from its debug formatting.
+fn describe(error: &impl fmt::Debug) -> Vec<u8> {
+	struct Description(Vec<u8>);
+
+	impl Write for Description {
+		fn write_str(&mut self, s: &str) -> fmt::Result {
+			self.0.extend_from_slice(s.as_bytes());
+			Ok(())
+		}
+	}
+
+	let mut description = Description(Vec::new());
+	let _ = write!(description, "{:?}", error);
+	description.0
+}
+
+/// The changes `values` make to the state.
+pub(crate) fn account_changes<A, I>(values: A) -> Vec<AccountChange> where
+	A: IntoIterator<Item = Apply<I>>,
+	I: IntoIterator<Item = (H256, H256)>,
+{
+	values.into_iter()
+		.map(|apply| match apply {
+			Apply::Modify { address, basic, code, storage, reset_storage } =>
+				AccountChange::Modify {
+					address,
+					nonce: basic.nonce,
+					balance: basic.balance,
+					code,
+					reset_storage,
+					storage: storage.into_iter().collect(),
+				},
+			Apply::Delete { address } => AccountChange::Delete { address },
+		})
+		.collect()
+}
+
+/// The steps of a frame, as they are recorded.
+#[derive(Default)]
+struct Trace {
+	steps: Vec<Step>,
+	/// The bytes of stacks and memories held by `steps`.
+	size: usize,
+	truncated: bool,
+}
+
+impl Trace {
+	/// Record the step before the next opcode of `machine`, at position `pc` with `gas` left,
+	/// unless it does not fit the trace anymore.
+	fn record(&mut self, machine: &Machine, opcode: u8, pc: usize, gas: usize) -> bool {
+		let stack = machine.stack();
+		let memory = machine.memory();
+		let size = self.size
+			.saturating_add(stack.len().saturating_mul(32))
+			.saturating_add(memory.len());
+		if self.truncated || size > MAX_TRACE_SIZE {
+			self.truncated = true;
+			return false;
+		}
+		self.size = size;
+		self.steps.push(Step {
+			pc: pc as u32,
+			opcode,
+			gas: U256::from(gas),
+			gas_cost: U256::zero(),
+			// `peek` counts from the top.
+			stack: (0..stack.len()).rev()
+				.map(|index| stack.peek(index).unwrap_or_default())
+				.collect(),
+			memory: memory.get(0, memory.len()),
+		});
+		true
+	}
+}
+
+/// The position of the opcode after `opcode`, at `pc`, when it does not exit, given the stack
+/// before it.
+fn next_pc(opcode: u8, pc: usize, stack: &Stack) -> usize {
+	let word = |index| stack.peek(index).map(|word| U256::from_big_endian(&word[..]));
+	match opcode {
+		// `PUSH1` to `PUSH32`, followed by the bytes they push.
+		0x60..=0x7f => pc + 2 + (opcode - 0x60) as usize,
+		// `JUMP`, to a valid destination since it did not exit.
+		0x56 => word(0).map_or(pc + 1, |destination| destination.low_u64() as usize),
+		// `JUMPI`, jumping unless its condition is zero.
+		0x57 => match (word(0), word(1)) {
+			(Ok(destination), Ok(condition)) if !condition.is_zero() =>
+				destination.low_u64() as usize,
+			_ => pc + 1,
+		},
+		_ => pc + 1,
+	}
+}
+
+/// Step `runtime` on `executor` to its exit, recording the steps in `trace`.
+///
+/// The opcodes of nested calls and creations are run by the executor, each of these being a
+/// single step.
+fn run_frame<B: BackendT>(
+	executor: &mut StackExecutor<B>,
+	runtime: &mut Runtime,
+	code: &[u8],
+	trace: &mut Trace,
+) {
+	let mut pc = 0;
+	loop {
+		// Running past the end of the code stops, as does an exit.
+		let opcode = match (runtime.machine().inspect(), code.get(pc)) {
+			(Some(_), Some(opcode)) => *opcode,
+			_ => return,
+		};
+		let next = next_pc(opcode, pc, runtime.machine().stack());
+		let gas = executor.gas();
+		let recorded = trace.record(runtime.machine(), opcode, pc, gas);
+		let exited = match runtime.step(executor) {
+			Ok(()) => false,
+			Err(Capture::Exit(_)) => true,
+			Err(Capture::Trap(_)) => unreachable!("the executor runs nested frames itself"),
+		};
+		if recorded {
+			if let Some(step) = trace.steps.last_mut() {
+				step.gas_cost = U256::from(gas.saturating_sub(executor.gas()));
+			}
+		}
+		if exited {
+			return;
+		}
+		pc = next;
+	}
+}
+
+/// Record the steps of the outermost frame of a call from `source` to `target`, or of the
+/// creation of a contract by `source` without a `target`, with the state and the gas it runs
+/// with, up to `MAX_TRACE_SIZE`.
+///
+/// The executor only runs whole transactions, without reporting their steps, so the outermost
+/// frame is run again here on a substate, set up with the public methods of the executor the
+/// way `transact_call` and `transact_create` set it up. Its nested frames are run by the
+/// executor itself. The outcome of the execution is not taken from this run, but from the
+/// executor running the whole transaction.
+///
+/// Returns the steps, and whether steps were left out.
+pub(crate) fn steps<T: Trait>(
+	source: H160,
+	target: Option<H160>,
+	input: Vec<u8>,
+	value: U256,
+	gas_limit: u32,
+	gas_price: U256,
+) -> (Vec<Step>, bool) {
+	let config = T::config();
+	let vicinity = Vicinity {
+		gas_price,
+		origin: source,
+	};
+	let backend = Backend::<T>::new(&vicinity);
+	let mut executor = StackExecutor::new_with_precompile(
+		&backend,
+		gas_limit as usize,
+		config,
+		T::Precompiles::execute,
+	);
+	let mut trace = Trace::default();
+
+	// The most the execution may cost is withdrawn before it runs, as `execute_evm` does.
+	let total_fee = gas_price.saturating_mul(U256::from(gas_limit));
+	if executor.withdraw(source, total_fee).is_err() {
+		return (trace.steps, trace.truncated);
+	}
+
+	let mut gasometer = Gasometer::new(gas_limit as usize, config);
+	let transaction_cost = match target {
+		Some(_) => gasometer::call_transaction_cost(&input),
+		None => gasometer::create_transaction_cost(&input),
+	};
+	if gasometer.record_transaction(transaction_cost).is_err() {
+		return (trace.steps, trace.truncated);
+	}
+	let gas = gasometer.gas();
+
+	let address = target.unwrap_or_else(|| {
+		executor.create_address(CreateScheme::Legacy { caller: source })
+	});
+	executor.account_mut(source).basic.nonce += U256::one();
+
+	let mut frame = executor.substate(gas, false);
+	// The init code of a creation is its input.
+	let (code, input) = match target {
+		Some(target) => (frame.code(target), input),
+		None => {
+			if frame.code_size(address) != U256::zero() ||
+				frame.nonce(address) > U256::zero() ||
+				frame.balance(source) < value
+			{
+				return (trace.steps, trace.truncated);
+			}
+			let account = frame.account_mut(address);
+			account.reset_storage = true;
+			account.storage = Default::default();
+			(input, Vec::new())
+		},
+	};
+	if frame.withdraw(source, value).is_err() {
+		return (trace.steps, trace.truncated);
+	}
+	frame.deposit(address, value);
+	if target.map_or(false, |target| T::Precompiles::execute(target, &input, Some(gas)).is_some()) {
+		return (trace.steps, trace.truncated);
+	}
+	if target.is_none() && config.create_increase_nonce {
+		frame.account_mut(address).basic.nonce += U256::one();
+	}
+
+	let context = Context {
+		address,
+		caller: source,
+		apparent_value: value,
+	};
+	let code = Rc::new(code);
+	let mut runtime = Runtime::new(code.clone(), Rc::new(input), context, config);
+	run_frame(&mut frame, &mut runtime, &code, &mut trace);
+	(trace.steps, trace.truncated)
+}
@@ -0,0 +1,176 @@
+// Copyright 2020 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Raw signed Ethereum transactions, as submitted with `transact`.
+//!
+//! A transaction is the RLP list `[nonce, gas_price, gas_limit, to, value, input, v, r, s]`, where
+//! `to` is empty for the creation of a contract. Following EIP-155, the signature covers the RLP
+//! list `[nonce, gas_price, gas_limit, to, value, input, chain_id, 0, 0]` and
+//! `v = chain_id * 2 + 35 + recovery_id`.
+
+use sp_std::vec::Vec;
+use sp_core::{H160, H256, U256};
+use sp_runtime::RuntimeDebug;
+use rlp::{Rlp, RlpStream, DecoderError};
+use sha3::{Digest, Keccak256};
+
+/// Half the order of the secp256k1 curve. Signatures with a higher `s` are rejected, as every
+/// signature has such a twin (EIP-2).
+const SECP256K1N_HALF: U256 = U256([
+	0xdfe92f46681b20a0,
+	0x5d576e7357a4501d,
+	0xffffffffffffffff,
+	0x7fffffffffffffff,
+]);
+
+/// What a transaction does.
+#[derive(Clone, Copy, PartialEq, Eq, RuntimeDebug)]
+pub enum TransactionAction {
+	/// Call the account at the given address.
+	Call(H160),
+	/// Create a contract, whose code is returned by running the input.
+	Create,
+}
+
+/// A signed Ethereum transaction.
+#[derive(Clone, PartialEq, Eq, RuntimeDebug)]
+pub struct Transaction {
+	/// The nonce of the sender.
+	pub nonce: U256,
+	/// The price the sender pays for each unit of gas.
+	pub gas_price: U256,
+	/// The gas the transaction may use.
+	pub gas_limit: U256,
+	/// What the transaction does.
+	pub action: TransactionAction,
+	/// The value transferred to the callee or the created contract.
+	pub value: U256,
+	/// The input of the call, or the init code of the contract.
+	pub input: Vec<u8>,
+	/// The recovery id of the signature, with the chain id folded in.
+	pub v: u64,
+	/// The `r` of the signature.
+	pub r: U256,
+	/// The `s` of the signature.
+	pub s: U256,
+}
+
+impl Transaction {
+	/// Decode a transaction from its raw RLP encoding, which must be nothing but the transaction.
+	pub fn decode(raw: &[u8]) -> Result<Self, DecoderError> {
+		let rlp = Rlp::new(raw);
+		let info = rlp.payload_info()?;
+		if info.header_len + info.value_len != raw.len() {
+			return Err(DecoderError::RlpInconsistentLengthAndData);
+		}
+		if rlp.item_count()? != 9 {
+			return Err(DecoderError::RlpIncorrectListLen);
+		}
+		let to = rlp.at(3)?;
+		let action = if to.is_empty() {
+			TransactionAction::Create
+		} else {
+			TransactionAction::Call(to.as_val()?)
+		};
+		Ok(Transaction {
+			nonce: rlp.val_at(0)?,
+			gas_price: rlp.val_at(1)?,
+			gas_limit: rlp.val_at(2)?,
+			action,
+			value: rlp.val_at(4)?,
+			input: rlp.val_at(5)?,
+			v: rlp.val_at(6)?,
+			r: rlp.val_at(7)?,
+			s: rlp.val_at(8)?,
+		})
+	}
+
+	/// The raw RLP encoding of the transaction.
+	pub fn encode(&self) -> Vec<u8> {
+		let mut stream = RlpStream::new_list(9);
+		self.append_unsigned(&mut stream);
+		stream.append(&self.v);
+		stream.append(&self.r);
+		stream.append(&self.s);
+		stream.out()
+	}
+
+	/// The hash identifying the transaction.
+	pub fn hash(&self) -> H256 {
+		H256::from_slice(Keccak256::digest(&self.encode()).as_slice())
+	}
+
+	/// The chain id the transaction is signed for, `None` if the signature predates EIP-155.
+	pub fn chain_id(&self) -> Option<u64> {
+		if self.v >= 35 {
+			Some((self.v - 35) / 2)
+		} else {
+			None
+		}
+	}
+
+	/// The message signed by the sender.
+	pub fn message(&self) -> H256 {
+		let stream = match self.chain_id() {
+			Some(chain_id) => {
+				let mut stream = RlpStream::new_list(9);
+				self.append_unsigned(&mut stream);
+				stream.append(&chain_id);
+				stream.append(&0u8);
+				stream.append(&0u8);
+				stream
+			},
+			None => {
+				let mut stream = RlpStream::new_list(6);
+				self.append_unsigned(&mut stream);
+				stream
+			},
+		};
+		H256::from_slice(Keccak256::digest(&stream.out()).as_slice())
+	}
+
+	/// The address of the sender, recovered from the signature. `None` if the signature is invalid.
+	pub fn sender(&self) -> Option<H160> {
+		let recovery_id = match self.v {
+			27 | 28 => self.v - 27,
+			v if v >= 35 => (v - 35) % 2,
+			_ => return None,
+		};
+		if self.r.is_zero() || self.s.is_zero() || self.s > SECP256K1N_HALF {
+			return None;
+		}
+		let mut signature = [0u8; 65];
+		self.r.to_big_endian(&mut signature[0..32]);
+		self.s.to_big_endian(&mut signature[32..64]);
+		signature[64] = recovery_id as u8;
+		let public = sp_io::crypto::secp256k1_ecdsa_recover(&signature, self.message().as_fixed_bytes())
+			.ok()?;
+		Some(H160::from_slice(&Keccak256::digest(&public).as_slice()[12..]))
+	}
+
+	/// Append the fields not part of the signature to `stream`.
+	fn append_unsigned(&self, stream: &mut RlpStream) {
+		stream.append(&self.nonce);
+		stream.append(&self.gas_price);
+		stream.append(&self.gas_limit);
+		match self.action {
+			TransactionAction::Call(to) => stream.append(&to),
+			TransactionAction::Create => stream.append_empty_data(),
+		};
+		stream.append(&self.value);
+		stream.append(&self.input);
+	}
+}
@@ -0,0 +1,50 @@
+//! Runtime API definition required by Search RPC extensions.
+//!
+//! This API should be imported and implemented by the runtime,
+//! of a node that wants to use the custom RPC extension
+//! adding search service query methods.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use codec::Codec;
+use sp_std::vec::Vec;
+
+pub use pallet_search::{MerkleProof, SearchServiceInfo, SearchServiceHash, Tag, TagMatch};
+
+sp_api::decl_runtime_apis! {
+	/// The API to query registered search services without submitting extrinsics.
+	pub trait SearchApi<AccountId> where
+		AccountId: Codec,
+	{
+		/// Recommend the hottest search services, optionally only those carrying `tag`.
+		///
+		/// At most `count` are returned, hottest first.
+		fn recommend(tag: Option<Tag>, count: u32) -> Vec<SearchServiceInfo<AccountId>>;
+
+		/// Find the search services carrying all (or any) of `tags`, ordered by name.
+		///
+		/// The first `start` matches are skipped and at most `count` are returned.
+		fn services_by_tags(
+			tags: Vec<Tag>,
+			matching: TagMatch,
+			start: u32,
+			count: u32,
+		) -> Vec<SearchServiceInfo<AccountId>>;
+
+		/// Find the search service registered under `name`.
+		fn service_by_name(name: Vec<u8>) -> Option<SearchServiceInfo<AccountId>>;
+
+		/// Find the latest root hash uploaded for the search service registered under `name`.
+		fn service_hash(name: Vec<u8>) -> Option<SearchServiceHash<AccountId>>;
+
+		/// Check that `proof` proves `record` to be part of the root hash of the upload `upload`
+		/// of the search service registered under `name`, or of its current root hash if `upload`
+		/// is `None`.
+		fn verify_searched_item(
+			name: Vec<u8>,
+			upload: Option<u32>,
+			record: Vec<u8>,
+			proof: MerkleProof,
+		) -> bool;
+	}
+}
@@ -0,0 +1,316 @@
+//! Node-specific RPC methods for querying search services and feeding the offchain worker of
+//! search providers.
+
+use std::sync::Arc;
+
+use codec::{Codec, Decode, Encode};
+use jsonrpc_core::{Error, ErrorCode, Result};
+use jsonrpc_derive::rpc;
+use pallet_search::{Msg, Sig, offchain::{self, PendingUpload}};
+use parking_lot::RwLock;
+use sp_api::ProvideRuntimeApi;
+use sp_blockchain::HeaderBackend;
+use sp_core::{Bytes, offchain::OffchainStorage};
+use sp_runtime::{generic::BlockId, traits::Block as BlockT};
+
+pub use self::gen_client::Client as SearchClient;
+pub use self::search_offchain::gen_client::Client as SearchOffchainClient;
+pub use self::search_offchain::SearchOffchainApi;
+pub use pallet_search_rpc_runtime_api::{
+	self as runtime_api, MerkleProof, SearchApi as SearchRuntimeApi, SearchServiceHash,
+	SearchServiceInfo, TagMatch,
+};
+
+const RUNTIME_ERROR: i64 = 1;
+const QUEUE_FULL: i64 = 2;
+
+/// The maximum number of search services uploads can be queued for at once.
+pub const MAX_QUEUED_SERVICES: usize = 100;
+/// The maximum number of signatures queued for a search service. They all commit to its current
+/// root hash, so only its next upload can count them, and that counts at most `MAX_SIGNS`.
+pub const MAX_QUEUED_SIGNS: usize = pallet_search::MAX_SIGNS;
+/// The maximum total size, in bytes, of the records queued for a search service.
+pub const MAX_QUEUED_RECORD_BYTES: usize = 16 * 1024 * 1024;
+
+/// Search RPC methods.
+///
+/// All of them are answered from the state at `at`, or at the best block if `at` is omitted.
+#[rpc]
+pub trait SearchApi<BlockHash, AccountId> {
+	/// Recommend the hottest search services, hottest first, optionally only those carrying
+	/// `tag`.
+	///
+	/// At most `count` are returned; the runtime caps `count` at its maximum page size.
+	#[rpc(name = "search_recommend")]
+	fn recommend(
+		&self,
+		tag: Option<Bytes>,
+		count: u32,
+		at: Option<BlockHash>,
+	) -> Result<Vec<SearchServiceInfo<AccountId>>>;
+
+	/// Returns the search services carrying all (or any, if `matching` is `"any"`) of `tags`,
+	/// ordered by name.
+	///
+	/// The first `start` matches are skipped and at most `count` are returned; the runtime caps
+	/// `count` at its maximum page size.
+	#[rpc(name = "search_getByTags")]
+	fn services_by_tags(
+		&self,
+		tags: Vec<Bytes>,
+		matching: TagMatch,
+		start: u32,
+		count: u32,
+		at: Option<BlockHash>,
+	) -> Result<Vec<SearchServiceInfo<AccountId>>>;
+
+	/// Returns the search service registered under `name`, or `None` if there is none.
+	#[rpc(name = "search_getByName")]
+	fn service_by_name(
+		&self,
+		name: Bytes,
+		at: Option<BlockHash>,
+	) -> Result<Option<SearchServiceInfo<AccountId>>>;
+
+	/// Returns the latest root hash uploaded for the search service registered under `name`, or
+	/// `None` if there is no such service.
+	#[rpc(name = "search_getHash")]
+	fn service_hash(
+		&self,
+		name: Bytes,
+		at: Option<BlockHash>,
+	) -> Result<Option<SearchServiceHash<AccountId>>>;
+
+	/// Returns whether `proof` proves `record` to be part of the root hash of the upload `upload`
+	/// of the search service registered under `name`, or of its current root hash if `upload` is
+	/// omitted.
+	///
+	/// Only the root hashes of the latest uploads are kept; for older ones, and for unknown
+	/// services, this is `false`.
+	#[rpc(name = "search_verifyItem")]
+	fn verify_searched_item(
+		&self,
+		name: Bytes,
+		record: Bytes,
+		proof: MerkleProof,
+		upload: Option<u32>,
+		at: Option<BlockHash>,
+	) -> Result<bool>;
+}
+
+/// An implementation of search specific RPC methods.
+pub struct Search<C, B> {
+	client: Arc<C>,
+	_marker: std::marker::PhantomData<B>,
+}
+
+impl<C, B> Search<C, B> {
+	/// Create new `Search` with the given reference to the client.
+	pub fn new(client: Arc<C>) -> Self {
+		Search {
+			client,
+			_marker: Default::default(),
+		}
+	}
+}
+
+impl<C, Block> Search<C, Block> where
+	Block: BlockT,
+	C: HeaderBackend<Block>,
+{
+	fn block_id(&self, at: Option<<Block as BlockT>::Hash>) -> BlockId<Block> {
+		BlockId::hash(at.unwrap_or_else(||
+			// If the block hash is not supplied assume the best block.
+			self.client.info().best_hash))
+	}
+}
+
+impl<C, Block, AccountId> SearchApi<<Block as BlockT>::Hash, AccountId> for Search<C, Block>
+where
+	Block: BlockT,
+	C: Send + Sync + 'static + ProvideRuntimeApi<Block> + HeaderBackend<Block>,
+	C::Api: SearchRuntimeApi<Block, AccountId>,
+	AccountId: Codec,
+{
+	fn recommend(
+		&self,
+		tag: Option<Bytes>,
+		count: u32,
+		at: Option<<Block as BlockT>::Hash>,
+	) -> Result<Vec<SearchServiceInfo<AccountId>>> {
+		let api = self.client.runtime_api();
+		api.recommend(&self.block_id(at), tag.map(|tag| tag.to_vec()), count)
+			.map_err(runtime_error_into_rpc_err)
+	}
+
+	fn services_by_tags(
+		&self,
+		tags: Vec<Bytes>,
+		matching: TagMatch,
+		start: u32,
+		count: u32,
+		at: Option<<Block as BlockT>::Hash>,
+	) -> Result<Vec<SearchServiceInfo<AccountId>>> {
+		let api = self.client.runtime_api();
+		let tags = tags.into_iter().map(|tag| tag.to_vec()).collect();
+		api.services_by_tags(&self.block_id(at), tags, matching, start, count)
+			.map_err(runtime_error_into_rpc_err)
+	}
+
+	fn service_by_name(
+		&self,
+		name: Bytes,
+		at: Option<<Block as BlockT>::Hash>,
+	) -> Result<Option<SearchServiceInfo<AccountId>>> {
+		let api = self.client.runtime_api();
+		api.service_by_name(&self.block_id(at), name.to_vec()).map_err(runtime_error_into_rpc_err)
+	}
+
+	fn service_hash(
+		&self,
+		name: Bytes,
+		at: Option<<Block as BlockT>::Hash>,
+	) -> Result<Option<SearchServiceHash<AccountId>>> {
+		let api = self.client.runtime_api();
+		api.service_hash(&self.block_id(at), name.to_vec()).map_err(runtime_error_into_rpc_err)
+	}
+
+	fn verify_searched_item(
+		&self,
+		name: Bytes,
+		record: Bytes,
+		proof: MerkleProof,
+		upload: Option<u32>,
+		at: Option<<Block as BlockT>::Hash>,
+	) -> Result<bool> {
+		let api = self.client.runtime_api();
+		api.verify_searched_item(&self.block_id(at), name.to_vec(), upload, record.to_vec(), proof)
+			.map_err(runtime_error_into_rpc_err)
+	}
+}
+
+/// Converts a runtime trap into an RPC error.
+fn runtime_error_into_rpc_err(err: impl std::fmt::Debug) -> Error {
+	Error {
+		code: ErrorCode::ServerError(RUNTIME_ERROR),
+		message: "Runtime trapped".into(),
+		data: Some(format!("{:?}", err).into()),
+	}
+}
+
+mod search_offchain {
+	use super::*;
+
+	/// Search RPC methods feeding the offchain worker of the node.
+	///
+	/// Anyone able to call these can queue uploads for the search services provided by the node,
+	/// so they must never be exposed publicly.
+	#[rpc]
+	pub trait SearchOffchainApi {
+		/// Queue user signatures and served records for the next upload the offchain worker of this
+		/// node submits for the search service `name`.
+		///
+		/// Each signature is a 65-byte RSV signature paired with the 32-byte message signed.
+		/// Nothing is queued if that would queue uploads for more than `MAX_QUEUED_SERVICES`
+		/// search services, or more than `MAX_QUEUED_SIGNS` signatures or
+		/// `MAX_QUEUED_RECORD_BYTES` bytes of records for one.
+		#[rpc(name = "search_queueUpload")]
+		fn queue_upload(&self, name: Bytes, signs: Vec<(Bytes, Bytes)>, records: Vec<Bytes>) -> Result<()>;
+	}
+}
+
+/// An implementation of the search RPC methods feeding the offchain worker.
+pub struct SearchOffchain<S> {
+	storage: Arc<RwLock<S>>,
+}
+
+impl<S: OffchainStorage> SearchOffchain<S> {
+	/// Create new `SearchOffchain` writing to the given offchain storage.
+	pub fn new(storage: S) -> Self {
+		SearchOffchain {
+			storage: Arc::new(RwLock::new(storage)),
+		}
+	}
+
+	/// Change the value under `key` with `f`, retrying until no other writer interferes. Nothing
+	/// is changed if `f` fails.
+	fn mutate<V: Encode + Decode + Default>(
+		&self,
+		key: &[u8],
+		f: impl Fn(&mut V) -> Result<()>,
+	) -> Result<()> {
+		let mut storage = self.storage.write();
+		loop {
+			let old = storage.get(sp_offchain::STORAGE_PREFIX, key);
+			let mut value = old.as_ref()
+				.and_then(|old| V::decode(&mut &old[..]).ok())
+				.unwrap_or_default();
+			f(&mut value)?;
+			if storage.compare_and_set(sp_offchain::STORAGE_PREFIX, key, old.as_deref(), &value.encode()) {
+				return Ok(());
+			}
+		}
+	}
+}
+
+impl<S: OffchainStorage + 'static> SearchOffchainApi for SearchOffchain<S> {
+	fn queue_upload(&self, name: Bytes, signs: Vec<(Bytes, Bytes)>, records: Vec<Bytes>) -> Result<()> {
+		let signs = signs.into_iter()
+			.map(|(sig, msg)| to_signed(&sig, &msg))
+			.collect::<Result<Vec<_>>>()?;
+		let queued = PendingUpload {
+			signs,
+			records: records.into_iter().map(|record| record.to_vec()).collect(),
+		};
+
+		self.mutate(offchain::PENDING_NAMES_KEY, |names: &mut Vec<Vec<u8>>| {
+			if !names.contains(&name.0) {
+				if names.len() >= MAX_QUEUED_SERVICES {
+					return Err(queue_full("Uploads are queued for too many search services"));
+				}
+				names.push(name.to_vec());
+			}
+			Ok(())
+		})?;
+		self.mutate(&offchain::pending_key(&name), |pending: &mut PendingUpload| {
+			if pending.signs.len() + queued.signs.len() > MAX_QUEUED_SIGNS {
+				return Err(queue_full("Too many signatures are queued for the search service"));
+			}
+			if record_bytes(pending) + record_bytes(&queued) > MAX_QUEUED_RECORD_BYTES {
+				return Err(queue_full("Too many records are queued for the search service"));
+			}
+			pending.append(queued.clone());
+			Ok(())
+		})
+	}
+}
+
+/// The total size of the records of `pending`.
+fn record_bytes(pending: &PendingUpload) -> usize {
+	pending.records.iter().map(|record| record.len()).sum()
+}
+
+/// The error returned when the queue of the offchain worker can not take more.
+fn queue_full(message: &str) -> Error {
+	Error {
+		code: ErrorCode::ServerError(QUEUE_FULL),
+		message: message.into(),
+		data: None,
+	}
+}
+
+/// Turn a signature and the message signed, as raw bytes, into a `(Sig, Msg)`.
+fn to_signed(sig: &[u8], msg: &[u8]) -> Result<(Sig, Msg)> {
+	if sig.len() != 65 || msg.len() != 32 {
+		return Err(Error {
+			code: ErrorCode::InvalidParams,
+			message: "A signature must be 65 bytes long and a message 32 bytes long".into(),
+			data: None,
+		});
+	}
+	let mut raw_sig = [0u8; 65];
+	raw_sig.copy_from_slice(sig);
+	let mut raw_msg = [0u8; 32];
+	raw_msg.copy_from_slice(msg);
+	Ok((Sig(raw_sig), Msg(raw_msg)))
+}
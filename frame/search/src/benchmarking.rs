@@ -0,0 +1,184 @@
+//! Search pallet benchmarking.
+
+use super::*;
+
+use frame_system::RawOrigin;
+use frame_benchmarking::{benchmarks, account};
+use frame_support::traits::OnInitialize;
+use sp_io::hashing::blake2_256;
+use sp_runtime::traits::{Bounded, Zero};
+
+use crate::Module as Search;
+
+const SEED: u32 = 0;
+// The maximum length of a name or url we will test.
+const MAX_BYTES: u32 = 1024;
+
+// Create `n` tags of 32 bytes each.
+fn create_tags(n: u32) -> Vec<Tag> {
+	(0..n).map(|i| vec![i as u8; 32]).collect()
+}
+
+// Create an account called `name` able to pay any deposit.
+fn funded_account<T: Trait>(name: &'static str, index: u32) -> T::AccountId {
+	let who = account(name, index, SEED);
+	let _ = T::Currency::make_free_balance_be(&who, BalanceOf::<T>::max_value());
+	who
+}
+
+// Register a search service called `name` on behalf of `provider`.
+fn register<T: Trait>(provider: T::AccountId, name: Vec<u8>) -> Result<(), &'static str> {
+	Search::<T>::register_search_service(
+		RawOrigin::Signed(provider).into(),
+		name,
+		vec![0; 32],
+		create_tags(MAX_TAGS as u32),
+	)?;
+	Ok(())
+}
+
+// Create `s` signatures from distinct signers for the search service `name`, all stamped with
+// its last update time.
+fn create_signs<T: Trait>(s: u32, name: &[u8]) -> Vec<(Sig, Msg)> {
+	let ss_hash = Search::<T>::get_hash(name);
+	let msg = Msg::new(name, &ss_hash.root_hash, ss_hash.update_time);
+	(0..s).map(|i| {
+		let secret = secp256k1::SecretKey::parse(&blake2_256(&(b"signer", i).encode()))
+			.expect("a blake2 hash is a valid secret key with overwhelming probability; qed");
+		let (sig, recovery_id) = secp256k1::sign(&secp256k1::Message::parse(&msg.0), &secret);
+		let mut raw = [0u8; 65];
+		raw[0..64].copy_from_slice(&sig.serialize()[..]);
+		raw[64] = recovery_id.serialize();
+		(Sig(raw), msg.clone())
+	}).collect()
+}
+
+// Register a search service called `name` on behalf of `provider` and upload the root of
+// `records` for it, with one signature. Returns the signature.
+fn upload_records<T: Trait>(
+	provider: T::AccountId,
+	name: Vec<u8>,
+	records: &[Vec<u8>],
+) -> Result<Vec<(Sig, Msg)>, &'static str> {
+	register::<T>(provider.clone(), name.clone())?;
+	let signs = create_signs::<T>(1, &name);
+	Search::<T>::upload_searched_info(
+		RawOrigin::Signed(provider).into(),
+		name,
+		signs.clone(),
+		merkle::root(records).to_vec(),
+		None,
+	)?;
+	Ok(signs)
+}
+
+benchmarks! {
+	_ { }
+
+	register_search_service {
+		let n in 1 .. MAX_BYTES;
+		let u in 1 .. MAX_BYTES;
+		let t in 0 .. MAX_TAGS as u32;
+		let caller = funded_account::<T>("caller", 0);
+	}: _(RawOrigin::Signed(caller), vec![0; n as usize], vec![0; u as usize], create_tags(t))
+
+	update_service_info {
+		let u in 1 .. MAX_BYTES;
+		let t in 0 .. MAX_TAGS as u32;
+		let caller = funded_account::<T>("caller", 0);
+		let name = b"search".to_vec();
+		register::<T>(caller.clone(), name.clone())?;
+		let tags = (0..t).map(|i| vec![i as u8 + 1; 32]).collect::<Vec<_>>();
+	}: _(RawOrigin::Signed(caller), name, vec![0; u as usize], tags)
+
+	transfer_service_ownership {
+		let u in 0 .. 1000;
+		let caller = funded_account::<T>("caller", 0);
+		let name = b"search".to_vec();
+		register::<T>(caller.clone(), name.clone())?;
+		let recipient = funded_account::<T>("recipient", u);
+		let recipient_lookup = T::Lookup::unlookup(recipient);
+	}: _(RawOrigin::Signed(caller), name, recipient_lookup)
+
+	deregister_search_service {
+		let u in 0 .. 1000;
+		let caller = funded_account::<T>("caller", u);
+		let name = b"search".to_vec();
+		register::<T>(caller.clone(), name.clone())?;
+	}: _(RawOrigin::Signed(caller), name)
+
+	force_remove_search_service {
+		let u in 0 .. 1000;
+		let caller = funded_account::<T>("caller", u);
+		let name = b"search".to_vec();
+		register::<T>(caller, name.clone())?;
+	}: _(RawOrigin::Root, name)
+
+	upload_searched_info {
+		let s in 1 .. MAX_SIGNS as u32;
+		let caller = funded_account::<T>("caller", 0);
+		let name = b"search".to_vec();
+		register::<T>(caller.clone(), name.clone())?;
+		let signs = create_signs::<T>(s, &name);
+	}: _(RawOrigin::Signed(caller), name, signs, vec![0; 32], None)
+
+	challenge {
+		let u in 0 .. 1000;
+		let caller = funded_account::<T>("caller", 0);
+		let name = b"search".to_vec();
+		let signs = upload_records::<T>(caller, name.clone(), &[vec![0; 32]])?;
+		let (sig, msg) = signs[0].clone();
+		let challenger = funded_account::<T>("challenger", u);
+	}: _(RawOrigin::Signed(challenger), name, 0, Evidence::BadSignature(sig, msg))
+
+	answer_challenge {
+		let r in 1 .. 1000;
+		let caller = funded_account::<T>("caller", 0);
+		let name = b"search".to_vec();
+		let records = (0..r).map(|i| i.encode()).collect::<Vec<_>>();
+		upload_records::<T>(caller.clone(), name.clone(), &records)?;
+		let challenger = funded_account::<T>("challenger", 0);
+		let evidence = Evidence::MissingLeaf(records[0].clone());
+		Search::<T>::challenge(RawOrigin::Signed(challenger).into(), name, 0, evidence)?;
+		let proof = MerkleProof::generate(&records, 0).ok_or("no such record")?;
+	}: _(RawOrigin::Signed(caller), 0, proof)
+
+	resolve_challenge {
+		let u in 0 .. 1000;
+		let caller = funded_account::<T>("caller", u);
+		let name = b"search".to_vec();
+		upload_records::<T>(caller, name.clone(), &[vec![0; 32]])?;
+		let challenger = funded_account::<T>("challenger", 0);
+		let evidence = Evidence::MissingLeaf(vec![1; 32]);
+		Search::<T>::challenge(RawOrigin::Signed(challenger).into(), name, 0, evidence)?;
+	}: _(RawOrigin::Root, 0, true)
+
+	claim_reward {
+		let u in 0 .. 1000;
+		let pot_account = Search::<T>::account_id();
+		let _ = T::Currency::make_free_balance_be(&pot_account, BalanceOf::<T>::max_value());
+		let caller = funded_account::<T>("caller", u);
+		let name = b"search".to_vec();
+		register::<T>(caller.clone(), name.clone())?;
+		EraHeat::insert(0, &name, 1_000);
+		EraTotalHeat::insert(0, 1_000);
+		Search::<T>::on_initialize(T::BlockNumber::zero());
+	}: _(RawOrigin::Signed(caller), name)
+
+	on_initialize {
+		let p in 0 .. MAX_PRUNED_KEYS as u32;
+		let pot_account = Search::<T>::account_id();
+		let _ = T::Currency::make_free_balance_be(&pot_account, BalanceOf::<T>::max_value());
+		// The messages and the heat of the era before the one ending are removed, up to
+		// `MAX_PRUNED_KEYS` of them.
+		for i in 0 .. p {
+			let name = (b"search", i).encode();
+			UsedMessages::insert(0, blake2_256(&name), ());
+			EraHeat::insert(0, name, 1);
+		}
+		EraTotalHeat::insert(1, 1_000);
+		CurrentEra::put(1);
+	}: {
+		Search::<T>::on_initialize(T::BlockNumber::zero());
+	}
+}
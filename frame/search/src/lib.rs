@@ -0,0 +1,1624 @@
+//! # Search Module
+//!
+//! - [`search::Trait`](./trait.Trait.html)
+//! - [`Call`](./enum.Call.html)
+//!
+//! ## Overview
+//!
+//! The Search module keeps a registry of off-chain search services. A provider registers a
+//! service under a unique name together with its url and a handful of tags, and then periodically
+//! uploads the merkle-root hash of the records it has served. Every upload carries the user
+//! signatures collected since the previous one; their count is the "heat" of the service and the
+//! provider is rewarded in proportion to it.
+//!
+//! ### Rewards
+//!
+//! Rewards are not minted: they are paid out of a pot, the account of `T::ModuleId`, for every
+//! era of `EraDuration` blocks. Each search service is owed `RewardPerHeat` for every unit of heat
+//! uploaded during the era, but no more than `EraBudget` (nor than what the pot holds) is set aside
+//! at the end of an era. When the budget falls short, it is shared between the services in
+//! proportion to their heat. Providers claim the rewards of an era with `claim_reward` during the
+//! next era; rewards not claimed by then are forfeited, as is the heat of services removed before
+//! claiming.
+//!
+//! ### Signed heat
+//!
+//! Users sign a `Msg` that carries the time of signing and commits to the name of the service and
+//! to its root hash at that time (see `Msg::new`). On upload, the signer of every message is
+//! recovered; each signer counts once per upload and a message can never be counted twice.
+//!
+//! The heat of a service accumulates over its uploads and decays over time: every
+//! `HeatDecayPeriod` blocks, only the `HeatDecay` part of it is kept. Heat is decayed lazily,
+//! whenever it is read or added to. The `RankingSize` hottest services are kept ranked on chain
+//! and are what `recommend` returns; a service enters the ranking when it uploads.
+//!
+//! ### Verifiable records
+//!
+//! The root hash of an upload is the root of a merkle tree over the records served, in the
+//! format of the `merkle` module. Anyone holding a record and a `MerkleProof` can check it against
+//! the current root of a service or any of its `RootHistoryDepth` latest uploads with
+//! `verify_searched_item`.
+//!
+//! ### Challenges
+//!
+//! Anyone may contest an upload with `challenge`, holding `ChallengeBond` on deposit, and with one
+//! of two kinds of `Evidence`:
+//!
+//! * `MissingLeaf` - A record the provider served was left out of the root hash. The provider
+//!   refutes it by proving the record part of the root hash with `answer_challenge`. Since nothing
+//!   on chain ties the record to the provider, only the `ResolveOrigin` can uphold it.
+//! * `BadSignature` - A message counted as heat by the upload was not genuinely signed by a user of
+//!   the service. Only the `ResolveOrigin` can judge this.
+//!
+//! A challenge that is neither answered nor resolved within `ChallengePeriod` blocks is dismissed
+//! and the bond returned.
+//!
+//! The `ResolveOrigin` may settle any challenge early with `resolve_challenge`. An upheld challenge
+//! removes the search service and pays its whole deposit to the challenger; a rejected one
+//! slashes the bond. While a service is under challenge, it can not be updated, transferred,
+//! deregistered or registered anew.
+//!
+//! ### Offchain worker
+//!
+//! A provider does not have to build uploads by hand. The signatures and records it collects can
+//! be queued in the offchain local storage of its node (see the `offchain` module), and the
+//! offchain worker of the node submits them with `upload_searched_info`, signed with the key of
+//! the provider of type `KEY_TYPE`, at most once every `UploadInterval` blocks per service.
+//!
+//! ### Genesis configuration
+//!
+//! A chain may start with search services already registered, each with its deposit reserved
+//! from its provider and, optionally, the root hash of a first upload.
+//!
+//! ## Interface
+//!
+//! ### Dispatchable Functions
+//!
+//! * `register_search_service` - Register a new search service under an unused name; a deposit
+//!   depending on the size of its name, url and tags is reserved.
+//! * `update_service_info` - Replace the url and tags of a search service; the deposit is adjusted.
+//! * `transfer_service_ownership` - Hand a search service over to another provider, who takes over
+//!   the deposit.
+//! * `deregister_search_service` - Remove a search service and everything stored about it; the
+//!   deposit is returned.
+//! * `force_remove_search_service` - Forcibly remove a search service; the deposit is slashed.
+//! * `upload_searched_info` - Publish a new merkle-root hash together with the user signatures
+//!   collected since the last one, to be rewarded for them.
+//! * `claim_reward` - Claim the reward of a search service for its heat in the previous era.
+//! * `challenge` - Contest an upload, holding a bond.
+//! * `answer_challenge` - Refute a `MissingLeaf` challenge with a merkle proof.
+//! * `resolve_challenge` - Uphold or reject a challenge.
+//!
+//! ### Public Functions
+//!
+//! These are exposed to clients through the `SearchApi` runtime API and the `search_*` RPCs.
+//!
+//! * `recommend` - The hottest search services, optionally only those carrying a given tag.
+//! * `services_by_tags` - The search services carrying all or any of the given tags.
+//! * `service_by_name` - The search service registered under the given name.
+//! * `service_hash` - The latest root hash uploaded for the given search service.
+//! * `verify_searched_item` - Whether a record is proven to be part of an uploaded root hash.
+//!
+//! [`Call`]: ./enum.Call.html
+//! [`Trait`]: ./trait.Trait.html
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use sp_std::prelude::*;
+use sp_std::{cmp::Reverse, collections::btree_set::BTreeSet};
+use sp_runtime::{
+	KeyTypeId, ModuleId, Perbill, PerThing, RuntimeDebug,
+	offchain::storage::StorageValueRef,
+	traits::{SaturatedConversion, StaticLookup, EnsureOrigin, Saturating, AccountIdConversion, Zero},
+};
+use frame_support::{
+	debug, decl_module, decl_event, decl_storage, decl_error, ensure,
+	dispatch::DispatchResult,
+	traits::{
+		Currency, ReservableCurrency, OnUnbalanced, Get, UnixTime, BalanceStatus,
+		ExistenceRequirement::KeepAlive,
+	},
+	weights::{SimpleDispatchInfo, FunctionOf, DispatchClass, Weight},
+	storage::IterableStorageDoubleMap,
+};
+use frame_system::{self as system, ensure_signed, ensure_root, offchain as system_offchain};
+use codec::{Encode, Decode};
+use sp_io::{crypto::secp256k1_ecdsa_recover, hashing::blake2_256};
+
+pub use merkle::MerkleProof;
+
+/// The key type of the provider keys the offchain worker signs uploads with.
+pub const KEY_TYPE: KeyTypeId = KeyTypeId(*b"srch");
+
+/// The crypto of the provider keys the offchain worker signs uploads with.
+pub mod crypto {
+	use super::KEY_TYPE;
+	use sp_runtime::app_crypto::{app_crypto, sr25519};
+	app_crypto!(sr25519, KEY_TYPE);
+}
+
+pub mod merkle;
+pub mod offchain;
+#[cfg(test)]
+mod tests;
+#[cfg(feature = "runtime-benchmarks")]
+mod benchmarking;
+
+type BalanceOf<T> = <<T as Trait>::Currency as Currency<<T as frame_system::Trait>::AccountId>>::Balance;
+type NegativeImbalanceOf<T> = <<T as Trait>::Currency as Currency<<T as frame_system::Trait>::AccountId>>::NegativeImbalance;
+
+/// The maximum number of tags a search service may carry.
+pub const MAX_TAGS: usize = 10;
+
+/// The maximum number of search services returned by a single tag query.
+pub const MAX_PAGE_SIZE: u32 = 100;
+
+/// The maximum number of signatures a single upload may carry.
+pub const MAX_SIGNS: usize = 1_000;
+
+/// The maximum number of used messages and heat records of stale eras removed in a block.
+pub const MAX_PRUNED_KEYS: usize = 1_000;
+
+pub type Tag = Vec<u8>;
+/// merkle-tree root hash
+pub type RootHash = Vec<u8>;
+
+/// A secp256k1 signature of a user over a `Msg`, in RSV format.
+#[derive(Encode, Decode, Clone)]
+pub struct Sig(pub [u8; 65]);
+
+impl PartialEq for Sig {
+	fn eq(&self, other: &Self) -> bool {
+		&self.0[..] == &other.0[..]
+	}
+}
+
+impl Eq for Sig {}
+
+impl sp_std::fmt::Debug for Sig {
+	fn fmt(&self, f: &mut sp_std::fmt::Formatter<'_>) -> sp_std::fmt::Result {
+		write!(f, "Signature({:?})", &self.0[..])
+	}
+}
+
+/// The message signed by a user. The first 8 bytes are the big-endian unix timestamp, in
+/// milliseconds, at which it was signed; the remaining 24 bytes are a commitment to the name and
+/// the root hash of the search service at that time.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug)]
+pub struct Msg(pub [u8; 32]);
+
+impl Msg {
+	/// The message to be signed at `timestamp` for the search service `name` whose current root
+	/// hash is `root_hash`.
+	pub fn new(name: &[u8], root_hash: &Option<RootHash>, timestamp: u64) -> Self {
+		let mut msg = [0u8; 32];
+		msg[0..8].copy_from_slice(&timestamp.to_be_bytes());
+		msg[8..32].copy_from_slice(&Self::commitment(name, root_hash)[0..24]);
+		Msg(msg)
+	}
+
+	/// The unix timestamp, in milliseconds, carried by this message.
+	pub fn timestamp(&self) -> u64 {
+		let mut ts = [0u8; 8];
+		ts.copy_from_slice(&self.0[0..8]);
+		u64::from_be_bytes(ts)
+	}
+
+	/// Whether this message was made for the search service `name` whose current root hash is
+	/// `root_hash`.
+	pub fn commits_to(&self, name: &[u8], root_hash: &Option<RootHash>) -> bool {
+		self.0[8..32] == Self::commitment(name, root_hash)[0..24]
+	}
+
+	fn commitment(name: &[u8], root_hash: &Option<RootHash>) -> [u8; 32] {
+		(name, root_hash).using_encoded(blake2_256)
+	}
+}
+
+/// An index of a challenge.
+pub type ChallengeIndex = u32;
+
+/// An index of an era.
+pub type EraIndex = u32;
+
+/// What a challenger holds against an upload.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug)]
+pub enum Evidence {
+	/// A record the provider served but left out of the root hash of the upload.
+	MissingLeaf(Vec<u8>),
+	/// A message counted as heat by the upload that was not genuinely signed by a user of the
+	/// service.
+	BadSignature(Sig, Msg),
+}
+
+/// An open challenge against an upload.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug)]
+pub struct Challenge<AccountId, Balance, BlockNumber> {
+	/// The name of the challenged search service.
+	pub name: Vec<u8>,
+	/// The index of the challenged upload.
+	pub upload: u32,
+	/// The root hash of the challenged upload.
+	pub root_hash: RootHash,
+	/// The account holding the bond.
+	pub challenger: AccountId,
+	/// The amount held on deposit by the challenger.
+	pub bond: Balance,
+	/// What the challenger holds against the upload.
+	pub evidence: Evidence,
+	/// The block at which the challenge is settled if nobody did before.
+	pub deadline: BlockNumber,
+}
+
+/// How the tags of a tag query are combined.
+#[derive(Encode, Decode, Clone, Copy, PartialEq, Eq, RuntimeDebug)]
+#[cfg_attr(feature = "std", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "std", serde(rename_all = "camelCase"))]
+pub enum TagMatch {
+	/// Match the search services carrying every one of the tags.
+	All,
+	/// Match the search services carrying at least one of the tags.
+	Any,
+}
+
+/// The weight of a call costing `base` plus an index write for each of `tags`.
+fn tagged_weight(base: Weight, tags: usize) -> Weight {
+	base.saturating_add((tags as Weight).saturating_mul(5_000))
+}
+
+/// The weight of a call costing `base` plus a signer recovery and a message write for each of
+/// `signs`.
+fn signed_weight(base: Weight, signs: usize) -> Weight {
+	base.saturating_add((signs as Weight).saturating_mul(25_000))
+}
+
+/// What is left of `heat` after `periods` decays keeping the part `decay` of it, rounded down so
+/// that any heat eventually decays away.
+fn decayed(decay: Perbill, heat: u64, periods: u32) -> u64 {
+	// Square and multiply, so that the heat of long idle services decays in O(log(periods)).
+	let (mut kept, mut square, mut periods) = (Perbill::one(), decay, periods);
+	while periods > 0 {
+		if periods & 1 == 1 {
+			kept = kept.saturating_mul(square);
+		}
+		square = square.square();
+		periods >>= 1;
+	}
+	let accuracy = u128::from(<Perbill as PerThing>::ACCURACY);
+	(u128::from(heat) * u128::from(kept.deconstruct()) / accuracy) as u64
+}
+
+pub trait Trait: frame_system::Trait {
+	/// The overarching event type.
+	type Event: From<Event<Self>> + Into<<Self as frame_system::Trait>::Event>;
+
+	/// The overarching dispatch call type.
+	type Call: From<Call<Self>>;
+
+	/// The type to sign and submit uploads from the offchain worker.
+	type SubmitSignedTransaction: system_offchain::SubmitSignedTransaction<Self, <Self as Trait>::Call>;
+
+	/// The minimum number of blocks between two uploads the offchain worker submits for a search
+	/// service, so that an upload is included before the next one is built on its root hash.
+	type UploadInterval: Get<Self::BlockNumber>;
+
+	/// The currency in which deposits are reserved and providers are rewarded.
+	type Currency: ReservableCurrency<Self::AccountId>;
+
+	/// The id of the pot that rewards are paid from. This may well be the id of the treasury.
+	type ModuleId: Get<ModuleId>;
+
+	/// The number of blocks in an era. Rewards for the heat uploaded during an era are claimed
+	/// during the next one. Eras never end if this is zero.
+	type EraDuration: Get<Self::BlockNumber>;
+
+	/// The amount owed to a provider for each unit of heat.
+	type RewardPerHeat: Get<BalanceOf<Self>>;
+
+	/// The maximum amount set aside in the pot for the rewards of one era.
+	type EraBudget: Get<BalanceOf<Self>>;
+
+	/// The number of blocks between two decays of the heat of every search service. Heat never
+	/// decays if this is zero.
+	type HeatDecayPeriod: Get<Self::BlockNumber>;
+
+	/// The part of its heat a search service keeps on every decay.
+	type HeatDecay: Get<Perbill>;
+
+	/// The number of search services kept in the on-chain ranking.
+	type RankingSize: Get<u32>;
+
+	/// The number of latest uploads of a search service whose root hash is kept.
+	type RootHistoryDepth: Get<u32>;
+
+	/// The amount held on deposit for a registered search service.
+	type ServiceDepositBase: Get<BalanceOf<Self>>;
+
+	/// The amount held on deposit per byte of the name, url and tags of a search service.
+	type ServiceDepositPerByte: Get<BalanceOf<Self>>;
+
+	/// What to do with slashed deposits.
+	type Slashed: OnUnbalanced<NegativeImbalanceOf<Self>>;
+
+	/// The origin which may forcibly remove a search service. Root can always do this.
+	type ForceOrigin: EnsureOrigin<Self::Origin>;
+
+	/// The amount held on deposit by a challenger.
+	type ChallengeBond: Get<BalanceOf<Self>>;
+
+	/// The number of blocks a challenge stays open. Must be less than `EraDuration`, so that a
+	/// challenge against an upload is settled while the reward for its heat can be claimed.
+	type ChallengePeriod: Get<Self::BlockNumber>;
+
+	/// The part of the bond of a challenger slashed when nobody settles the challenge in time.
+	type DismissalSlash: Get<Perbill>;
+
+	/// The origin which may resolve a challenge. Root can always do this.
+	type ResolveOrigin: EnsureOrigin<Self::Origin>;
+
+	/// Time used for stamping registrations and uploads.
+	type UnixTime: UnixTime;
+}
+
+/// Information about a registered search service. Times are unix timestamps in milliseconds.
+#[derive(Encode, Decode, Default, Clone, PartialEq, Eq, RuntimeDebug)]
+#[cfg_attr(feature = "std", derive(serde::Serialize, serde::Deserialize))]
+pub struct SearchServiceInfo<AccountId> {
+	/// The account providing the service.
+	pub provider: AccountId,
+	/// The unique name of the service.
+	pub name: Vec<u8>,
+	/// Where the service can be reached.
+	pub url: Vec<u8>,
+	/// The tags describing the service.
+	pub tags: Vec<Tag>,
+	/// When the service was registered.
+	pub register_time: u64,
+	/// The number of distinct signers of all uploads, decayed over time. The heat stored is only
+	/// decayed up to the last upload; the one returned by the public functions is up to date.
+	pub heat: u64,
+}
+
+/// The latest merkle-root hash uploaded for a search service.
+#[derive(Encode, Decode, Default, Clone, PartialEq, Eq, RuntimeDebug)]
+#[cfg_attr(feature = "std", derive(serde::Serialize, serde::Deserialize))]
+pub struct SearchServiceHash<AccountId> {
+	/// The account providing the service.
+	pub provider: AccountId,
+	/// The latest root hash, if anything was uploaded yet.
+	pub root_hash: Option<RootHash>,
+	/// When the root hash was last updated.
+	pub update_time: u64,
+}
+
+decl_storage! {
+	trait Store for Module<T: Trait> as Search {
+		/// search service name -> search service info
+		SearchServices get(fn get_ss): map hasher(blake2_128_concat) Vec<u8> => SearchServiceInfo<T::AccountId>;
+		/// search service name -> search service hash
+		SsHashes get(fn get_hash): map hasher(blake2_128_concat) Vec<u8> => SearchServiceHash<T::AccountId>;
+		/// search service name -> the deposit reserved from its provider
+		Deposits get(fn deposit_of): map hasher(blake2_128_concat) Vec<u8> => BalanceOf<T>;
+		/// The index of the current era.
+		CurrentEra get(fn current_era): EraIndex;
+		/// The number of eras whose used messages and heat were all removed. The eras before the
+		/// previous one are stale and removed `MAX_PRUNED_KEYS` keys per block at most.
+		PrunedEras get(fn pruned_eras): EraIndex;
+		/// era -> blake2_256 hash of (signer, message) -> (), the signed messages counted as heat
+		/// during that era, kept for the current and the previous era only
+		UsedMessages: double_map hasher(twox_64_concat) EraIndex, hasher(identity) [u8; 32] => ();
+		/// tag -> search service name -> (), an index of the services carrying each tag
+		TagIndex: double_map hasher(blake2_128_concat) Tag, hasher(blake2_128_concat) Vec<u8> => ();
+		/// era -> search service name -> the heat uploaded during that era, yet to be claimed
+		EraHeat get(fn era_heat):
+			double_map hasher(twox_64_concat) EraIndex, hasher(blake2_128_concat) Vec<u8> => u64;
+		/// era -> the heat uploaded during that era by the search services not removed since
+		EraTotalHeat get(fn era_total_heat): map hasher(twox_64_concat) EraIndex => u64;
+		/// era -> the (amount, total heat) to be shared for the heat uploaded during that era, kept
+		/// until the end of the next era only
+		EraRewards get(fn era_rewards):
+			map hasher(twox_64_concat) EraIndex => Option<(BalanceOf<T>, u64)>;
+		/// search service name -> the decay period its stored heat was last decayed in
+		HeatDecayedAt: map hasher(blake2_128_concat) Vec<u8> => T::BlockNumber;
+		/// The (heat, name) of the hottest search services, hottest first and ties by name, at
+		/// most `RankingSize` of them. The heat is decayed up to `RankedAt`.
+		Ranking get(fn ranking): Vec<(u64, Vec<u8>)>;
+		/// The decay period the heat in the ranking was last decayed in.
+		RankedAt: T::BlockNumber;
+		/// search service name -> the number of uploads made for it
+		Uploads get(fn uploads): map hasher(blake2_128_concat) Vec<u8> => u32;
+		/// search service name -> upload index -> the (root hash, update time) of that upload,
+		/// for the latest `RootHistoryDepth` uploads only
+		RootHistory get(fn root_history):
+			double_map hasher(blake2_128_concat) Vec<u8>, hasher(twox_64_concat) u32 => Option<(RootHash, u64)>;
+		/// The number of challenges ever opened.
+		ChallengeCount get(fn challenge_count): ChallengeIndex;
+		/// challenge index -> the open challenge
+		Challenges get(fn challenges):
+			map hasher(twox_64_concat) ChallengeIndex => Option<Challenge<T::AccountId, BalanceOf<T>, T::BlockNumber>>;
+		/// block number -> the challenges to be settled at that block, if still open
+		ChallengeDeadlines: map hasher(twox_64_concat) T::BlockNumber => Vec<ChallengeIndex>;
+		/// search service name -> the number of open challenges against it
+		OpenChallenges get(fn open_challenges): map hasher(blake2_128_concat) Vec<u8> => u32;
+	}
+	add_extra_genesis {
+		config(services): Vec<(T::AccountId, Vec<u8>, Vec<u8>, Vec<Tag>, Option<RootHash>)>;
+		build(|config: &GenesisConfig<T>| {
+			let era_duration = T::EraDuration::get();
+			assert!(
+				era_duration.is_zero() || T::ChallengePeriod::get() < era_duration,
+				"challenges must be settled within an era",
+			);
+			// Create the reward pot account
+			let _ = T::Currency::make_free_balance_be(
+				&<Module<T>>::account_id(),
+				T::Currency::minimum_balance(),
+			);
+			// Register the initial search services
+			// * provider - Account providing the service, from which the deposit is reserved
+			// * name, url, tags - As given to `register_search_service`
+			// * root_hash - The root hash of a first upload, if any
+			for (provider, name, url, tags, root_hash) in config.services.iter() {
+				<Module<T>>::register_at_genesis(provider, name, url, tags, root_hash.clone());
+			}
+		});
+	}
+}
+
+decl_event!(
+	pub enum Event<T> where AccountId = <T as frame_system::Trait>::AccountId, Balance = BalanceOf<T> {
+		/// A search service was registered by the given provider, who reserved the given deposit.
+		Registered(Vec<u8>, AccountId, Balance),
+		/// The url and tags of a search service were updated.
+		Updated(Vec<u8>),
+		/// A search service was handed over from the first provider to the second one.
+		OwnershipTransferred(Vec<u8>, AccountId, AccountId),
+		/// A search service was deregistered by the given provider and the given deposit returned.
+		Deregistered(Vec<u8>, AccountId, Balance),
+		/// A search service was forcibly removed and the given deposit slashed.
+		Killed(Vec<u8>, AccountId, Balance),
+		/// return a timestamp after uploading searched info
+		Timestamp(u64),
+		/// The provider of a search service was paid the given reward for its heat in the last era.
+		Rewarded(Vec<u8>, AccountId, Balance),
+		/// An era ended; the given total was set aside for the given total heat.
+		EraEnded(Balance, u64),
+		/// A challenge was opened against a search service by the given challenger.
+		Challenged(ChallengeIndex, Vec<u8>, AccountId),
+		/// A challenge was upheld: the search service was removed and the given deposit paid to the
+		/// given challenger.
+		ChallengeUpheld(ChallengeIndex, AccountId, Balance),
+		/// A challenge was rejected and the given bond slashed.
+		ChallengeRejected(ChallengeIndex, Balance),
+		/// A challenge was closed without a verdict; the given part of its bond was slashed and
+		/// the rest returned.
+		ChallengeDismissed(ChallengeIndex, Balance),
+	}
+);
+
+decl_error! {
+	/// Error for the search module.
+	pub enum Error for Module<T: Trait> {
+		/// when the count of tags more than 10, give an error
+		TagsOverflow,
+		/// service name exists
+		NameExists,
+		/// service name does not exist
+		NameNotExists,
+		/// merkle-root hash is illegal
+		RootHashIllegal,
+		/// signature is illegal
+		SignatureIllegal,
+		/// permission denied
+		PermissionDenied,
+		/// signature earlier than update_time
+		SignatureTooEarly,
+		/// signature later than the current time
+		SignatureTooLate,
+		/// signed message does not commit to the service name and its current root hash
+		MessageMismatch,
+		/// signed message was already counted
+		MessageUsed,
+		/// when the count of signatures more than `MAX_SIGNS`, give an error
+		TooManySignatures,
+		/// the root hash of the upload is not known
+		UploadNotExists,
+		/// signed message was never counted
+		MessageNotCounted,
+		/// challenge does not exist
+		ChallengeNotExists,
+		/// challenge can not be answered with a merkle proof
+		ChallengeNotAnswerable,
+		/// merkle proof does not prove the record part of the root hash
+		ProofInvalid,
+		/// search service is under challenge
+		UnderChallenge,
+		/// there is no reward to claim
+		NothingToClaim,
+		/// no more challenges can be opened
+		TooManyChallenges,
+	}
+}
+
+decl_module! {
+	pub struct Module<T: Trait> for enum Call where origin: T::Origin {
+		type Error = Error<T>;
+
+		fn deposit_event() = default;
+
+		/// The amount held on deposit for a registered search service.
+		const ServiceDepositBase: BalanceOf<T> = T::ServiceDepositBase::get();
+
+		/// The amount held on deposit per byte of the name, url and tags of a search service.
+		const ServiceDepositPerByte: BalanceOf<T> = T::ServiceDepositPerByte::get();
+
+		/// The number of blocks in an era.
+		const EraDuration: T::BlockNumber = T::EraDuration::get();
+
+		/// The amount owed to a provider for each unit of heat.
+		const RewardPerHeat: BalanceOf<T> = T::RewardPerHeat::get();
+
+		/// The maximum amount set aside in the pot for the rewards of one era.
+		const EraBudget: BalanceOf<T> = T::EraBudget::get();
+
+		/// The number of blocks between two decays of the heat of every search service.
+		const HeatDecayPeriod: T::BlockNumber = T::HeatDecayPeriod::get();
+
+		/// The part of its heat a search service keeps on every decay.
+		const HeatDecay: Perbill = T::HeatDecay::get();
+
+		/// The number of search services kept in the on-chain ranking.
+		const RankingSize: u32 = T::RankingSize::get();
+
+		/// The number of latest uploads of a search service whose root hash is kept.
+		const RootHistoryDepth: u32 = T::RootHistoryDepth::get();
+
+		/// The amount held on deposit by a challenger.
+		const ChallengeBond: BalanceOf<T> = T::ChallengeBond::get();
+
+		/// The number of blocks a challenge stays open.
+		const ChallengePeriod: T::BlockNumber = T::ChallengePeriod::get();
+
+		/// The part of the bond of a challenger slashed when nobody settles the challenge in time.
+		const DismissalSlash: Perbill = T::DismissalSlash::get();
+
+		/// Register a search service under `name`. The caller becomes its provider.
+		///
+		/// Duplicate tags are dropped. A deposit of `ServiceDepositBase` plus `ServiceDepositPerByte`
+		/// for every byte of the name, url and tags is reserved from the caller.
+		///
+		/// The dispatch origin for this call must be _Signed_.
+		///
+		/// # <weight>
+		/// - O(T) where T is the number of tags (bounded by `MAX_TAGS`).
+		/// - One balance reserve operation.
+		/// - Three storage writes plus one index write per tag.
+		/// - One event.
+		/// # </weight>
+		#[weight = FunctionOf(
+			|args: (&Vec<u8>, &Vec<u8>, &Vec<Tag>)| tagged_weight(10_000, args.2.len()),
+			DispatchClass::Normal,
+			true
+		)]
+		fn register_search_service(origin, name: Vec<u8>, url: Vec<u8>, tags: Vec<Tag>) -> DispatchResult {
+			let provider = ensure_signed(origin)?;
+			ensure!(!SearchServices::<T>::contains_key(&name), Error::<T>::NameExists);
+			Self::ensure_unchallenged(&name)?;
+			ensure!(tags.len() <= MAX_TAGS, Error::<T>::TagsOverflow);
+			let tags = Self::normalize_tags(tags);
+			let deposit = Self::deposit_for(&name, &url, &tags);
+			T::Currency::reserve(&provider, deposit)?;
+			let now = Self::now();
+			let ss_info = SearchServiceInfo {
+				provider: provider.clone(),
+				name: name.clone(),
+				url,
+				tags,
+				register_time: now,
+				heat: 0,
+			};
+			let ss_hash = SearchServiceHash {
+				provider,
+				root_hash: None,
+				update_time: now,
+			};
+			Self::index_tags(&name, &ss_info.tags);
+			SearchServices::<T>::insert(&name, &ss_info);
+			SsHashes::<T>::insert(&name, &ss_hash);
+			Deposits::<T>::insert(&name, deposit);
+			Self::deposit_event(RawEvent::Registered(name, ss_hash.provider, deposit));
+			Ok(())
+		}
+
+		/// Replace the url and tags of the search service `name`.
+		///
+		/// The deposit is recomputed for the new url and tags; any difference is reserved from or
+		/// returned to the provider.
+		///
+		/// The dispatch origin for this call must be _Signed_ by the provider of `name`.
+		///
+		/// # <weight>
+		/// - O(T) where T is the number of old and new tags (bounded by `MAX_TAGS`).
+		/// - At most one balance operation.
+		/// - Two storage reads/writes plus one index write per old and new tag.
+		/// - One event.
+		/// # </weight>
+		#[weight = FunctionOf(
+			|args: (&Vec<u8>, &Vec<u8>, &Vec<Tag>)| tagged_weight(10_000, MAX_TAGS + args.2.len()),
+			DispatchClass::Normal,
+			true
+		)]
+		fn update_service_info(origin, name: Vec<u8>, url: Vec<u8>, tags: Vec<Tag>) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			ensure!(tags.len() <= MAX_TAGS, Error::<T>::TagsOverflow);
+			let mut ss_info = Self::ensure_provider(&name, &who)?;
+			Self::ensure_unchallenged(&name)?;
+			let tags = Self::normalize_tags(tags);
+
+			let old_deposit = Self::deposit_of(&name);
+			let new_deposit = Self::deposit_for(&name, &url, &tags);
+			if new_deposit > old_deposit {
+				T::Currency::reserve(&who, new_deposit - old_deposit)?;
+			}
+			if old_deposit > new_deposit {
+				let _ = T::Currency::unreserve(&who, old_deposit - new_deposit);
+			}
+			Deposits::<T>::insert(&name, new_deposit);
+
+			Self::unindex_tags(&name, &ss_info.tags);
+			Self::index_tags(&name, &tags);
+			ss_info.url = url;
+			ss_info.tags = tags;
+			SearchServices::<T>::insert(&name, &ss_info);
+
+			Self::deposit_event(RawEvent::Updated(name));
+			Ok(())
+		}
+
+		/// Hand the search service `name` over to `new_provider`, who gets all of its future
+		/// rewards.
+		///
+		/// The deposit is reserved from `new_provider` and returned to the caller.
+		///
+		/// The dispatch origin for this call must be _Signed_ by the provider of `name`.
+		///
+		/// # <weight>
+		/// - O(1).
+		/// - Two balance operations.
+		/// - Three storage reads, two storage writes.
+		/// - One event.
+		/// # </weight>
+		#[weight = SimpleDispatchInfo::FixedNormal(20_000)]
+		fn transfer_service_ownership(
+			origin,
+			name: Vec<u8>,
+			new_provider: <T::Lookup as StaticLookup>::Source
+		) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			let new_provider = T::Lookup::lookup(new_provider)?;
+			Self::ensure_provider(&name, &who)?;
+			Self::ensure_unchallenged(&name)?;
+
+			let deposit = Self::deposit_of(&name);
+			T::Currency::reserve(&new_provider, deposit)?;
+			let _ = T::Currency::unreserve(&who, deposit);
+
+			SearchServices::<T>::mutate(&name, |ssi| ssi.provider = new_provider.clone());
+			SsHashes::<T>::mutate(&name, |sh| sh.provider = new_provider.clone());
+
+			Self::deposit_event(RawEvent::OwnershipTransferred(name, who, new_provider));
+			Ok(())
+		}
+
+		/// Remove the search service `name`, its root hash and its tag index entries and return
+		/// the deposit.
+		///
+		/// The dispatch origin for this call must be _Signed_ by the provider of `name`.
+		///
+		/// # <weight>
+		/// - O(T) where T is the number of tags (bounded by `MAX_TAGS`).
+		/// - One balance operation.
+		/// - Three storage removals plus one index removal per tag.
+		/// - One event.
+		/// # </weight>
+		#[weight = FunctionOf(
+			|_: (&Vec<u8>,)| tagged_weight(10_000, MAX_TAGS),
+			DispatchClass::Normal,
+			true
+		)]
+		fn deregister_search_service(origin, name: Vec<u8>) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			let ss_info = Self::ensure_provider(&name, &who)?;
+			Self::ensure_unchallenged(&name)?;
+
+			let deposit = Self::remove_service(&name, &ss_info.tags);
+			let _ = T::Currency::unreserve(&who, deposit);
+
+			Self::deposit_event(RawEvent::Deregistered(name, who, deposit));
+			Ok(())
+		}
+
+		/// Remove the search service `name` and take charge of the deposit.
+		///
+		/// The deposit is dealt with through the `T::Slashed` imbalance handler.
+		///
+		/// The dispatch origin for this call must be _Root_ or match `T::ForceOrigin`.
+		///
+		/// # <weight>
+		/// - O(T) where T is the number of tags (bounded by `MAX_TAGS`).
+		/// - One unbalanced handler (probably a balance transfer).
+		/// - Three storage removals plus one index removal per tag.
+		/// - One event.
+		/// # </weight>
+		#[weight = FunctionOf(
+			|_: (&Vec<u8>,)| tagged_weight(20_000, MAX_TAGS),
+			DispatchClass::Operational,
+			true
+		)]
+		fn force_remove_search_service(origin, name: Vec<u8>) -> DispatchResult {
+			T::ForceOrigin::try_origin(origin)
+				.map(|_| ())
+				.or_else(ensure_root)?;
+
+			ensure!(SearchServices::<T>::contains_key(&name), Error::<T>::NameNotExists);
+			let ss_info = Self::get_ss(&name);
+
+			let deposit = Self::remove_service(&name, &ss_info.tags);
+			T::Slashed::on_unbalanced(T::Currency::slash_reserved(&ss_info.provider, deposit).0);
+
+			Self::deposit_event(RawEvent::Killed(name, ss_info.provider, deposit));
+			Ok(())
+		}
+
+		/// Replace the root hash of `name` with `root_hash` and add the heat of `signs` to it, to be
+		/// rewarded for the current era.
+		///
+		/// `last_root_hash` must match the currently stored root hash. Every message in `signs` must
+		/// be signed between the previous upload and now, commit to `name` and `last_root_hash`,
+		/// and never have been counted before. The heat is the number of distinct signers. At most
+		/// `MAX_SIGNS` signatures are accepted.
+		///
+		/// The dispatch origin for this call must be _Signed_ by the provider of `name`.
+		///
+		/// # <weight>
+		/// - O(S + R) where S is the number of signatures (bounded by `MAX_SIGNS`) and R is
+		///   `RankingSize`.
+		/// - Eight storage reads/writes plus one signer recovery and two reads/one write per
+		///   signature.
+		/// - One event.
+		/// # </weight>
+		#[weight = FunctionOf(
+			|args: (&Vec<u8>, &Vec<(Sig, Msg)>, &RootHash, &Option<RootHash>)|
+				signed_weight(10_000, args.1.len()),
+			DispatchClass::Normal,
+			true
+		)]
+		fn upload_searched_info(
+			origin,
+			name: Vec<u8>,
+			signs: Vec<(Sig, Msg)>,
+			root_hash: RootHash,
+			last_root_hash: Option<RootHash>
+		) -> DispatchResult {
+			let ssp = ensure_signed(origin)?;
+			ensure!(signs.len() <= MAX_SIGNS, Error::<T>::TooManySignatures);
+			ensure!(SsHashes::<T>::contains_key(&name), Error::<T>::NameNotExists);
+			let ss_hash = Self::get_hash(&name);
+			ensure!(ss_hash.provider == ssp, Error::<T>::PermissionDenied);
+			ensure!(ss_hash.root_hash == last_root_hash, Error::<T>::RootHashIllegal);
+			let now = Self::now();
+			let (heat, used) = Self::validate_signatures(&name, &ss_hash, &signs, now)?;
+
+			let era = CurrentEra::get();
+			for key in used {
+				UsedMessages::insert(era, key, ());
+			}
+			Self::record_root(&name, &root_hash, now);
+			SsHashes::<T>::mutate(&name, |sh| {
+				sh.root_hash = Some(root_hash);
+				sh.update_time = now;
+			});
+			let period = Self::decay_period();
+			let total_heat = SearchServices::<T>::mutate(&name, |ssi| {
+				ssi.heat = Self::decay(&name, ssi.heat, period).saturating_add(heat);
+				ssi.heat
+			});
+			HeatDecayedAt::<T>::insert(&name, period);
+			Self::rank(&name, total_heat, period);
+			EraHeat::mutate(era, &name, |h| *h = h.saturating_add(heat));
+			EraTotalHeat::mutate(era, |h| *h = h.saturating_add(heat));
+			Self::deposit_event(RawEvent::Timestamp(now));
+			Ok(())
+		}
+
+		/// Claim the reward of the search service `name` for its heat in the previous era. It is
+		/// paid out of the pot to the provider, even while the service is under challenge.
+		///
+		/// The dispatch origin for this call must be _Signed_ by the provider of `name`.
+		///
+		/// # <weight>
+		/// - O(1).
+		/// - One balance transfer.
+		/// - Five storage reads, one storage removal.
+		/// - One event.
+		/// # </weight>
+		#[weight = SimpleDispatchInfo::FixedNormal(20_000)]
+		fn claim_reward(origin, name: Vec<u8>) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			Self::ensure_provider(&name, &who)?;
+			let era = CurrentEra::get().checked_sub(1).ok_or(Error::<T>::NothingToClaim)?;
+			let (amount, total_heat) = Self::era_rewards(era).ok_or(Error::<T>::NothingToClaim)?;
+			let heat = Self::era_heat(era, &name);
+			ensure!(heat > 0, Error::<T>::NothingToClaim);
+
+			let reward = Self::reward_for(heat, amount, total_heat);
+			T::Currency::transfer(&Self::account_id(), &who, reward, KeepAlive)?;
+			EraHeat::remove(era, &name);
+
+			Self::deposit_event(RawEvent::Rewarded(name, who, reward));
+			Ok(())
+		}
+
+		/// Challenge the upload `upload` of the search service `name` with `evidence`.
+		///
+		/// `ChallengeBond` is reserved from the caller, of which `DismissalSlash` is slashed if nobody
+		/// settles the challenge within `ChallengePeriod`. A `BadSignature` must be about a message
+		/// that was counted as heat by the upload, during the current or the previous era; the root
+		/// hash of the upload before it must still be known.
+		///
+		/// The dispatch origin for this call must be _Signed_.
+		///
+		/// # <weight>
+		/// - O(1).
+		/// - One balance reserve operation.
+		/// - Six storage reads, four storage writes.
+		/// - One event.
+		/// # </weight>
+		#[weight = SimpleDispatchInfo::FixedNormal(20_000)]
+		fn challenge(origin, name: Vec<u8>, upload: u32, evidence: Evidence) -> DispatchResult {
+			let challenger = ensure_signed(origin)?;
+			ensure!(SearchServices::<T>::contains_key(&name), Error::<T>::NameNotExists);
+			let (root_hash, _) = RootHistory::get(&name, upload).ok_or(Error::<T>::UploadNotExists)?;
+			if let Evidence::BadSignature(sig, msg) = &evidence {
+				// The messages counted by an upload commit to the root hash it replaced.
+				let last_root_hash = match upload.checked_sub(1) {
+					Some(last) => {
+						let (last_root_hash, _) = RootHistory::get(&name, last)
+							.ok_or(Error::<T>::UploadNotExists)?;
+						Some(last_root_hash)
+					},
+					None => None,
+				};
+				ensure!(msg.commits_to(&name, &last_root_hash), Error::<T>::MessageMismatch);
+				let signer = Self::recover_signer(sig, msg)?;
+				let key = Self::message_key(&signer, msg);
+				ensure!(Self::is_used(&key), Error::<T>::MessageNotCounted);
+			}
+
+			let index = ChallengeCount::get();
+			let count = index.checked_add(1).ok_or(Error::<T>::TooManyChallenges)?;
+			let bond = T::ChallengeBond::get();
+			T::Currency::reserve(&challenger, bond)?;
+
+			ChallengeCount::put(count);
+			let deadline = <system::Module<T>>::block_number() + T::ChallengePeriod::get();
+			ChallengeDeadlines::<T>::append_or_insert(deadline, &[index][..]);
+			OpenChallenges::mutate(&name, |count| *count += 1);
+			Challenges::<T>::insert(index, Challenge {
+				name: name.clone(),
+				upload,
+				root_hash,
+				challenger: challenger.clone(),
+				bond,
+				evidence,
+				deadline,
+			});
+
+			Self::deposit_event(RawEvent::Challenged(index, name, challenger));
+			Ok(())
+		}
+
+		/// Refute the `MissingLeaf` challenge `index` by proving its record part of the root hash of
+		/// the challenged upload. The bond of the challenger is slashed.
+		///
+		/// The dispatch origin for this call must be _Signed_ by the provider of the challenged
+		/// search service.
+		///
+		/// # <weight>
+		/// - O(P) where P is the length of the proof.
+		/// - One unbalanced handler (probably a balance transfer).
+		/// - Two storage reads, two storage writes.
+		/// - One event.
+		/// # </weight>
+		#[weight = SimpleDispatchInfo::FixedNormal(20_000)]
+		fn answer_challenge(origin, index: ChallengeIndex, proof: MerkleProof) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			let challenge = Self::challenges(index).ok_or(Error::<T>::ChallengeNotExists)?;
+			Self::ensure_provider(&challenge.name, &who)?;
+			match &challenge.evidence {
+				Evidence::MissingLeaf(record) =>
+					ensure!(proof.verify(record, &challenge.root_hash), Error::<T>::ProofInvalid),
+				Evidence::BadSignature(..) => return Err(Error::<T>::ChallengeNotAnswerable.into()),
+			}
+
+			Self::reject(index);
+			Ok(())
+		}
+
+		/// Uphold or reject the challenge `index`.
+		///
+		/// The dispatch origin for this call must be _Root_ or match `T::ResolveOrigin`.
+		///
+		/// # <weight>
+		/// - O(T) where T is the number of tags (bounded by `MAX_TAGS`).
+		/// - Up to two balance operations.
+		/// - Two storage reads/writes, plus the removal of the search service if upheld.
+		/// - One event.
+		/// # </weight>
+		#[weight = FunctionOf(
+			|_: (&ChallengeIndex, &bool)| tagged_weight(30_000, MAX_TAGS),
+			DispatchClass::Operational,
+			true
+		)]
+		fn resolve_challenge(origin, index: ChallengeIndex, upheld: bool) -> DispatchResult {
+			T::ResolveOrigin::try_origin(origin)
+				.map(|_| ())
+				.or_else(ensure_root)?;
+			ensure!(Challenges::<T>::contains_key(index), Error::<T>::ChallengeNotExists);
+
+			if upheld {
+				Self::uphold(index);
+			} else {
+				Self::reject(index);
+			}
+			Ok(())
+		}
+
+		fn on_initialize(n: T::BlockNumber) -> Weight {
+			// Taking the deadlines of the block.
+			let mut weight: Weight = 5_000;
+
+			// Set the rewards of the era that just ended aside and start the next one.
+			let era_duration = T::EraDuration::get();
+			if !era_duration.is_zero() && (n % era_duration).is_zero() {
+				Self::new_era();
+				weight = weight.saturating_add(50_000);
+			}
+
+			// Remove some of what is left of the stale eras.
+			let pruned = Self::prune_stale_eras(MAX_PRUNED_KEYS);
+			weight = weight.saturating_add((pruned as Weight).saturating_mul(2_000));
+
+			// Dismiss the challenges nobody settled in time.
+			for index in ChallengeDeadlines::<T>::take(n) {
+				Self::dismiss(index);
+				weight = weight.saturating_add(20_000);
+			}
+
+			weight
+		}
+
+		fn offchain_worker(n: T::BlockNumber) {
+			Self::upload_pending(n);
+		}
+	}
+}
+
+impl<T: Trait> Module<T> {
+	// PUBLIC IMMUTABLES
+
+	/// The account ID of the reward pot.
+	///
+	/// This actually does computation. If you need to keep using it, then make sure you cache the
+	/// value and only call this once.
+	pub fn account_id() -> T::AccountId {
+		T::ModuleId::get().into_account()
+	}
+
+	/// The hottest search services, hottest first, at most `count` (bounded by `MAX_PAGE_SIZE`)
+	/// of them.
+	///
+	/// Without a `tag`, they are taken from the on-chain ranking. With a `tag`, all the services
+	/// carrying it are ranked.
+	pub fn recommend(tag: Option<Tag>, count: u32) -> Vec<SearchServiceInfo<T::AccountId>> {
+		let count = count.min(MAX_PAGE_SIZE) as usize;
+		match tag {
+			None => Self::ranking()
+				.into_iter()
+				.map(|(_, name)| Self::decayed_info(&name))
+				.filter(|ss_info| ss_info.heat > 0)
+				.take(count)
+				.collect(),
+			Some(tag) => {
+				let mut services = TagIndex::iter(tag)
+					.map(|(name, _)| Self::decayed_info(&name))
+					.collect::<Vec<_>>();
+				services.sort_by(|a, b| (Reverse(a.heat), &a.name).cmp(&(Reverse(b.heat), &b.name)));
+				services.truncate(count);
+				services
+			}
+		}
+	}
+
+	/// The search services carrying all (or any, depending on `matching`) of `tags`, ordered by
+	/// name.
+	///
+	/// Results are paginated: the first `start` matches are skipped and at most `count` (bounded
+	/// by `MAX_PAGE_SIZE`) are returned. An empty `tags` matches nothing.
+	pub fn services_by_tags(
+		tags: Vec<Tag>,
+		matching: TagMatch,
+		start: u32,
+		count: u32,
+	) -> Vec<SearchServiceInfo<T::AccountId>> {
+		let tags = Self::normalize_tags(tags);
+		let names: BTreeSet<Vec<u8>> = match matching {
+			TagMatch::Any => tags.iter()
+				.flat_map(|tag| TagIndex::iter(tag).map(|(name, _)| name))
+				.collect(),
+			TagMatch::All => match tags.split_first() {
+				Some((first, rest)) => TagIndex::iter(first)
+					.map(|(name, _)| name)
+					.filter(|name| rest.iter().all(|tag| TagIndex::contains_key(tag, name)))
+					.collect(),
+				None => BTreeSet::new(),
+			},
+		};
+		names.into_iter()
+			.skip(start as usize)
+			.take(count.min(MAX_PAGE_SIZE) as usize)
+			.map(|name| Self::decayed_info(&name))
+			.collect()
+	}
+
+	/// The search service registered under `name`, if any.
+	pub fn service_by_name(name: Vec<u8>) -> Option<SearchServiceInfo<T::AccountId>> {
+		if SearchServices::<T>::contains_key(&name) {
+			Some(Self::decayed_info(&name))
+		} else {
+			None
+		}
+	}
+
+	/// The latest root hash uploaded for the search service registered under `name`, if any.
+	pub fn service_hash(name: Vec<u8>) -> Option<SearchServiceHash<T::AccountId>> {
+		if SsHashes::<T>::contains_key(&name) {
+			Some(Self::get_hash(name))
+		} else {
+			None
+		}
+	}
+
+	/// The root hash of the upload `upload` of the search service `name`, or its current root hash
+	/// if `upload` is `None`. Only the latest `RootHistoryDepth` uploads are known.
+	pub fn root_at(name: &[u8], upload: Option<u32>) -> Option<RootHash> {
+		match upload {
+			None => SsHashes::<T>::get(name).root_hash,
+			Some(index) => RootHistory::get(name, index).map(|(root_hash, _)| root_hash),
+		}
+	}
+
+	/// Whether `proof` proves `record` to be part of the root hash of the upload `upload` of the
+	/// search service `name`, or of its current root hash if `upload` is `None`.
+	pub fn verify_searched_item(
+		name: Vec<u8>,
+		upload: Option<u32>,
+		record: Vec<u8>,
+		proof: MerkleProof,
+	) -> bool {
+		Self::root_at(&name, upload)
+			.map_or(false, |root_hash| proof.verify(&record, &root_hash))
+	}
+
+	// PRIVATE IMMUTABLES
+
+	/// The current unix time in milliseconds.
+	fn now() -> u64 {
+		T::UnixTime::now().as_millis().saturated_into::<u64>()
+	}
+
+	/// The number of heat decay periods elapsed so far.
+	fn decay_period() -> T::BlockNumber {
+		let period = T::HeatDecayPeriod::get();
+		if period.is_zero() {
+			return Zero::zero();
+		}
+		<system::Module<T>>::block_number() / period
+	}
+
+	/// The stored `heat` of the search service `name`, decayed up to the decay period `period`.
+	fn decay(name: &Vec<u8>, heat: u64, period: T::BlockNumber) -> u64 {
+		let periods = period.saturating_sub(HeatDecayedAt::<T>::get(name));
+		decayed(T::HeatDecay::get(), heat, periods.saturated_into::<u32>())
+	}
+
+	/// The information about the search service `name`, with its heat decayed up to now.
+	fn decayed_info(name: &Vec<u8>) -> SearchServiceInfo<T::AccountId> {
+		let mut ss_info = Self::get_ss(name);
+		ss_info.heat = Self::decay(name, ss_info.heat, Self::decay_period());
+		ss_info
+	}
+
+	/// Check `signs` submitted for the search service `name` at `now`.
+	///
+	/// Returns the number of distinct signers together with the keys under which the messages are
+	/// to be marked as used in `UsedMessages`.
+	fn validate_signatures(
+		name: &[u8],
+		ss_hash: &SearchServiceHash<T::AccountId>,
+		signs: &[(Sig, Msg)],
+		now: u64,
+	) -> Result<(u64, Vec<[u8; 32]>), Error<T>> {
+		let mut signers = BTreeSet::new();
+		let mut used = BTreeSet::new();
+		for (sig, msg) in signs {
+			ensure!(msg.timestamp() >= ss_hash.update_time, Error::<T>::SignatureTooEarly);
+			ensure!(msg.timestamp() <= now, Error::<T>::SignatureTooLate);
+			ensure!(msg.commits_to(name, &ss_hash.root_hash), Error::<T>::MessageMismatch);
+			let signer = Self::recover_signer(sig, msg)?;
+			let key = Self::message_key(&signer, msg);
+			ensure!(!Self::is_used(&key) && used.insert(key), Error::<T>::MessageUsed);
+			signers.insert(signer.to_vec());
+		}
+		Ok((signers.len() as u64, used.into_iter().collect()))
+	}
+
+	/// The public key of the signer of `msg`.
+	fn recover_signer(sig: &Sig, msg: &Msg) -> Result<[u8; 64], Error<T>> {
+		secp256k1_ecdsa_recover(&sig.0, &msg.0).map_err(|_| Error::<T>::SignatureIllegal)
+	}
+
+	/// The key under which `msg` signed by `signer` is marked as used in `UsedMessages`.
+	fn message_key(signer: &[u8; 64], msg: &Msg) -> [u8; 32] {
+		// Keyed by signer rather than by signature, since ECDSA signatures are malleable.
+		(&signer[..], msg).using_encoded(blake2_256)
+	}
+
+	/// Whether the message under `key` was counted as heat during the current or the previous era.
+	///
+	/// Older messages are forgotten. They can not be counted again anyway, as every message must be
+	/// signed after the last upload of its search service.
+	fn is_used(key: &[u8; 32]) -> bool {
+		let era = CurrentEra::get();
+		UsedMessages::contains_key(era, key)
+			|| era.checked_sub(1).map_or(false, |prev| UsedMessages::contains_key(prev, key))
+	}
+
+	/// Ensure that there is no open challenge against the search service `name`.
+	fn ensure_unchallenged(name: &Vec<u8>) -> Result<(), Error<T>> {
+		ensure!(!OpenChallenges::contains_key(name), Error::<T>::UnderChallenge);
+		Ok(())
+	}
+
+	/// Ensure that `who` provides the search service `name` and return its information.
+	fn ensure_provider(
+		name: &Vec<u8>,
+		who: &T::AccountId,
+	) -> Result<SearchServiceInfo<T::AccountId>, Error<T>> {
+		ensure!(SearchServices::<T>::contains_key(name), Error::<T>::NameNotExists);
+		let ss_info = Self::get_ss(name);
+		ensure!(&ss_info.provider == who, Error::<T>::PermissionDenied);
+		Ok(ss_info)
+	}
+
+	/// The reward for `heat` out of the `total_heat` uploaded during an era for which `amount` was
+	/// set aside.
+	///
+	/// Every unit of heat is owed `RewardPerHeat`. If `amount` falls short of the total owed, it is
+	/// shared in proportion to heat instead.
+	fn reward_for(heat: u64, amount: BalanceOf<T>, total_heat: u64) -> BalanceOf<T> {
+		let per_heat = T::RewardPerHeat::get();
+		if per_heat.saturating_mul(total_heat.saturated_into()) <= amount {
+			per_heat.saturating_mul(heat.saturated_into())
+		} else {
+			Perbill::from_rational_approximation(heat, total_heat) * amount
+		}
+	}
+
+	/// The deposit for a search service called `name` at `url` carrying `tags`.
+	fn deposit_for(name: &[u8], url: &[u8], tags: &[Tag]) -> BalanceOf<T> {
+		let bytes = tags.iter().fold(name.len() + url.len(), |acc, tag| acc.saturating_add(tag.len()));
+		let bytes = BalanceOf::<T>::from(bytes.saturated_into::<u32>());
+		T::ServiceDepositBase::get().saturating_add(T::ServiceDepositPerByte::get().saturating_mul(bytes))
+	}
+
+	/// The amount of money in the pot that may be paid out. The existential deposit is not part of
+	/// it, so the pot account never gets deleted.
+	fn pot() -> BalanceOf<T> {
+		T::Currency::free_balance(&Self::account_id())
+			.saturating_sub(T::Currency::minimum_balance())
+	}
+
+	/// Sort `tags` and drop the duplicates.
+	fn normalize_tags(mut tags: Vec<Tag>) -> Vec<Tag> {
+		tags.sort();
+		tags.dedup();
+		tags
+	}
+
+	// PRIVATE MUTABLES
+
+	/// Register the search service `name` at genesis, as `register_search_service` would, and
+	/// record `root_hash` as its first upload, if any.
+	fn register_at_genesis(
+		provider: &T::AccountId,
+		name: &Vec<u8>,
+		url: &[u8],
+		tags: &[Tag],
+		root_hash: Option<RootHash>,
+	) {
+		assert!(!SearchServices::<T>::contains_key(name), "Search service names must be unique");
+		assert!(tags.len() <= MAX_TAGS, "Search services carry at most `MAX_TAGS` tags");
+		let tags = Self::normalize_tags(tags.to_vec());
+		let deposit = Self::deposit_for(name, url, &tags);
+		T::Currency::reserve(provider, deposit)
+			.expect("Providers must be able to pay the deposit of their genesis search services");
+		let now = Self::now();
+		if let Some(root_hash) = &root_hash {
+			Self::record_root(name, root_hash, now);
+		}
+		Self::index_tags(name, &tags);
+		SearchServices::<T>::insert(name, SearchServiceInfo {
+			provider: provider.clone(),
+			name: name.clone(),
+			url: url.to_vec(),
+			tags,
+			register_time: now,
+			heat: 0,
+		});
+		SsHashes::<T>::insert(name, SearchServiceHash {
+			provider: provider.clone(),
+			root_hash,
+			update_time: now,
+		});
+		Deposits::<T>::insert(name, deposit);
+	}
+
+	/// Add `name` to the index of each of `tags`.
+	fn index_tags(name: &Vec<u8>, tags: &[Tag]) {
+		for tag in tags {
+			TagIndex::insert(tag, name, ());
+		}
+	}
+
+	/// Remove `name` from the index of each of `tags`.
+	fn unindex_tags(name: &Vec<u8>, tags: &[Tag]) {
+		for tag in tags {
+			TagIndex::remove(tag, name);
+		}
+	}
+
+	/// Remove everything stored about the search service `name` carrying `tags`, including the
+	/// heat not yet rewarded, and return its deposit, which is left reserved.
+	fn remove_service(name: &Vec<u8>, tags: &[Tag]) -> BalanceOf<T> {
+		Self::unindex_tags(name, tags);
+		SearchServices::<T>::remove(name);
+		SsHashes::<T>::remove(name);
+		let era = CurrentEra::get();
+		let heat = EraHeat::take(era, name);
+		EraTotalHeat::mutate(era, |h| *h = h.saturating_sub(heat));
+		if let Some(previous) = era.checked_sub(1) {
+			EraHeat::remove(previous, name);
+		}
+		HeatDecayedAt::<T>::remove(name);
+		Uploads::remove(name);
+		RootHistory::remove_prefix(name);
+		Ranking::mutate(|ranking| ranking.retain(|(_, n)| n != name));
+		Deposits::<T>::take(name)
+	}
+
+	/// Record `root_hash` uploaded at `now` as the next upload of the search service `name`,
+	/// forgetting the root hash that falls out of the history.
+	fn record_root(name: &Vec<u8>, root_hash: &RootHash, now: u64) {
+		let index = Uploads::mutate(name, |count| {
+			let index = *count;
+			*count = count.saturating_add(1);
+			index
+		});
+		RootHistory::insert(name, index, (root_hash.clone(), now));
+		if let Some(stale) = index.checked_sub(T::RootHistoryDepth::get()) {
+			RootHistory::remove(name, stale);
+		}
+	}
+
+	/// Move the search service `name` to its place in the ranking for its new `heat`, decayed up
+	/// to the decay period `period`; it drops out if it is not hot enough. The rest of the ranking
+	/// is decayed up to `period` too, which keeps it in order.
+	fn rank(name: &Vec<u8>, heat: u64, period: T::BlockNumber) {
+		let periods = period.saturating_sub(RankedAt::<T>::get()).saturated_into::<u32>();
+		RankedAt::<T>::put(period);
+		let decay = T::HeatDecay::get();
+		Ranking::mutate(|ranking| {
+			ranking.retain(|(_, n)| n != name);
+			if periods > 0 {
+				for (h, _) in ranking.iter_mut() {
+					*h = decayed(decay, *h, periods);
+				}
+				ranking.retain(|(h, _)| *h > 0);
+			}
+			if heat == 0 {
+				return;
+			}
+			let key = (Reverse(heat), name);
+			let pos = ranking.binary_search_by(|(h, n)| (Reverse(*h), n).cmp(&key))
+				.unwrap_or_else(|pos| pos);
+			ranking.insert(pos, (heat, name.clone()));
+			ranking.truncate(T::RankingSize::get() as usize);
+		});
+	}
+
+	/// End the current era and start the next one.
+	///
+	/// The rewards of the era that ended are set aside: `RewardPerHeat` for every unit of heat, but
+	/// no more than the lesser of `EraBudget` and the pot. The rewards not claimed for the previous
+	/// era are forfeited and the messages counted during it forgotten, the previous era becoming
+	/// stale.
+	fn new_era() {
+		let ended = CurrentEra::get();
+		CurrentEra::put(ended.saturating_add(1));
+		if let Some(stale) = ended.checked_sub(1) {
+			EraRewards::<T>::remove(stale);
+		}
+
+		let total_heat = EraTotalHeat::take(ended);
+		if total_heat == 0 {
+			return;
+		}
+		let owed = T::RewardPerHeat::get().saturating_mul(total_heat.saturated_into());
+		let amount = owed.min(T::EraBudget::get()).min(Self::pot());
+		EraRewards::<T>::insert(ended, (amount, total_heat));
+		Self::deposit_event(RawEvent::EraEnded(amount, total_heat));
+	}
+
+	/// Remove up to `limit` used messages and heat records of the stale eras, oldest first, and
+	/// return the number removed.
+	///
+	/// Nothing reads them anymore, so they are removed over as many blocks as it takes.
+	fn prune_stale_eras(limit: usize) -> usize {
+		let stale = CurrentEra::get().saturating_sub(1);
+		let pruned_eras = PrunedEras::get();
+		let mut era = pruned_eras;
+		let mut removed = 0;
+		while era < stale && removed < limit {
+			removed += UsedMessages::drain(era).take(limit - removed).count();
+			removed += EraHeat::drain(era).take(limit - removed).count();
+			// Both were drained if fewer keys than asked for were removed.
+			if removed < limit {
+				era += 1;
+			}
+		}
+		if era != pruned_eras {
+			PrunedEras::put(era);
+		}
+		removed
+	}
+
+	/// Close the challenge `index`, if open, and return it.
+	fn close_challenge(index: ChallengeIndex) -> Option<Challenge<T::AccountId, BalanceOf<T>, T::BlockNumber>> {
+		let challenge = Challenges::<T>::take(index)?;
+		OpenChallenges::mutate_exists(&challenge.name, |count| {
+			*count = count.map(|c| c.saturating_sub(1)).filter(|c| *c > 0);
+		});
+		Some(challenge)
+	}
+
+	/// Uphold the challenge `index`: remove the challenged search service and pay its deposit to the
+	/// challenger. If the service is already gone, the challenge is dismissed instead.
+	fn uphold(index: ChallengeIndex) {
+		let challenge = match Self::close_challenge(index) {
+			Some(challenge) => challenge,
+			None => return,
+		};
+		let _ = T::Currency::unreserve(&challenge.challenger, challenge.bond);
+		if !SearchServices::<T>::contains_key(&challenge.name) {
+			Self::deposit_event(RawEvent::ChallengeDismissed(index, Zero::zero()));
+			return;
+		}
+
+		let ss_info = Self::get_ss(&challenge.name);
+		let deposit = Self::remove_service(&challenge.name, &ss_info.tags);
+		// Best-effort only: the deposit is reserved by the provider.
+		let _ = T::Currency::repatriate_reserved(
+			&ss_info.provider,
+			&challenge.challenger,
+			deposit,
+			BalanceStatus::Free,
+		);
+		Self::deposit_event(RawEvent::ChallengeUpheld(index, challenge.challenger, deposit));
+	}
+
+	/// Reject the challenge `index` and slash the bond of the challenger.
+	fn reject(index: ChallengeIndex) {
+		if let Some(challenge) = Self::close_challenge(index) {
+			T::Slashed::on_unbalanced(T::Currency::slash_reserved(&challenge.challenger, challenge.bond).0);
+			Self::deposit_event(RawEvent::ChallengeRejected(index, challenge.bond));
+		}
+	}
+
+	/// Close the challenge `index` without a verdict, nobody having settled it in time. The
+	/// `DismissalSlash` of the bond of the challenger is slashed, so that challenges are not opened
+	/// for free, and the rest returned.
+	fn dismiss(index: ChallengeIndex) {
+		if let Some(challenge) = Self::close_challenge(index) {
+			let slash = T::DismissalSlash::get() * challenge.bond;
+			T::Slashed::on_unbalanced(T::Currency::slash_reserved(&challenge.challenger, slash).0);
+			let _ = T::Currency::unreserve(&challenge.challenger, challenge.bond - slash);
+			Self::deposit_event(RawEvent::ChallengeDismissed(index, slash));
+		}
+	}
+
+	// OFFCHAIN
+
+	/// Submit an upload for every search service with a queued `PendingUpload` whose provider has
+	/// a local key.
+	///
+	/// Signatures that could not be counted by the upload are dropped. If the upload can not be
+	/// submitted, what was queued is queued again. What was submitted is kept until the upload is
+	/// recorded, and queued again ahead of the next upload if it is not recorded by then. The names
+	/// of the search services that are no longer registered or whose provider has no local key are
+	/// dropped from `PENDING_NAMES_KEY`, together with what was queued for them.
+	fn upload_pending(block_number: T::BlockNumber) {
+		use system_offchain::SubmitSignedTransaction;
+		if !T::SubmitSignedTransaction::can_sign() {
+			return;
+		}
+
+		let names = StorageValueRef::persistent(offchain::PENDING_NAMES_KEY)
+			.get::<Vec<Vec<u8>>>()
+			.and_then(|names| names)
+			.unwrap_or_default();
+		let mut dropped = Vec::new();
+		for name in names {
+			if !SearchServices::<T>::contains_key(&name) {
+				dropped.push(name);
+				continue;
+			}
+			let provider = Self::get_ss(&name).provider;
+			if !T::SubmitSignedTransaction::can_sign_with(Some(vec![provider.clone()])) {
+				dropped.push(name);
+				continue;
+			}
+			let submitted_key = offchain::submitted_key(&name);
+			let in_flight = StorageValueRef::persistent(&submitted_key);
+			let unrecorded = match in_flight.get::<Option<(u32, offchain::PendingUpload)>>() {
+				Some(Some(Some((upload, pending))))
+					if !Self::is_recorded(&name, upload, &pending) => Some(pending),
+				Some(Some(Some(_))) => {
+					in_flight.set(&None::<(u32, offchain::PendingUpload)>);
+					None
+				},
+				_ => None,
+			};
+			let queued = StorageValueRef::persistent(&offchain::pending_key(&name))
+				.get::<offchain::PendingUpload>()
+				.and_then(|pending| pending)
+				.map_or(false, |pending| !pending.is_empty());
+			if (!queued && unrecorded.is_none()) || !Self::lock_upload(&name, block_number) {
+				continue;
+			}
+			// The last upload was not recorded in time; submit it again with what was queued since.
+			if let Some(pending) = unrecorded {
+				in_flight.set(&None::<(u32, offchain::PendingUpload)>);
+				Self::requeue_pending(&name, pending);
+			}
+			let pending = match Self::take_pending(&name) {
+				Some(pending) => pending,
+				None => continue,
+			};
+			in_flight.set(&Some((Self::uploads(&name), pending.clone())));
+
+			let ss_hash = Self::get_hash(&name);
+			let signs = Self::countable_signatures(&name, &ss_hash, pending.signs.clone());
+			let root_hash = merkle::root(&pending.records).to_vec();
+			let call = Call::upload_searched_info(name.clone(), signs, root_hash, ss_hash.root_hash);
+			let results = T::SubmitSignedTransaction::submit_signed_from(call, vec![provider]);
+			let mut submitted = !results.is_empty();
+			for (account, result) in results {
+				match result {
+					Ok(()) => debug::info!("[{:?}] Submitted upload for search service {:?}", account, name),
+					Err(e) => {
+						debug::error!("[{:?}] Failed to submit upload: {:?}", account, e);
+						submitted = false;
+					},
+				}
+			}
+			if !submitted {
+				in_flight.set(&None::<(u32, offchain::PendingUpload)>);
+				Self::requeue_pending(&name, pending);
+			}
+		}
+
+		if !dropped.is_empty() {
+			for name in &dropped {
+				StorageValueRef::persistent(&offchain::pending_key(name))
+					.set(&offchain::PendingUpload::default());
+				StorageValueRef::persistent(&offchain::submitted_key(name))
+					.set(&None::<(u32, offchain::PendingUpload)>);
+			}
+			// The node RPC may have queued more names meanwhile; only remove the dropped ones.
+			let _ = StorageValueRef::persistent(offchain::PENDING_NAMES_KEY)
+				.mutate(|names: Option<Option<Vec<Vec<u8>>>>| {
+					let mut names = names.and_then(|names| names).unwrap_or_default();
+					names.retain(|name| !dropped.contains(name));
+					Ok::<_, ()>(names)
+				});
+		}
+	}
+
+	/// The part of `signs` an upload for the search service `name` can count now, at most
+	/// `MAX_SIGNS` of them.
+	fn countable_signatures(
+		name: &[u8],
+		ss_hash: &SearchServiceHash<T::AccountId>,
+		signs: Vec<(Sig, Msg)>,
+	) -> Vec<(Sig, Msg)> {
+		let now = Self::now();
+		let mut keys = BTreeSet::new();
+		signs.into_iter()
+			.filter(|(sig, msg)| {
+				msg.timestamp() >= ss_hash.update_time
+					&& msg.timestamp() <= now
+					&& msg.commits_to(name, &ss_hash.root_hash)
+					&& Self::recover_signer(sig, msg).map_or(false, |signer| {
+						let key = Self::message_key(&signer, msg);
+						!Self::is_used(&key) && keys.insert(key)
+					})
+			})
+			.take(MAX_SIGNS)
+			.collect()
+	}
+
+	/// Whether `pending`, submitted as the upload `upload` of the search service `name`, was
+	/// recorded as that upload.
+	fn is_recorded(name: &[u8], upload: u32, pending: &offchain::PendingUpload) -> bool {
+		Self::root_at(name, Some(upload)).map_or(false, |root_hash| {
+			root_hash[..] == merkle::root(&pending.records)[..]
+		})
+	}
+
+	/// Take the right to submit an upload for the search service `name` at `block_number`, unless
+	/// one was submitted less than `UploadInterval` blocks ago.
+	fn lock_upload(name: &[u8], block_number: T::BlockNumber) -> bool {
+		let key = offchain::last_upload_key(name);
+		let res = StorageValueRef::persistent(&key).mutate(|last: Option<Option<T::BlockNumber>>| {
+			match last {
+				Some(Some(block)) if block_number < block + T::UploadInterval::get() => Err(()),
+				_ => Ok(block_number),
+			}
+		});
+		// The lock may also have been taken by a worker running concurrently.
+		match res {
+			Ok(Ok(_)) => true,
+			_ => false,
+		}
+	}
+
+	/// Queue `pending`, taken for the search service `name`, again, ahead of what was queued since.
+	fn requeue_pending(name: &[u8], pending: offchain::PendingUpload) {
+		let key = offchain::pending_key(name);
+		let requeue = |queued: Option<Option<offchain::PendingUpload>>| {
+			let mut requeued = pending.clone();
+			if let Some(queued) = queued.and_then(|queued| queued) {
+				requeued.append(queued);
+			}
+			Ok::<_, ()>(requeued)
+		};
+		// Retry until the node RPC stops queueing concurrently.
+		while let Ok(Err(_)) = StorageValueRef::persistent(&key).mutate(&requeue) {}
+	}
+
+	/// Take the `PendingUpload` queued for the search service `name`, leaving an empty one.
+	fn take_pending(name: &[u8]) -> Option<offchain::PendingUpload> {
+		let key = offchain::pending_key(name);
+		let mut taken = None;
+		let res = StorageValueRef::persistent(&key).mutate(|pending: Option<Option<offchain::PendingUpload>>| {
+			taken = pending.and_then(|pending| pending);
+			Ok::<_, ()>(offchain::PendingUpload::default())
+		});
+		// Something was queued concurrently; leave it all for the next run.
+		match res {
+			Ok(Ok(_)) => taken,
+			_ => None,
+		}
+	}
+}
@@ -0,0 +1,127 @@
+//! The merkle-tree format of searched-info records.
+//!
+//! The root hash a provider uploads is the root of a binary merkle tree over the records it has
+//! served, in the order it chooses:
+//!
+//! * A leaf is `blake2_256(0x00 ++ record)`, where `record` is the raw bytes of the record.
+//! * An inner node is `blake2_256(0x01 ++ left ++ right)`.
+//! * Each level pairs up the nodes of the level below from left to right; a last node left without
+//!   a partner is carried up to the next level unchanged.
+//!
+//! The distinct prefixes keep a leaf from ever passing for an inner node. The root of a tree
+//! without leaves is the hash of nothing, `blake2_256([])`.
+
+use sp_std::prelude::*;
+use codec::{Encode, Decode};
+use sp_io::hashing::blake2_256;
+use sp_runtime::RuntimeDebug;
+
+/// A node of the tree.
+pub type Hash = [u8; 32];
+
+const LEAF_PREFIX: u8 = 0;
+const NODE_PREFIX: u8 = 1;
+
+/// The leaf of `record`.
+pub fn leaf_hash(record: &[u8]) -> Hash {
+	let mut data = Vec::with_capacity(1 + record.len());
+	data.push(LEAF_PREFIX);
+	data.extend_from_slice(record);
+	blake2_256(&data)
+}
+
+/// The inner node above `left` and `right`.
+pub fn node_hash(left: &Hash, right: &Hash) -> Hash {
+	let mut data = [0u8; 65];
+	data[0] = NODE_PREFIX;
+	data[1..33].copy_from_slice(left);
+	data[33..65].copy_from_slice(right);
+	blake2_256(&data)
+}
+
+/// The level above `level`.
+fn parent_level(level: &[Hash]) -> Vec<Hash> {
+	level.chunks(2)
+		.map(|pair| match pair {
+			[left, right] => node_hash(left, right),
+			[last] => *last,
+			_ => unreachable!("chunks of two are never empty; qed"),
+		})
+		.collect()
+}
+
+/// The root of the tree over `records`.
+pub fn root<R: AsRef<[u8]>>(records: &[R]) -> Hash {
+	let mut level = records.iter().map(|r| leaf_hash(r.as_ref())).collect::<Vec<_>>();
+	if level.is_empty() {
+		return blake2_256(&[]);
+	}
+	while level.len() > 1 {
+		level = parent_level(&level);
+	}
+	level[0]
+}
+
+/// A proof that a record is a leaf of a tree.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug)]
+#[cfg_attr(feature = "std", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "std", serde(rename_all = "camelCase"))]
+pub struct MerkleProof {
+	/// The position of the leaf among all leaves, from zero.
+	pub index: u32,
+	/// The number of leaves of the tree.
+	pub leaves: u32,
+	/// The siblings on the path from the leaf up to the root, lowest first. Levels at which the
+	/// path is carried up unchanged have none.
+	pub siblings: Vec<Hash>,
+}
+
+impl MerkleProof {
+	/// The proof that the record at `index` is a leaf of the tree over `records`, if there is such
+	/// a record.
+	pub fn generate<R: AsRef<[u8]>>(records: &[R], index: u32) -> Option<Self> {
+		if index as usize >= records.len() {
+			return None;
+		}
+		let mut level = records.iter().map(|r| leaf_hash(r.as_ref())).collect::<Vec<_>>();
+		let mut position = index as usize;
+		let mut siblings = Vec::new();
+		while level.len() > 1 {
+			let sibling = position ^ 1;
+			if sibling < level.len() {
+				siblings.push(level[sibling]);
+			}
+			level = parent_level(&level);
+			position /= 2;
+		}
+		Some(MerkleProof { index, leaves: records.len() as u32, siblings })
+	}
+
+	/// Whether this proves `record` to be a leaf of the tree whose root is `root`.
+	pub fn verify(&self, record: &[u8], root: &[u8]) -> bool {
+		if self.index >= self.leaves {
+			return false;
+		}
+		let mut node = leaf_hash(record);
+		let mut position = self.index;
+		let mut width = self.leaves;
+		let mut siblings = self.siblings.iter();
+		while width > 1 {
+			// The last node of an odd level is carried up unchanged.
+			if !(position == width - 1 && width % 2 == 1) {
+				let sibling = match siblings.next() {
+					Some(sibling) => sibling,
+					None => return false,
+				};
+				node = if position % 2 == 0 {
+					node_hash(&node, sibling)
+				} else {
+					node_hash(sibling, &node)
+				};
+			}
+			position /= 2;
+			width = (width + 1) / 2;
+		}
+		siblings.next().is_none() && &node[..] == root
+	}
+}
@@ -0,0 +1,64 @@
+//! The offchain local storage shared by the offchain worker and the node RPC that feeds it.
+//!
+//! The node RPC queues, for each search service, the user signatures and the records served since
+//! the last upload as a `PendingUpload` under `pending_key(name)`, and lists the name under
+//! `PENDING_NAMES_KEY`. The offchain worker of a node holding the key of the provider takes the
+//! queue, computes the root hash of the records and submits the upload, or queues it all again if
+//! it could not. What it submitted is kept under `submitted_key(name)` until the upload is seen on
+//! chain, and queued again if it is not by the time the next upload may be submitted. It drops the
+//! names of the services it can not upload for, so the list only holds the services provided by the
+//! node.
+//!
+//! Everything is kept in `PERSISTENT` storage and SCALE-encoded.
+
+use sp_std::prelude::*;
+use codec::{Encode, Decode};
+use sp_runtime::RuntimeDebug;
+
+use crate::{Sig, Msg};
+
+/// The key of the `Vec<Vec<u8>>` of the names of the search services uploads are queued for.
+pub const PENDING_NAMES_KEY: &[u8] = b"search::pending-names";
+
+const PENDING_PREFIX: &[u8] = b"search::pending::";
+const LAST_UPLOAD_PREFIX: &[u8] = b"search::last-upload::";
+const SUBMITTED_PREFIX: &[u8] = b"search::submitted::";
+
+/// The key of the `PendingUpload` queued for the search service `name`.
+pub fn pending_key(name: &[u8]) -> Vec<u8> {
+	[PENDING_PREFIX, name].concat()
+}
+
+/// The key of the block number at which the last upload for the search service `name` was
+/// submitted.
+pub(crate) fn last_upload_key(name: &[u8]) -> Vec<u8> {
+	[LAST_UPLOAD_PREFIX, name].concat()
+}
+
+/// The key of the `Option<(u32, PendingUpload)>` submitted as the last upload for the search
+/// service `name`: the index the upload was submitted as, and what it was made of.
+pub(crate) fn submitted_key(name: &[u8]) -> Vec<u8> {
+	[SUBMITTED_PREFIX, name].concat()
+}
+
+/// What is queued for the next upload of a search service.
+#[derive(Encode, Decode, Default, Clone, PartialEq, Eq, RuntimeDebug)]
+pub struct PendingUpload {
+	/// The user signatures collected since the last upload.
+	pub signs: Vec<(Sig, Msg)>,
+	/// The records served since the last upload, in order.
+	pub records: Vec<Vec<u8>>,
+}
+
+impl PendingUpload {
+	/// Whether nothing is queued.
+	pub fn is_empty(&self) -> bool {
+		self.signs.is_empty() && self.records.is_empty()
+	}
+
+	/// Queue everything of `other` after what is already queued.
+	pub fn append(&mut self, other: PendingUpload) {
+		self.signs.extend(other.signs);
+		self.records.extend(other.records);
+	}
+}
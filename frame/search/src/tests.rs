@@ -0,0 +1,1350 @@
+//! Tests for the search module.
+
+use super::*;
+
+use frame_support::{
+	assert_noop, assert_ok, impl_outer_origin, parameter_types, ord_parameter_types, weights::Weight,
+	traits::{OnInitialize, OffchainWorker},
+};
+use frame_system::EnsureSignedBy;
+use sp_core::{
+	H256,
+	offchain::{OffchainExt, TransactionPoolExt, testing},
+	sr25519,
+	testing::KeyStore,
+	traits::KeystoreExt,
+};
+use sp_runtime::{
+	Perbill, RuntimeAppPublic,
+	testing::{Header, TestXt},
+	traits::{BlakeTwo256, IdentityLookup, BadOrigin, IdentifyAccount, Extrinsic as ExtrinsicT},
+};
+
+impl_outer_origin! {
+	pub enum Origin for Test where system = frame_system {}
+}
+
+#[derive(Clone, Eq, PartialEq)]
+pub struct Test;
+parameter_types! {
+	pub const BlockHashCount: u64 = 250;
+	pub const MaximumBlockWeight: Weight = 1024;
+	pub const MaximumBlockLength: u32 = 2 * 1024;
+	pub const AvailableBlockRatio: Perbill = Perbill::one();
+}
+impl frame_system::Trait for Test {
+	type Origin = Origin;
+	type Index = u64;
+	type BlockNumber = u64;
+	type Call = ();
+	type Hash = H256;
+	type Hashing = BlakeTwo256;
+	type AccountId = u64;
+	type Lookup = IdentityLookup<Self::AccountId>;
+	type Header = Header;
+	type Event = ();
+	type BlockHashCount = BlockHashCount;
+	type MaximumBlockWeight = MaximumBlockWeight;
+	type AvailableBlockRatio = AvailableBlockRatio;
+	type MaximumBlockLength = MaximumBlockLength;
+	type Version = ();
+	type ModuleToIndex = ();
+	type AccountData = pallet_balances::AccountData<u64>;
+	type OnNewAccount = ();
+	type OnKilledAccount = ();
+}
+parameter_types! {
+	pub const ExistentialDeposit: u64 = 1;
+}
+impl pallet_balances::Trait for Test {
+	type Balance = u64;
+	type Event = ();
+	type DustRemoval = ();
+	type ExistentialDeposit = ExistentialDeposit;
+	type AccountStore = System;
+}
+parameter_types! {
+	pub const MinimumPeriod: u64 = 1;
+}
+impl pallet_timestamp::Trait for Test {
+	type Moment = u64;
+	type OnTimestampSet = ();
+	type MinimumPeriod = MinimumPeriod;
+}
+type Extrinsic = TestXt<Call<Test>, ()>;
+type SubmitTransaction = frame_system::offchain::TransactionSubmitter<crypto::Public, Test, Extrinsic>;
+
+/// An sr25519 key standing for the account made of its first eight bytes.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct TestPublic(sr25519::Public);
+
+impl IdentifyAccount for TestPublic {
+	type AccountId = u64;
+	fn into_account(self) -> u64 {
+		let mut account = [0u8; 8];
+		account.copy_from_slice(&AsRef::<[u8]>::as_ref(&self.0)[0..8]);
+		u64::from_le_bytes(account)
+	}
+}
+
+impl From<sr25519::Public> for TestPublic {
+	fn from(public: sr25519::Public) -> Self {
+		TestPublic(public)
+	}
+}
+
+impl From<TestPublic> for sr25519::Public {
+	fn from(public: TestPublic) -> Self {
+		public.0
+	}
+}
+
+impl frame_system::offchain::CreateTransaction<Test, Extrinsic> for Test {
+	type Public = TestPublic;
+	type Signature = sr25519::Signature;
+
+	fn create_transaction<F: frame_system::offchain::Signer<Self::Public, Self::Signature>>(
+		call: <Extrinsic as ExtrinsicT>::Call,
+		_public: Self::Public,
+		_account: u64,
+		nonce: u64,
+	) -> Option<(<Extrinsic as ExtrinsicT>::Call, <Extrinsic as ExtrinsicT>::SignaturePayload)> {
+		Some((call, (nonce, ())))
+	}
+}
+
+parameter_types! {
+	pub const UploadInterval: u64 = 5;
+	pub const ServiceDepositBase: u64 = 10;
+	pub const ServiceDepositPerByte: u64 = 1;
+	pub const SearchModuleId: ModuleId = ModuleId(*b"py/srchr");
+	pub const EraDuration: u64 = 10;
+	pub const RewardPerHeat: u64 = 10;
+	pub const EraBudget: u64 = 100;
+	pub const HeatDecayPeriod: u64 = 5;
+	pub const HeatDecay: Perbill = Perbill::from_percent(50);
+	pub const RankingSize: u32 = 2;
+	pub const RootHistoryDepth: u32 = 2;
+	pub const ChallengeBond: u64 = 5;
+	pub const ChallengePeriod: u64 = 3;
+	pub const DismissalSlash: Perbill = Perbill::from_percent(20);
+}
+ord_parameter_types! {
+	pub const One: u64 = 1;
+}
+impl Trait for Test {
+	type Event = ();
+	type Call = Call<Test>;
+	type SubmitSignedTransaction = SubmitTransaction;
+	type UploadInterval = UploadInterval;
+	type Currency = Balances;
+	type ModuleId = SearchModuleId;
+	type EraDuration = EraDuration;
+	type RewardPerHeat = RewardPerHeat;
+	type EraBudget = EraBudget;
+	type HeatDecayPeriod = HeatDecayPeriod;
+	type HeatDecay = HeatDecay;
+	type RankingSize = RankingSize;
+	type RootHistoryDepth = RootHistoryDepth;
+	type ServiceDepositBase = ServiceDepositBase;
+	type ServiceDepositPerByte = ServiceDepositPerByte;
+	type Slashed = ();
+	type ForceOrigin = EnsureSignedBy<One, u64>;
+	type ChallengeBond = ChallengeBond;
+	type ChallengePeriod = ChallengePeriod;
+	type DismissalSlash = DismissalSlash;
+	type ResolveOrigin = EnsureSignedBy<One, u64>;
+	type UnixTime = Timestamp;
+}
+type System = frame_system::Module<Test>;
+type Balances = pallet_balances::Module<Test>;
+type Timestamp = pallet_timestamp::Module<Test>;
+type Search = Module<Test>;
+
+fn new_test_ext() -> sp_io::TestExternalities {
+	let mut t = frame_system::GenesisConfig::default().build_storage::<Test>().unwrap();
+	pallet_balances::GenesisConfig::<Test> {
+		balances: vec![(1, 1_000), (2, 1_000), (3, 10)],
+	}.assimilate_storage(&mut t).unwrap();
+	let mut ext = sp_io::TestExternalities::new(t);
+	ext.execute_with(|| Timestamp::set_timestamp(1_000));
+	ext
+}
+
+fn register(who: u64, name: &[u8], tags: Vec<Tag>) {
+	assert_ok!(Search::register_search_service(
+		Origin::signed(who),
+		name.to_vec(),
+		b"https://example.com".to_vec(),
+		tags,
+	));
+}
+
+/// Sign `msg` with the key derived from `seed`.
+fn sign_msg(seed: u8, msg: Msg) -> (Sig, Msg) {
+	let secret = secp256k1::SecretKey::parse(&[seed; 32]).unwrap();
+	let (sig, recovery_id) = secp256k1::sign(&secp256k1::Message::parse(&msg.0), &secret);
+	let mut raw = [0u8; 65];
+	raw[0..64].copy_from_slice(&sig.serialize()[..]);
+	raw[64] = recovery_id.serialize();
+	(Sig(raw), msg)
+}
+
+/// Sign, with the key derived from `seed`, a message stamped with `ts` for the search service
+/// `name` whose current root hash is `root`.
+fn sign(seed: u8, name: &[u8], root: Option<&[u8]>, ts: u64) -> (Sig, Msg) {
+	sign_msg(seed, Msg::new(name, &root.map(|r| r.to_vec()), ts))
+}
+
+#[test]
+fn register_search_service_works() {
+	new_test_ext().execute_with(|| {
+		register(1, b"alice", vec![b"news".to_vec()]);
+
+		let ss = Search::get_ss(b"alice".to_vec());
+		assert_eq!(ss.provider, 1);
+		assert_eq!(ss.url, b"https://example.com".to_vec());
+		assert_eq!(ss.tags, vec![b"news".to_vec()]);
+		assert_eq!(ss.register_time, 1_000);
+		assert_eq!(ss.heat, 0);
+
+		let hash = Search::get_hash(b"alice".to_vec());
+		assert_eq!(hash.provider, 1);
+		assert_eq!(hash.root_hash, None);
+		assert_eq!(hash.update_time, 1_000);
+	});
+}
+
+#[test]
+fn register_search_service_fails_for_taken_name() {
+	new_test_ext().execute_with(|| {
+		register(1, b"alice", vec![]);
+		assert_noop!(
+			Search::register_search_service(Origin::signed(2), b"alice".to_vec(), vec![], vec![]),
+			Error::<Test>::NameExists,
+		);
+	});
+}
+
+#[test]
+fn register_search_service_fails_for_too_many_tags() {
+	new_test_ext().execute_with(|| {
+		let tags = (0..=MAX_TAGS as u8).map(|i| vec![i]).collect::<Vec<_>>();
+		assert_noop!(
+			Search::register_search_service(Origin::signed(1), b"alice".to_vec(), vec![], tags),
+			Error::<Test>::TagsOverflow,
+		);
+	});
+}
+
+#[test]
+fn genesis_config_registers_services() {
+	let mut t = frame_system::GenesisConfig::default().build_storage::<Test>().unwrap();
+	pallet_balances::GenesisConfig::<Test> {
+		balances: vec![(1, 1_000), (2, 1_000)],
+	}.assimilate_storage(&mut t).unwrap();
+	let records = vec![b"first".to_vec(), b"second".to_vec()];
+	let root = merkle::root(&records).to_vec();
+	GenesisConfig::<Test> {
+		services: vec![
+			(1, b"alice".to_vec(), b"https://example.com".to_vec(), vec![b"news".to_vec()], Some(root.clone())),
+			(2, b"bob".to_vec(), b"https://example.com".to_vec(), vec![], None),
+		],
+	}.assimilate_storage(&mut t).unwrap();
+
+	sp_io::TestExternalities::new(t).execute_with(|| {
+		let ss = Search::get_ss(b"alice".to_vec());
+		assert_eq!(ss.provider, 1);
+		assert_eq!(ss.tags, vec![b"news".to_vec()]);
+		assert_eq!(Search::get_hash(b"alice".to_vec()).root_hash, Some(root));
+		assert_eq!(Search::uploads(b"alice".to_vec()), 1);
+		let proof = MerkleProof::generate(&records, 1).unwrap();
+		assert!(Search::verify_searched_item(b"alice".to_vec(), None, records[1].clone(), proof));
+		assert_eq!(names(Search::services_by_tags(vec![b"news".to_vec()], TagMatch::All, 0, 10)), vec![b"alice".to_vec()]);
+
+		assert_eq!(Search::get_hash(b"bob".to_vec()).root_hash, None);
+		assert_eq!(Search::uploads(b"bob".to_vec()), 0);
+
+		// name + url + tags bytes on top of the base deposit
+		assert_eq!(Search::deposit_of(b"alice".to_vec()), 10 + 5 + 19 + 4);
+		assert_eq!(Balances::reserved_balance(1), 38);
+		assert_eq!(Balances::reserved_balance(2), 10 + 3 + 19);
+		assert_eq!(Balances::free_balance(Search::account_id()), Balances::minimum_balance());
+	});
+}
+
+#[test]
+fn upload_searched_info_works() {
+	new_test_ext().execute_with(|| {
+		register(1, b"alice", vec![]);
+		Timestamp::set_timestamp(2_000);
+
+		let signs = vec![sign(1, b"alice", None, 1_500), sign(2, b"alice", None, 1_000)];
+		assert_ok!(Search::upload_searched_info(
+			Origin::signed(1),
+			b"alice".to_vec(),
+			signs,
+			b"root".to_vec(),
+			None,
+		));
+
+		let hash = Search::get_hash(b"alice".to_vec());
+		assert_eq!(hash.root_hash, Some(b"root".to_vec()));
+		assert_eq!(hash.update_time, 2_000);
+		assert_eq!(Search::get_ss(b"alice".to_vec()).heat, 2);
+		assert_eq!(Search::era_heat(0, b"alice".to_vec()), 2);
+
+		// The next upload has to chain from the previous root.
+		assert_ok!(Search::upload_searched_info(
+			Origin::signed(1),
+			b"alice".to_vec(),
+			vec![sign(3, b"alice", Some(b"root"), 2_000)],
+			b"root2".to_vec(),
+			Some(b"root".to_vec()),
+		));
+		assert_eq!(Search::get_ss(b"alice".to_vec()).heat, 3);
+		assert_eq!(Search::era_heat(0, b"alice".to_vec()), 3);
+	});
+}
+
+#[test]
+fn upload_searched_info_checks_provider_and_root() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(
+			Search::upload_searched_info(Origin::signed(1), b"alice".to_vec(), vec![], vec![], None),
+			Error::<Test>::NameNotExists,
+		);
+
+		register(1, b"alice", vec![]);
+		assert_noop!(
+			Search::upload_searched_info(Origin::signed(2), b"alice".to_vec(), vec![], vec![], None),
+			Error::<Test>::PermissionDenied,
+		);
+		assert_noop!(
+			Search::upload_searched_info(
+				Origin::signed(1),
+				b"alice".to_vec(),
+				vec![],
+				b"root".to_vec(),
+				Some(b"other".to_vec()),
+			),
+			Error::<Test>::RootHashIllegal,
+		);
+	});
+}
+
+#[test]
+fn upload_searched_info_checks_signatures() {
+	new_test_ext().execute_with(|| {
+		register(1, b"alice", vec![]);
+
+		assert_noop!(
+			Search::upload_searched_info(
+				Origin::signed(1),
+				b"alice".to_vec(),
+				vec![sign(1, b"alice", None, 999)],
+				b"root".to_vec(),
+				None,
+			),
+			Error::<Test>::SignatureTooEarly,
+		);
+
+		let (mut sig, msg) = sign(1, b"alice", None, 1_000);
+		sig.0[64] = 4;
+		assert_noop!(
+			Search::upload_searched_info(
+				Origin::signed(1),
+				b"alice".to_vec(),
+				vec![(sig, msg)],
+				b"root".to_vec(),
+				None,
+			),
+			Error::<Test>::SignatureIllegal,
+		);
+	});
+}
+
+#[test]
+fn queries_work() {
+	new_test_ext().execute_with(|| {
+		register(1, b"alice", vec![b"news".to_vec(), b"sport".to_vec()]);
+		register(2, b"bob", vec![b"news".to_vec()]);
+
+		assert_eq!(Search::recommend(None, 10).len(), 0);
+		assert_eq!(Search::service_by_name(b"bob".to_vec()).map(|ss| ss.provider), Some(2));
+		assert_eq!(Search::service_by_name(b"carol".to_vec()), None);
+		assert_eq!(Search::service_hash(b"alice".to_vec()).map(|sh| sh.root_hash), Some(None));
+		assert_eq!(Search::service_hash(b"carol".to_vec()), None);
+	});
+}
+
+fn names(services: Vec<SearchServiceInfo<u64>>) -> Vec<Vec<u8>> {
+	services.into_iter().map(|ss| ss.name).collect()
+}
+
+#[test]
+fn register_search_service_indexes_tags() {
+	new_test_ext().execute_with(|| {
+		register(1, b"alice", vec![b"sport".to_vec(), b"news".to_vec(), b"news".to_vec()]);
+
+		assert_eq!(Search::get_ss(b"alice".to_vec()).tags, vec![b"news".to_vec(), b"sport".to_vec()]);
+		assert!(TagIndex::contains_key(b"news".to_vec(), b"alice".to_vec()));
+		assert!(TagIndex::contains_key(b"sport".to_vec(), b"alice".to_vec()));
+		assert!(!TagIndex::contains_key(b"music".to_vec(), b"alice".to_vec()));
+	});
+}
+
+#[test]
+fn services_by_tags_matches_all_or_any() {
+	new_test_ext().execute_with(|| {
+		register(1, b"alice", vec![b"news".to_vec(), b"sport".to_vec()]);
+		register(2, b"bob", vec![b"news".to_vec()]);
+		register(2, b"carol", vec![b"sport".to_vec(), b"music".to_vec()]);
+
+		let query = |tags: &[&[u8]], matching| names(Search::services_by_tags(
+			tags.iter().map(|tag| tag.to_vec()).collect(),
+			matching,
+			0,
+			MAX_PAGE_SIZE,
+		));
+
+		assert_eq!(query(&[b"news"], TagMatch::All), vec![b"alice".to_vec(), b"bob".to_vec()]);
+		// The order of the tags does not matter.
+		assert_eq!(query(&[b"sport", b"news"], TagMatch::All), vec![b"alice".to_vec()]);
+		assert_eq!(query(&[b"news", b"sport"], TagMatch::All), vec![b"alice".to_vec()]);
+		assert_eq!(query(&[b"news", b"music"], TagMatch::All), Vec::<Vec<u8>>::new());
+		assert_eq!(
+			query(&[b"news", b"music"], TagMatch::Any),
+			vec![b"alice".to_vec(), b"bob".to_vec(), b"carol".to_vec()],
+		);
+		assert_eq!(query(&[b"movie"], TagMatch::Any), Vec::<Vec<u8>>::new());
+		assert_eq!(query(&[], TagMatch::All), Vec::<Vec<u8>>::new());
+		assert_eq!(query(&[], TagMatch::Any), Vec::<Vec<u8>>::new());
+	});
+}
+
+#[test]
+fn services_by_tags_paginates() {
+	new_test_ext().execute_with(|| {
+		for i in 0..5u8 {
+			register(1, &[b'a' + i], vec![b"news".to_vec()]);
+		}
+		let page = |start, count| names(Search::services_by_tags(
+			vec![b"news".to_vec()],
+			TagMatch::Any,
+			start,
+			count,
+		));
+
+		assert_eq!(page(0, 2), vec![b"a".to_vec(), b"b".to_vec()]);
+		assert_eq!(page(2, 2), vec![b"c".to_vec(), b"d".to_vec()]);
+		assert_eq!(page(4, 2), vec![b"e".to_vec()]);
+		assert_eq!(page(5, 2), Vec::<Vec<u8>>::new());
+		assert_eq!(page(0, u32::max_value()).len(), 5);
+	});
+}
+
+#[test]
+fn update_service_info_works() {
+	new_test_ext().execute_with(|| {
+		register(1, b"alice", vec![b"news".to_vec(), b"sport".to_vec()]);
+
+		assert_noop!(
+			Search::update_service_info(Origin::signed(2), b"alice".to_vec(), vec![], vec![]),
+			Error::<Test>::PermissionDenied,
+		);
+		assert_noop!(
+			Search::update_service_info(Origin::signed(1), b"bob".to_vec(), vec![], vec![]),
+			Error::<Test>::NameNotExists,
+		);
+		let tags = (0..=MAX_TAGS as u8).map(|i| vec![i]).collect::<Vec<_>>();
+		assert_noop!(
+			Search::update_service_info(Origin::signed(1), b"alice".to_vec(), vec![], tags),
+			Error::<Test>::TagsOverflow,
+		);
+
+		assert_ok!(Search::update_service_info(
+			Origin::signed(1),
+			b"alice".to_vec(),
+			b"https://alice.com".to_vec(),
+			vec![b"music".to_vec(), b"news".to_vec()],
+		));
+		let ss = Search::get_ss(b"alice".to_vec());
+		assert_eq!(ss.url, b"https://alice.com".to_vec());
+		assert_eq!(ss.tags, vec![b"music".to_vec(), b"news".to_vec()]);
+		assert!(TagIndex::contains_key(b"music".to_vec(), b"alice".to_vec()));
+		assert!(TagIndex::contains_key(b"news".to_vec(), b"alice".to_vec()));
+		assert!(!TagIndex::contains_key(b"sport".to_vec(), b"alice".to_vec()));
+	});
+}
+
+#[test]
+fn transfer_service_ownership_works() {
+	new_test_ext().execute_with(|| {
+		register(1, b"alice", vec![]);
+
+		assert_noop!(
+			Search::transfer_service_ownership(Origin::signed(2), b"alice".to_vec(), 2),
+			Error::<Test>::PermissionDenied,
+		);
+		assert_ok!(Search::transfer_service_ownership(Origin::signed(1), b"alice".to_vec(), 2));
+		assert_eq!(Search::get_ss(b"alice".to_vec()).provider, 2);
+		assert_eq!(Search::get_hash(b"alice".to_vec()).provider, 2);
+
+		// Only the new provider may upload now.
+		assert_noop!(
+			Search::upload_searched_info(Origin::signed(1), b"alice".to_vec(), vec![], vec![], None),
+			Error::<Test>::PermissionDenied,
+		);
+		assert_ok!(Search::upload_searched_info(
+			Origin::signed(2),
+			b"alice".to_vec(),
+			vec![],
+			b"root".to_vec(),
+			None,
+		));
+	});
+}
+
+#[test]
+fn deregister_search_service_works() {
+	new_test_ext().execute_with(|| {
+		register(1, b"alice", vec![b"news".to_vec()]);
+
+		assert_noop!(
+			Search::deregister_search_service(Origin::signed(2), b"alice".to_vec()),
+			Error::<Test>::PermissionDenied,
+		);
+		assert_ok!(Search::deregister_search_service(Origin::signed(1), b"alice".to_vec()));
+		assert!(!SearchServices::<Test>::contains_key(b"alice".to_vec()));
+		assert!(!SsHashes::<Test>::contains_key(b"alice".to_vec()));
+		assert!(!TagIndex::contains_key(b"news".to_vec(), b"alice".to_vec()));
+		assert_noop!(
+			Search::deregister_search_service(Origin::signed(1), b"alice".to_vec()),
+			Error::<Test>::NameNotExists,
+		);
+
+		// The name is free again.
+		register(2, b"alice", vec![]);
+	});
+}
+
+#[test]
+fn deposits_are_reserved_and_returned() {
+	new_test_ext().execute_with(|| {
+		// 10 base + 5 name bytes + 19 url bytes + 4 tag bytes.
+		register(1, b"alice", vec![b"news".to_vec()]);
+		assert_eq!(Search::deposit_of(b"alice".to_vec()), 38);
+		assert_eq!(Balances::reserved_balance(1), 38);
+
+		// A bigger url and more tags take more deposit, a smaller one gives some back.
+		assert_ok!(Search::update_service_info(
+			Origin::signed(1),
+			b"alice".to_vec(),
+			b"https://example.com/search".to_vec(),
+			vec![b"news".to_vec(), b"sport".to_vec()],
+		));
+		assert_eq!(Balances::reserved_balance(1), 50);
+		assert_ok!(Search::update_service_info(Origin::signed(1), b"alice".to_vec(), vec![], vec![]));
+		assert_eq!(Balances::reserved_balance(1), 15);
+		assert_eq!(Search::deposit_of(b"alice".to_vec()), 15);
+
+		// The new provider takes over the deposit.
+		assert_ok!(Search::transfer_service_ownership(Origin::signed(1), b"alice".to_vec(), 2));
+		assert_eq!(Balances::reserved_balance(1), 0);
+		assert_eq!(Balances::reserved_balance(2), 15);
+
+		assert_ok!(Search::deregister_search_service(Origin::signed(2), b"alice".to_vec()));
+		assert_eq!(Balances::reserved_balance(2), 0);
+		assert_eq!(Balances::free_balance(2), 1_000);
+		assert_eq!(Search::deposit_of(b"alice".to_vec()), 0);
+	});
+}
+
+#[test]
+fn registration_requires_deposit() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(
+			Search::register_search_service(Origin::signed(3), b"alice".to_vec(), vec![], vec![]),
+			pallet_balances::Error::<Test, _>::InsufficientBalance,
+		);
+
+		register(1, b"alice", vec![]);
+		assert_noop!(
+			Search::transfer_service_ownership(Origin::signed(1), b"alice".to_vec(), 3),
+			pallet_balances::Error::<Test, _>::InsufficientBalance,
+		);
+	});
+}
+
+#[test]
+fn force_remove_search_service_works() {
+	new_test_ext().execute_with(|| {
+		register(2, b"bob", vec![b"news".to_vec()]);
+		let deposit = Search::deposit_of(b"bob".to_vec());
+
+		assert_noop!(Search::force_remove_search_service(Origin::signed(2), b"bob".to_vec()), BadOrigin);
+		assert_noop!(
+			Search::force_remove_search_service(Origin::signed(1), b"carol".to_vec()),
+			Error::<Test>::NameNotExists,
+		);
+
+		assert_ok!(Search::force_remove_search_service(Origin::signed(1), b"bob".to_vec()));
+		assert!(!SearchServices::<Test>::contains_key(b"bob".to_vec()));
+		assert!(!SsHashes::<Test>::contains_key(b"bob".to_vec()));
+		assert!(!TagIndex::contains_key(b"news".to_vec(), b"bob".to_vec()));
+		assert_eq!(Balances::reserved_balance(2), 0);
+		assert_eq!(Balances::total_balance(&2), 1_000 - deposit);
+	});
+}
+
+#[test]
+fn upload_searched_info_binds_messages() {
+	new_test_ext().execute_with(|| {
+		register(1, b"alice", vec![]);
+		register(1, b"bob", vec![]);
+		let upload = |signs, root: &[u8], last_root: Option<&[u8]>| Search::upload_searched_info(
+			Origin::signed(1),
+			b"alice".to_vec(),
+			signs,
+			root.to_vec(),
+			last_root.map(|r| r.to_vec()),
+		);
+
+		// Messages must commit to the right service and root hash and must not be from the future.
+		assert_noop!(upload(vec![sign(1, b"bob", None, 1_000)], b"root", None), Error::<Test>::MessageMismatch);
+		assert_noop!(
+			upload(vec![sign(1, b"alice", Some(b"root"), 1_000)], b"root", None),
+			Error::<Test>::MessageMismatch,
+		);
+		assert_noop!(upload(vec![sign(1, b"alice", None, 1_001)], b"root", None), Error::<Test>::SignatureTooLate);
+
+		// The same message can not be counted twice in a batch...
+		assert_noop!(
+			upload(vec![sign(1, b"alice", None, 1_000), sign(1, b"alice", None, 1_000)], b"root", None),
+			Error::<Test>::MessageUsed,
+		);
+
+		// ...but a signer with several messages counts once.
+		Timestamp::set_timestamp(2_000);
+		assert_ok!(upload(
+			vec![sign(1, b"alice", None, 1_000), sign(1, b"alice", None, 1_500), sign(2, b"alice", None, 2_000)],
+			b"root",
+			None,
+		));
+		assert_eq!(Search::get_ss(b"alice".to_vec()).heat, 2);
+	});
+}
+
+#[test]
+fn upload_searched_info_rejects_replayed_messages() {
+	new_test_ext().execute_with(|| {
+		register(1, b"alice", vec![]);
+		let msg = Msg::new(b"alice", &None, 1_000);
+		assert_ok!(Search::upload_searched_info(
+			Origin::signed(1),
+			b"alice".to_vec(),
+			vec![sign_msg(1, msg.clone())],
+			b"root".to_vec(),
+			None,
+		));
+		assert_ok!(Search::upload_searched_info(
+			Origin::signed(1),
+			b"alice".to_vec(),
+			vec![],
+			b"root2".to_vec(),
+			Some(b"root".to_vec()),
+		));
+
+		// Even if the provider loops back to a previous root hash, a counted message is rejected.
+		Search::deregister_search_service(Origin::signed(1), b"alice".to_vec()).unwrap();
+		register(1, b"alice", vec![]);
+		Timestamp::set_timestamp(1_000);
+		assert_noop!(
+			Search::upload_searched_info(
+				Origin::signed(1),
+				b"alice".to_vec(),
+				vec![sign_msg(1, msg)],
+				b"root".to_vec(),
+				None,
+			),
+			Error::<Test>::MessageUsed,
+		);
+	});
+}
+
+#[test]
+fn upload_searched_info_limits_signatures() {
+	new_test_ext().execute_with(|| {
+		register(1, b"alice", vec![]);
+		assert_noop!(
+			Search::upload_searched_info(
+				Origin::signed(1),
+				b"alice".to_vec(),
+				vec![(Sig([0; 65]), Msg([0; 32])); MAX_SIGNS + 1],
+				b"root".to_vec(),
+				None,
+			),
+			Error::<Test>::TooManySignatures,
+		);
+	});
+}
+
+#[test]
+fn used_messages_are_kept_for_two_eras() {
+	new_test_ext().execute_with(|| {
+		let (sig, msg) = upload_records(&[b"a".to_vec()]);
+		let key = Search::message_key(&Search::recover_signer(&sig, &msg).unwrap(), &msg);
+		assert!(UsedMessages::contains_key(0, key));
+
+		// The message can still be challenged during the next era...
+		<Search as OnInitialize<u64>>::on_initialize(10);
+		assert_eq!(Search::current_era(), 1);
+		assert_ok!(Search::challenge(
+			Origin::signed(2),
+			b"alice".to_vec(),
+			0,
+			Evidence::BadSignature(sig.clone(), msg.clone()),
+		));
+
+		// ...but is forgotten after that.
+		<Search as OnInitialize<u64>>::on_initialize(20);
+		assert!(!UsedMessages::contains_key(0, key));
+		assert_noop!(
+			Search::challenge(Origin::signed(2), b"alice".to_vec(), 0, Evidence::BadSignature(sig, msg)),
+			Error::<Test>::MessageNotCounted,
+		);
+	});
+}
+
+#[test]
+fn stale_eras_are_pruned_over_blocks() {
+	new_test_ext().execute_with(|| {
+		for i in 0..MAX_PRUNED_KEYS as u32 {
+			UsedMessages::insert(0, blake2_256(&i.encode()), ());
+		}
+		EraHeat::insert(0, b"alice".to_vec(), 1);
+		<Search as OnInitialize<u64>>::on_initialize(10);
+		<Search as OnInitialize<u64>>::on_initialize(20);
+
+		// At most `MAX_PRUNED_KEYS` keys are removed in a block...
+		assert_eq!(UsedMessages::iter(0).count() + EraHeat::iter(0).count(), 1);
+		assert_eq!(Search::pruned_eras(), 0);
+
+		// ...and the rest in the next ones.
+		<Search as OnInitialize<u64>>::on_initialize(21);
+		assert_eq!(UsedMessages::iter(0).count() + EraHeat::iter(0).count(), 0);
+		assert_eq!(Search::pruned_eras(), 1);
+	});
+}
+
+/// Upload, as `who`, the signatures of the signers derived from `seeds` for the search service
+/// `name`, which must have no root hash yet.
+fn upload_heat(who: u64, name: &[u8], seeds: &[u8]) {
+	let ts = Search::get_hash(name.to_vec()).update_time;
+	assert_ok!(Search::upload_searched_info(
+		Origin::signed(who),
+		name.to_vec(),
+		seeds.iter().map(|seed| sign(*seed, name, None, ts)).collect(),
+		b"root".to_vec(),
+		None,
+	));
+}
+
+/// Claim, as `who`, the reward of the search service `name`.
+fn claim(who: u64, name: &[u8]) -> DispatchResult {
+	Search::claim_reward(Origin::signed(who), name.to_vec())
+}
+
+#[test]
+fn rewards_are_claimed_per_heat_after_the_era() {
+	new_test_ext().execute_with(|| {
+		Balances::make_free_balance_be(&Search::account_id(), 1_001);
+		register(1, b"alice", vec![]);
+		register(2, b"bob", vec![]);
+		upload_heat(1, b"alice", &[1, 2, 3]);
+		upload_heat(2, b"bob", &[4]);
+		let (alice, bob) = (Balances::free_balance(1), Balances::free_balance(2));
+
+		// Nothing can be claimed before the era ends.
+		<Search as OnInitialize<u64>>::on_initialize(9);
+		assert_noop!(claim(1, b"alice"), Error::<Test>::NothingToClaim);
+
+		<Search as OnInitialize<u64>>::on_initialize(10);
+		assert_eq!(Search::era_rewards(0), Some((40, 4)));
+		assert_noop!(claim(2, b"alice"), Error::<Test>::PermissionDenied);
+		assert_ok!(claim(1, b"alice"));
+		assert_ok!(claim(2, b"bob"));
+		assert_eq!(Balances::free_balance(1), alice + 30);
+		assert_eq!(Balances::free_balance(2), bob + 10);
+		assert_eq!(Balances::free_balance(Search::account_id()), 1_001 - 40);
+		assert_eq!(Search::era_heat(0, b"alice".to_vec()), 0);
+
+		// The heat is paid only once.
+		assert_noop!(claim(1, b"alice"), Error::<Test>::NothingToClaim);
+	});
+}
+
+#[test]
+fn rewards_share_the_era_budget_by_heat() {
+	new_test_ext().execute_with(|| {
+		Balances::make_free_balance_be(&Search::account_id(), 1_001);
+		register(1, b"alice", vec![]);
+		register(2, b"bob", vec![]);
+		upload_heat(1, b"alice", &(1..=15).collect::<Vec<_>>());
+		upload_heat(2, b"bob", &(16..=20).collect::<Vec<_>>());
+		let (alice, bob) = (Balances::free_balance(1), Balances::free_balance(2));
+
+		// 200 is owed, but only `EraBudget` is set aside, three quarters of it for alice.
+		<Search as OnInitialize<u64>>::on_initialize(10);
+		assert_ok!(claim(1, b"alice"));
+		assert_ok!(claim(2, b"bob"));
+		assert_eq!(Balances::free_balance(1), alice + 75);
+		assert_eq!(Balances::free_balance(2), bob + 25);
+		assert_eq!(Balances::free_balance(Search::account_id()), 1_001 - 100);
+	});
+}
+
+#[test]
+fn rewards_never_exceed_the_pot() {
+	new_test_ext().execute_with(|| {
+		Balances::make_free_balance_be(&Search::account_id(), 21);
+		register(1, b"alice", vec![]);
+		upload_heat(1, b"alice", &[1, 2, 3]);
+		let alice = Balances::free_balance(1);
+
+		<Search as OnInitialize<u64>>::on_initialize(10);
+		assert_ok!(claim(1, b"alice"));
+		assert_eq!(Balances::free_balance(1), alice + 20);
+		// The existential deposit stays in the pot.
+		assert_eq!(Balances::free_balance(Search::account_id()), 1);
+	});
+}
+
+#[test]
+fn removed_services_forfeit_their_heat() {
+	new_test_ext().execute_with(|| {
+		Balances::make_free_balance_be(&Search::account_id(), 1_001);
+		register(1, b"alice", vec![]);
+		register(2, b"bob", vec![]);
+		upload_heat(1, b"alice", &[1, 2, 3]);
+		upload_heat(2, b"bob", &[4]);
+		assert_ok!(Search::deregister_search_service(Origin::signed(1), b"alice".to_vec()));
+
+		// Only the heat of bob is rewarded.
+		<Search as OnInitialize<u64>>::on_initialize(10);
+		assert_eq!(Search::era_rewards(0), Some((10, 1)));
+		register(1, b"alice", vec![]);
+		assert_noop!(claim(1, b"alice"), Error::<Test>::NothingToClaim);
+	});
+}
+
+#[test]
+fn unclaimed_rewards_are_forfeited_after_the_next_era() {
+	new_test_ext().execute_with(|| {
+		Balances::make_free_balance_be(&Search::account_id(), 1_001);
+		register(1, b"alice", vec![]);
+		upload_heat(1, b"alice", &[1, 2, 3]);
+
+		<Search as OnInitialize<u64>>::on_initialize(10);
+		<Search as OnInitialize<u64>>::on_initialize(20);
+		assert_eq!(Search::era_rewards(0), None);
+		assert_noop!(claim(1, b"alice"), Error::<Test>::NothingToClaim);
+		assert_eq!(Balances::free_balance(Search::account_id()), 1_001);
+	});
+}
+
+#[test]
+fn heat_accumulates_and_decays() {
+	new_test_ext().execute_with(|| {
+		register(1, b"alice", vec![]);
+		upload_heat(1, b"alice", &(1..=8).collect::<Vec<_>>());
+		let heat = || Search::service_by_name(b"alice".to_vec()).unwrap().heat;
+		assert_eq!(heat(), 8);
+
+		// Heat decays every `HeatDecayPeriod` blocks, whenever it is read...
+		System::set_block_number(4);
+		assert_eq!(heat(), 8);
+		System::set_block_number(5);
+		assert_eq!(heat(), 4);
+		System::set_block_number(15);
+		assert_eq!(heat(), 1);
+		assert_eq!(names(Search::recommend(None, 10)), vec![b"alice".to_vec()]);
+
+		// ...or added to.
+		assert_ok!(Search::upload_searched_info(
+			Origin::signed(1),
+			b"alice".to_vec(),
+			vec![sign(9, b"alice", Some(b"root"), 1_000)],
+			b"root2".to_vec(),
+			Some(b"root".to_vec()),
+		));
+		assert_eq!(Search::get_ss(b"alice".to_vec()).heat, 2);
+		assert_eq!(Search::ranking(), vec![(2, b"alice".to_vec())]);
+
+		// Heat decays away entirely, which leaves nothing to recommend.
+		System::set_block_number(25);
+		assert_eq!(heat(), 0);
+		assert_eq!(names(Search::recommend(None, 10)), Vec::<Vec<u8>>::new());
+	});
+}
+
+#[test]
+fn ranking_keeps_the_hottest_services() {
+	new_test_ext().execute_with(|| {
+		register(1, b"alice", vec![]);
+		register(1, b"bob", vec![]);
+		register(1, b"carol", vec![]);
+		upload_heat(1, b"alice", &[1, 2]);
+		upload_heat(1, b"bob", &[1, 2, 3, 4]);
+		assert_eq!(Search::ranking(), vec![(4, b"bob".to_vec()), (2, b"alice".to_vec())]);
+
+		// Only `RankingSize` services are kept.
+		upload_heat(1, b"carol", &[1, 2, 3, 4, 5, 6]);
+		assert_eq!(Search::ranking(), vec![(6, b"carol".to_vec()), (4, b"bob".to_vec())]);
+
+		// A removed service leaves the ranking, and the next upload brings the ranking up to date.
+		assert_ok!(Search::deregister_search_service(Origin::signed(1), b"carol".to_vec()));
+		assert_eq!(Search::ranking(), vec![(4, b"bob".to_vec())]);
+		System::set_block_number(5);
+		assert_ok!(Search::upload_searched_info(
+			Origin::signed(1),
+			b"alice".to_vec(),
+			vec![sign(3, b"alice", Some(b"root"), 1_000)],
+			b"root2".to_vec(),
+			Some(b"root".to_vec()),
+		));
+		assert_eq!(Search::ranking(), vec![(2, b"alice".to_vec()), (2, b"bob".to_vec())]);
+	});
+}
+
+#[test]
+fn recommend_ranks_by_heat() {
+	new_test_ext().execute_with(|| {
+		register(1, b"alice", vec![b"news".to_vec()]);
+		register(1, b"bob", vec![b"news".to_vec(), b"sport".to_vec()]);
+		register(1, b"carol", vec![b"news".to_vec()]);
+		upload_heat(1, b"alice", &[1]);
+		upload_heat(1, b"bob", &[1, 2, 3]);
+		upload_heat(1, b"carol", &[1, 2]);
+
+		assert_eq!(names(Search::recommend(None, 10)), vec![b"bob".to_vec(), b"carol".to_vec()]);
+		assert_eq!(names(Search::recommend(None, 1)), vec![b"bob".to_vec()]);
+
+		// Filtering by tag ranks every service carrying it, not only the ranked ones.
+		assert_eq!(
+			names(Search::recommend(Some(b"news".to_vec()), 10)),
+			vec![b"bob".to_vec(), b"carol".to_vec(), b"alice".to_vec()],
+		);
+		assert_eq!(names(Search::recommend(Some(b"sport".to_vec()), 10)), vec![b"bob".to_vec()]);
+		assert_eq!(names(Search::recommend(Some(b"music".to_vec()), 10)), Vec::<Vec<u8>>::new());
+	});
+}
+
+#[test]
+fn merkle_proofs_work() {
+	let records = (0u8..7).map(|i| vec![i; 3]).collect::<Vec<_>>();
+	let root = merkle::root(&records);
+	assert_eq!(
+		root,
+		merkle::node_hash(
+			&merkle::node_hash(
+				&merkle::node_hash(&merkle::leaf_hash(&records[0]), &merkle::leaf_hash(&records[1])),
+				&merkle::node_hash(&merkle::leaf_hash(&records[2]), &merkle::leaf_hash(&records[3])),
+			),
+			&merkle::node_hash(
+				&merkle::node_hash(&merkle::leaf_hash(&records[4]), &merkle::leaf_hash(&records[5])),
+				&merkle::leaf_hash(&records[6]),
+			),
+		),
+	);
+	assert_eq!(merkle::root(&records[..1]), merkle::leaf_hash(&records[0]));
+
+	for (i, record) in records.iter().enumerate() {
+		let proof = MerkleProof::generate(&records, i as u32).unwrap();
+		assert!(proof.verify(record, &root));
+		// Neither a different record nor a different position is accepted.
+		assert!(!proof.verify(b"forged", &root));
+		assert!(!MerkleProof { index: (i as u32 + 1) % 7, ..proof.clone() }.verify(record, &root));
+		// Nor a proof with a sibling too many or too few.
+		let mut longer = proof.clone();
+		longer.siblings.push(root);
+		assert!(!longer.verify(record, &root));
+		let mut shorter = proof;
+		shorter.siblings.pop();
+		assert!(!shorter.verify(record, &root));
+	}
+	assert_eq!(MerkleProof::generate(&records, 7), None);
+
+	// An inner node can not pass for a leaf.
+	let root = merkle::root(&records[..4]);
+	let inner = merkle::node_hash(&merkle::leaf_hash(&records[0]), &merkle::leaf_hash(&records[1]));
+	let sibling = merkle::node_hash(&merkle::leaf_hash(&records[2]), &merkle::leaf_hash(&records[3]));
+	let proof = MerkleProof { index: 0, leaves: 2, siblings: vec![sibling] };
+	assert!(!proof.verify(&inner, &root));
+}
+
+#[test]
+fn verify_searched_item_checks_current_and_past_roots() {
+	new_test_ext().execute_with(|| {
+		register(1, b"alice", vec![]);
+		let batches = (0u8..3).map(|i| vec![vec![i], vec![i, i]]).collect::<Vec<_>>();
+		let mut last_root = None;
+		for batch in &batches {
+			let root = merkle::root(batch).to_vec();
+			assert_ok!(Search::upload_searched_info(
+				Origin::signed(1),
+				b"alice".to_vec(),
+				vec![],
+				root.clone(),
+				last_root,
+			));
+			last_root = Some(root);
+		}
+		assert_eq!(Search::uploads(b"alice".to_vec()), 3);
+		let verify = |upload, batch: usize| Search::verify_searched_item(
+			b"alice".to_vec(),
+			upload,
+			batches[batch][1].clone(),
+			MerkleProof::generate(&batches[batch], 1).unwrap(),
+		);
+
+		// The current root hash...
+		assert!(verify(None, 2));
+		assert!(!verify(None, 1));
+		// ...and the latest `RootHistoryDepth` ones are known.
+		assert!(verify(Some(2), 2));
+		assert!(verify(Some(1), 1));
+		assert!(!verify(Some(1), 2));
+		assert!(!verify(Some(0), 0));
+		assert_eq!(Search::root_history(b"alice".to_vec(), 0), None);
+
+		// Nothing is known about removed services.
+		assert_ok!(Search::deregister_search_service(Origin::signed(1), b"alice".to_vec()));
+		assert!(!verify(None, 2));
+		assert!(!verify(Some(2), 2));
+		assert_eq!(Search::uploads(b"alice".to_vec()), 0);
+	});
+}
+
+/// Register `alice` for 1 and upload the root of `records` for it, counting the message of the
+/// signer derived from 3.
+fn upload_records(records: &[Vec<u8>]) -> (Sig, Msg) {
+	register(1, b"alice", vec![]);
+	let signed = sign(3, b"alice", None, 1_000);
+	assert_ok!(Search::upload_searched_info(
+		Origin::signed(1),
+		b"alice".to_vec(),
+		vec![signed.clone()],
+		merkle::root(records).to_vec(),
+		None,
+	));
+	signed
+}
+
+#[test]
+fn challenge_works() {
+	new_test_ext().execute_with(|| {
+		let (sig, msg) = upload_records(&[b"a".to_vec(), b"b".to_vec()]);
+		let challenge = |who, name: &[u8], upload, evidence| Search::challenge(
+			Origin::signed(who),
+			name.to_vec(),
+			upload,
+			evidence,
+		);
+		let missing = Evidence::MissingLeaf(b"c".to_vec());
+
+		assert_noop!(challenge(2, b"bob", 0, missing.clone()), Error::<Test>::NameNotExists);
+		assert_noop!(challenge(2, b"alice", 1, missing.clone()), Error::<Test>::UploadNotExists);
+		let (other_sig, other_msg) = sign(4, b"alice", None, 1_000);
+		assert_noop!(
+			challenge(2, b"alice", 0, Evidence::BadSignature(other_sig, other_msg)),
+			Error::<Test>::MessageNotCounted,
+		);
+		assert_noop!(
+			challenge(4, b"alice", 0, missing.clone()),
+			pallet_balances::Error::<Test, _>::InsufficientBalance,
+		);
+
+		// A counted message can only be held against the upload that counted it.
+		assert_ok!(Search::upload_searched_info(
+			Origin::signed(1),
+			b"alice".to_vec(),
+			vec![],
+			b"root2".to_vec(),
+			Search::root_at(b"alice", None),
+		));
+		assert_noop!(
+			challenge(2, b"alice", 1, Evidence::BadSignature(sig.clone(), msg.clone())),
+			Error::<Test>::MessageMismatch,
+		);
+
+		assert_ok!(challenge(2, b"alice", 0, missing));
+		assert_ok!(challenge(2, b"alice", 0, Evidence::BadSignature(sig, msg)));
+		assert_eq!(Balances::reserved_balance(2), 10);
+		assert_eq!(Search::challenge_count(), 2);
+		assert_eq!(Search::open_challenges(b"alice".to_vec()), 2);
+		assert_eq!(Search::challenges(0).map(|c| c.deadline), Some(4));
+
+		// The service is frozen while under challenge.
+		assert_noop!(
+			Search::deregister_search_service(Origin::signed(1), b"alice".to_vec()),
+			Error::<Test>::UnderChallenge,
+		);
+		assert_noop!(
+			Search::transfer_service_ownership(Origin::signed(1), b"alice".to_vec(), 2),
+			Error::<Test>::UnderChallenge,
+		);
+		assert_noop!(
+			Search::update_service_info(Origin::signed(1), b"alice".to_vec(), vec![], vec![]),
+			Error::<Test>::UnderChallenge,
+		);
+		// But its reward can still be claimed.
+		assert_noop!(claim(1, b"alice"), Error::<Test>::NothingToClaim);
+
+		ChallengeCount::put(ChallengeIndex::max_value());
+		assert_noop!(
+			challenge(2, b"alice", 0, Evidence::MissingLeaf(b"d".to_vec())),
+			Error::<Test>::TooManyChallenges,
+		);
+	});
+}
+
+#[test]
+fn unanswered_missing_leaf_challenge_is_dismissed() {
+	new_test_ext().execute_with(|| {
+		upload_records(&[b"a".to_vec(), b"b".to_vec()]);
+		System::set_block_number(1);
+		assert_ok!(Search::challenge(
+			Origin::signed(2),
+			b"alice".to_vec(),
+			0,
+			Evidence::MissingLeaf(b"c".to_vec()),
+		));
+
+		<Search as OnInitialize<u64>>::on_initialize(3);
+		assert_eq!(Search::open_challenges(b"alice".to_vec()), 1);
+
+		// Nothing ties the record to the provider, so the service is kept.
+		<Search as OnInitialize<u64>>::on_initialize(4);
+		assert!(SearchServices::<Test>::contains_key(b"alice".to_vec()));
+		assert_eq!(Search::challenges(0), None);
+		assert_eq!(Search::open_challenges(b"alice".to_vec()), 0);
+		// `DismissalSlash` of the bond is kept.
+		assert_eq!(Balances::free_balance(2), 999);
+		assert_eq!(Balances::reserved_balance(2), 0);
+	});
+}
+
+#[test]
+fn answer_challenge_works() {
+	new_test_ext().execute_with(|| {
+		let records = vec![b"a".to_vec(), b"b".to_vec(), b"c".to_vec()];
+		let (sig, msg) = upload_records(&records);
+		assert_ok!(Search::challenge(
+			Origin::signed(2),
+			b"alice".to_vec(),
+			0,
+			Evidence::MissingLeaf(b"c".to_vec()),
+		));
+		assert_ok!(Search::challenge(
+			Origin::signed(2),
+			b"alice".to_vec(),
+			0,
+			Evidence::BadSignature(sig, msg),
+		));
+		let proof = MerkleProof::generate(&records, 2).unwrap();
+
+		assert_noop!(
+			Search::answer_challenge(Origin::signed(2), 0, proof.clone()),
+			Error::<Test>::PermissionDenied,
+		);
+		assert_noop!(
+			Search::answer_challenge(Origin::signed(1), 0, MerkleProof::generate(&records, 1).unwrap()),
+			Error::<Test>::ProofInvalid,
+		);
+		assert_noop!(
+			Search::answer_challenge(Origin::signed(1), 1, proof.clone()),
+			Error::<Test>::ChallengeNotAnswerable,
+		);
+		assert_noop!(
+			Search::answer_challenge(Origin::signed(1), 2, proof.clone()),
+			Error::<Test>::ChallengeNotExists,
+		);
+
+		assert_ok!(Search::answer_challenge(Origin::signed(1), 0, proof));
+		assert_eq!(Search::challenges(0), None);
+		assert_eq!(Search::open_challenges(b"alice".to_vec()), 1);
+		assert_eq!(Balances::reserved_balance(2), 5);
+		assert_eq!(Balances::total_balance(&2), 995);
+
+		// An unresolved `BadSignature` challenge is dismissed at its deadline.
+		<Search as OnInitialize<u64>>::on_initialize(4);
+		assert!(SearchServices::<Test>::contains_key(b"alice".to_vec()));
+		assert_eq!(Search::open_challenges(b"alice".to_vec()), 0);
+		assert_eq!(Balances::free_balance(2), 994);
+	});
+}
+
+#[test]
+fn resolve_challenge_works() {
+	new_test_ext().execute_with(|| {
+		let (sig, msg) = upload_records(&[b"a".to_vec()]);
+		let deposit = Search::deposit_of(b"alice".to_vec());
+		for _ in 0..2 {
+			assert_ok!(Search::challenge(
+				Origin::signed(2),
+				b"alice".to_vec(),
+				0,
+				Evidence::BadSignature(sig.clone(), msg.clone()),
+			));
+		}
+
+		assert_noop!(Search::resolve_challenge(Origin::signed(2), 0, true), BadOrigin);
+		assert_noop!(Search::resolve_challenge(Origin::signed(1), 2, true), Error::<Test>::ChallengeNotExists);
+
+		// A rejected challenge costs the bond...
+		assert_ok!(Search::resolve_challenge(Origin::signed(1), 0, false));
+		assert_eq!(Balances::total_balance(&2), 995);
+
+		// ...and an upheld one wins the deposit.
+		assert_ok!(Search::resolve_challenge(Origin::ROOT, 1, true));
+		assert!(!SearchServices::<Test>::contains_key(b"alice".to_vec()));
+		assert_eq!(Balances::free_balance(2), 995 + deposit);
+		assert_eq!(Balances::reserved_balance(2), 0);
+		assert_eq!(Search::open_challenges(b"alice".to_vec()), 0);
+	});
+}
+
+#[test]
+fn offchain_worker_submits_pending_uploads() {
+	const PHRASE: &str = "news slush supreme milk chapter athlete soap sausage put clutch what kitten";
+
+	let (offchain, _) = testing::TestOffchainExt::new();
+	let (pool, pool_state) = testing::TestTransactionPoolExt::new();
+	let keystore = KeyStore::new();
+	let public = keystore.write().sr25519_generate_new(
+		crypto::Public::ID,
+		Some(&format!("{}/hunter1", PHRASE))
+	).unwrap();
+
+	let mut t = new_test_ext();
+	t.register_extension(OffchainExt::new(offchain));
+	t.register_extension(TransactionPoolExt::new(pool));
+	t.register_extension(KeystoreExt(keystore));
+
+	t.execute_with(|| {
+		let provider = TestPublic(public).into_account();
+		Balances::make_free_balance_be(&provider, 1_000);
+		register(provider, b"alice", vec![]);
+		// No local key provides this one.
+		register(1, b"bob", vec![]);
+
+		let records = vec![b"a".to_vec(), b"b".to_vec()];
+		let counted = sign(1, b"alice", None, 1_000);
+		let pending = offchain::PendingUpload {
+			signs: vec![
+				counted.clone(),
+				// Duplicated, committing to another root hash or from the future: not countable.
+				counted.clone(),
+				sign(2, b"alice", Some(b"root"), 1_000),
+				sign(3, b"alice", None, 1_001),
+			],
+			records: records.clone(),
+		};
+		let queue = |name: &[u8]| StorageValueRef::persistent(&offchain::pending_key(name)).set(&pending);
+		queue(b"alice");
+		queue(b"bob");
+		StorageValueRef::persistent(offchain::PENDING_NAMES_KEY).set(&vec![b"alice".to_vec(), b"bob".to_vec()]);
+
+		<Search as OffchainWorker<u64>>::offchain_worker(1);
+		let tx = pool_state.write().transactions.pop().unwrap();
+		assert!(pool_state.read().transactions.is_empty());
+		let tx = Extrinsic::decode(&mut &*tx).unwrap();
+		assert_eq!(tx.signature.unwrap().0, 0);
+		assert_eq!(
+			tx.call,
+			Call::upload_searched_info(b"alice".to_vec(), vec![counted], merkle::root(&records).to_vec(), None),
+		);
+		assert_eq!(
+			StorageValueRef::persistent(&offchain::pending_key(b"alice")).get(),
+			Some(Some(offchain::PendingUpload::default())),
+		);
+		// Nothing is uploaded for bob, so it is no longer queued.
+		assert_eq!(
+			StorageValueRef::persistent(offchain::PENDING_NAMES_KEY).get(),
+			Some(Some(vec![b"alice".to_vec()])),
+		);
+		assert_eq!(
+			StorageValueRef::persistent(&offchain::pending_key(b"bob")).get(),
+			Some(Some(offchain::PendingUpload::default())),
+		);
+
+		// The next upload waits for `UploadInterval` blocks.
+		queue(b"alice");
+		<Search as OffchainWorker<u64>>::offchain_worker(5);
+		assert!(pool_state.read().transactions.is_empty());
+		<Search as OffchainWorker<u64>>::offchain_worker(6);
+		assert_eq!(pool_state.read().transactions.len(), 1);
+	});
+}
+
+#[test]
+fn offchain_worker_keeps_uploads_until_recorded() {
+	const PHRASE: &str = "news slush supreme milk chapter athlete soap sausage put clutch what kitten";
+
+	let (offchain, _) = testing::TestOffchainExt::new();
+	let (pool, pool_state) = testing::TestTransactionPoolExt::new();
+	let keystore = KeyStore::new();
+	let public = keystore.write().sr25519_generate_new(
+		crypto::Public::ID,
+		Some(&format!("{}/hunter1", PHRASE))
+	).unwrap();
+
+	let mut t = new_test_ext();
+	t.register_extension(OffchainExt::new(offchain));
+	t.register_extension(TransactionPoolExt::new(pool));
+	t.register_extension(KeystoreExt(keystore));
+
+	t.execute_with(|| {
+		let provider = TestPublic(public).into_account();
+		Balances::make_free_balance_be(&provider, 1_000);
+		register(provider, b"alice", vec![]);
+
+		let records = vec![b"a".to_vec(), b"b".to_vec()];
+		let pending = offchain::PendingUpload { signs: vec![], records: records.clone() };
+		StorageValueRef::persistent(&offchain::pending_key(b"alice")).set(&pending);
+		StorageValueRef::persistent(offchain::PENDING_NAMES_KEY).set(&vec![b"alice".to_vec()]);
+		let submitted = || StorageValueRef::persistent(&offchain::submitted_key(b"alice"))
+			.get::<Option<(u32, offchain::PendingUpload)>>();
+		let upload = Call::upload_searched_info(
+			b"alice".to_vec(),
+			vec![],
+			merkle::root(&records).to_vec(),
+			None,
+		);
+
+		<Search as OffchainWorker<u64>>::offchain_worker(1);
+		assert_eq!(pool_state.write().transactions.drain(..).count(), 1);
+		assert_eq!(submitted(), Some(Some(Some((0, pending.clone())))));
+
+		// The upload is not recorded by the time the next one may be submitted, so it is submitted
+		// again.
+		<Search as OffchainWorker<u64>>::offchain_worker(6);
+		let tx = pool_state.write().transactions.pop().unwrap();
+		assert_eq!(Extrinsic::decode(&mut &*tx).unwrap().call, upload);
+		assert_eq!(submitted(), Some(Some(Some((0, pending)))));
+
+		// Once it is recorded, it is forgotten.
+		assert_ok!(Search::upload_searched_info(
+			Origin::signed(provider),
+			b"alice".to_vec(),
+			vec![],
+			merkle::root(&records).to_vec(),
+			None,
+		));
+		<Search as OffchainWorker<u64>>::offchain_worker(11);
+		assert!(pool_state.read().transactions.is_empty());
+		assert_eq!(submitted(), Some(Some(None)));
+	});
+}
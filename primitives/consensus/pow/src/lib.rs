@@ -61,4 +61,11 @@ sp_api::decl_runtime_apis! {
 		/// Return the target difficulty of the next block.
 		fn difficulty() -> Difficulty;
 	}
+
+	/// API for those chains that adjust their difficulty towards a target block time, so that
+	/// nodes and miners know how often blocks are expected.
+	pub trait TargetBlockTimeApi<Moment: Decode> {
+		/// Return the time a block should take to mine.
+		fn target_block_time() -> Moment;
+	}
 }